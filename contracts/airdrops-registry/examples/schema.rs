@@ -3,6 +3,7 @@ use std::fs::create_dir_all;
 
 use airdrops_registry::msg::*;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
+use stader_utils::ownership::OwnershipResponse;
 
 use airdrops_registry::state::*;
 
@@ -19,4 +20,5 @@ fn main() {
     export_schema(&schema_for!(GetConfigResponse), &out_dir);
     export_schema(&schema_for!(GetAirdropContractsResponse), &out_dir);
     export_schema(&schema_for!(TmpManagerStoreResponse), &out_dir);
+    export_schema(&schema_for!(OwnershipResponse), &out_dir);
 }
@@ -1,16 +1,20 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use cosmwasm_std::{
+    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128,
+};
 
 use crate::error::ContractError;
 use crate::msg::{
     ExecuteMsg, GetAirdropContractsResponse, GetConfigResponse, InstantiateMsg, MigrateMsg,
     QueryMsg, TmpManagerStoreResponse,
 };
-use crate::state::{
-    AirdropRegistryInfo, Config, TmpManagerStore, AIRDROP_REGISTRY, CONFIG, TMP_MANAGER_STORE,
-};
+use crate::state::{AirdropRegistryInfo, Config, AIRDROP_REGISTRY, CONFIG, TMP_MANAGER_STORE};
 use cw2::set_contract_version;
+use stader_utils::events::lunax_event;
+use stader_utils::migration::start_migration;
+use stader_utils::ownership::{self, OwnershipResponse};
+use stader_utils::sweep::{self, DenomOrCw20, SweepHistoryResponse};
 
 const CONTRACT_NAME: &str = "airdrops-registry";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -32,8 +36,14 @@ pub fn instantiate(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    Ok(Response::default())
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let from_version = start_migration(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new().add_event(
+        lunax_event("migrate")
+            .add_attribute("from_version", from_version.to_string())
+            .add_attribute("to_version", CONTRACT_VERSION),
+    ))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -55,52 +65,82 @@ pub fn execute(
             airdrop_contract_str,
             cw20_contract_str,
         ),
-        ExecuteMsg::SetManager { manager } => set_manager(deps, info, _env, manager),
+        ExecuteMsg::SetManager {
+            manager,
+            expires_in,
+        } => set_manager(deps, info, _env, manager, expires_in),
         ExecuteMsg::AcceptManager {} => accept_manager(deps, info, _env),
+        ExecuteMsg::CancelManagerUpdate {} => cancel_manager_update(deps, info, _env),
+        ExecuteMsg::RenounceManager {} => renounce_manager(deps, info, _env),
+        ExecuteMsg::Sweep {
+            denom_or_cw20,
+            amount,
+            recipient,
+        } => sweep_funds(deps, info, _env, denom_or_cw20, amount, recipient),
     }
 }
 
 pub fn set_manager(
     deps: DepsMut,
     info: MessageInfo,
-    _env: Env,
+    env: Env,
     manager: String,
+    expires_in: Option<u64>,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    if info.sender != config.manager {
-        return Err(ContractError::Unauthorized {});
-    }
 
-    TMP_MANAGER_STORE.save(deps.storage, &TmpManagerStore { manager: manager })?;
+    let event = ownership::propose_manager(
+        deps.storage,
+        deps.api,
+        &env,
+        &info.sender,
+        &config.manager,
+        manager,
+        expires_in,
+    )?;
 
-    Ok(Response::default())
+    Ok(Response::new().add_event(event))
 }
 
 pub fn accept_manager(
     deps: DepsMut,
     info: MessageInfo,
-    _env: Env,
+    env: Env,
 ) -> Result<Response, ContractError> {
     let mut config = CONFIG.load(deps.storage)?;
 
-    let tmp_manager_store =
-        if let Some(tmp_manager_store) = TMP_MANAGER_STORE.may_load(deps.storage)? {
-            tmp_manager_store
-        } else {
-            return Err(ContractError::TmpManagerStoreEmpty {});
-        };
+    let (manager, event) = ownership::accept_manager(deps.storage, deps.api, &env, &info.sender)?;
+    config.manager = manager;
+    CONFIG.save(deps.storage, &config)?;
 
-    let manager = deps.api.addr_validate(tmp_manager_store.manager.as_str())?;
-    if info.sender != manager {
-        return Err(ContractError::Unauthorized {});
-    }
+    Ok(Response::new().add_event(event))
+}
+
+pub fn cancel_manager_update(
+    deps: DepsMut,
+    info: MessageInfo,
+    _env: Env,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let event = ownership::cancel_manager_proposal(deps.storage, &info.sender, &config.manager)?;
+
+    Ok(Response::new().add_event(event))
+}
 
-    config.manager = deps.api.addr_validate(tmp_manager_store.manager.as_str())?;
-    TMP_MANAGER_STORE.remove(deps.storage);
+pub fn renounce_manager(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
 
+    let (manager, event) =
+        ownership::renounce_manager(deps.storage, &env, &info.sender, &config.manager)?;
+    config.manager = manager;
     CONFIG.save(deps.storage, &config)?;
 
-    Ok(Response::default())
+    Ok(Response::new().add_event(event))
 }
 
 pub fn update_airdrop_registry(
@@ -123,6 +163,10 @@ pub fn update_airdrop_registry(
     let airdrop_token = airdrop_token_str.to_lowercase();
     let airdrop_contract = deps.api.addr_validate(airdrop_contract_str.as_str())?;
     let cw20_contract = deps.api.addr_validate(cw20_contract_str.as_str())?;
+    let event = lunax_event("update-airdrop-registry")
+        .add_attribute("airdrop_token", airdrop_token.as_str())
+        .add_attribute("airdrop_contract", airdrop_contract.as_str())
+        .add_attribute("cw20_contract", cw20_contract.as_str());
     AIRDROP_REGISTRY.save(
         deps.storage,
         airdrop_token.clone(),
@@ -133,20 +177,63 @@ pub fn update_airdrop_registry(
         },
     )?;
 
-    Ok(Response::default())
+    Ok(Response::new().add_event(event))
+}
+
+pub fn sweep_funds(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    denom_or_cw20: DenomOrCw20,
+    amount: Uint128,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.manager {
+        return Err(ContractError::Unauthorized {});
+    }
+    if amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+
+    let denom_or_cw20 = denom_or_cw20.validate(deps.api)?;
+    let recipient = deps.api.addr_validate(recipient.as_str())?;
+
+    let (msg, event) = sweep::sweep(deps.storage, &env, denom_or_cw20, amount, recipient)?;
+    Ok(Response::new().add_message(msg).add_event(event))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetConfig {} => to_binary(&query_config(deps)?),
         QueryMsg::GetAirdropContracts { token } => {
             to_binary(&query_airdrop_contracts(deps, token)?)
         }
         QueryMsg::TmpManagerStore {} => to_binary(&query_tmp_manager_store(deps)?),
+        QueryMsg::Ownership {} => to_binary(&query_ownership(deps, env)?),
+        QueryMsg::SweepHistory { start_after, limit } => to_binary(&sweep::query_sweep_history(
+            deps.storage,
+            start_after,
+            limit,
+        )?),
     }
 }
 
+pub fn query_ownership(deps: Deps, env: Env) -> StdResult<OwnershipResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    ownership::query_ownership(deps.storage, &env, config.manager)
+}
+
+pub fn query_sweep_history(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u64>,
+) -> StdResult<SweepHistoryResponse> {
+    sweep::query_sweep_history(deps.storage, start_after, limit)
+}
+
 pub fn query_tmp_manager_store(deps: Deps) -> StdResult<TmpManagerStoreResponse> {
     let tmp_manager_store = TMP_MANAGER_STORE.may_load(deps.storage)?;
     Ok(TmpManagerStoreResponse { tmp_manager_store })
@@ -1,4 +1,6 @@
 use cosmwasm_std::StdError;
+use stader_utils::migration::MigrationError;
+use stader_utils::ownership::OwnershipError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -12,6 +14,61 @@ pub enum ContractError {
     #[error("AirdropRegistry-Contract: Airdrop token cannot be empty")]
     TokenEmpty {},
 
+    #[error("AirdropRegistry-Contract: Amount cannot be zero")]
+    ZeroAmount {},
+
     #[error("AirdropRegistry-Contract: Temp manager store is empty")]
     TmpManagerStoreEmpty {},
+
+    #[error("AirdropRegistry-Contract: Manager proposal has expired")]
+    ManagerProposalExpired {},
+
+    #[error("AirdropRegistry-Contract: Manager proposal expiry should be non-zero")]
+    InvalidManagerProposalExpiry {},
+
+    #[error("AirdropRegistry-Contract: Cannot migrate from contract {0}")]
+    InvalidMigrationContract(String),
+
+    #[error("AirdropRegistry-Contract: Cannot migrate from version {0} to older version {1}")]
+    MigrationDowngrade(String, String),
+
+    #[error("AirdropRegistry-Contract: Invalid contract version {0}")]
+    InvalidContractVersion(String),
+
+    #[error("AirdropRegistry-Contract: No contract version stored for {0}")]
+    MissingContractVersion(String),
+}
+
+impl From<OwnershipError> for ContractError {
+    fn from(err: OwnershipError) -> Self {
+        match err {
+            OwnershipError::Std(err) => ContractError::Std(err),
+            OwnershipError::Unauthorized {} => ContractError::Unauthorized {},
+            OwnershipError::TmpManagerStoreEmpty {} => ContractError::TmpManagerStoreEmpty {},
+            OwnershipError::ManagerProposalExpired {} => ContractError::ManagerProposalExpired {},
+            OwnershipError::InvalidManagerProposalExpiry {} => {
+                ContractError::InvalidManagerProposalExpiry {}
+            }
+        }
+    }
+}
+
+impl From<MigrationError> for ContractError {
+    fn from(err: MigrationError) -> Self {
+        match err {
+            MigrationError::Std(err) => ContractError::Std(err),
+            MigrationError::InvalidContractName(name) => {
+                ContractError::InvalidMigrationContract(name)
+            }
+            MigrationError::Downgrade(stored, new) => {
+                ContractError::MigrationDowngrade(stored, new)
+            }
+            MigrationError::InvalidVersion(version) => {
+                ContractError::InvalidContractVersion(version)
+            }
+            MigrationError::MissingContractVersion(name) => {
+                ContractError::MissingContractVersion(name)
+            }
+        }
+    }
 }
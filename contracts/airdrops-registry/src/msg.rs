@@ -1,6 +1,8 @@
 use crate::state::{AirdropRegistryInfo, Config, TmpManagerStore};
+use cosmwasm_std::Uint128;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use stader_utils::sweep::DenomOrCw20;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {}
@@ -18,16 +20,32 @@ pub enum ExecuteMsg {
     },
     SetManager {
         manager: String,
+        expires_in: Option<u64>, // Seconds. Defaults to stader_utils::ownership::DEFAULT_OWNERSHIP_PROPOSAL_EXPIRY
     },
     AcceptManager {},
+    CancelManagerUpdate {},
+    RenounceManager {},
+    // Returns tokens sent here by mistake. The registry never holds staking contract funds.
+    Sweep {
+        denom_or_cw20: DenomOrCw20,
+        amount: Uint128,
+        recipient: String,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     GetConfig {},
-    GetAirdropContracts { token: String },
+    GetAirdropContracts {
+        token: String,
+    },
     TmpManagerStore {},
+    Ownership {},
+    SweepHistory {
+        start_after: Option<u64>,
+        limit: Option<u64>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -1,8 +1,8 @@
 #[cfg(test)]
 mod tests {
-    use crate::contract::{execute, instantiate, query};
+    use crate::contract::{execute, instantiate, migrate, query};
 
-    use crate::msg::{ExecuteMsg, GetConfigResponse, InstantiateMsg, QueryMsg};
+    use crate::msg::{ExecuteMsg, GetConfigResponse, InstantiateMsg, MigrateMsg, QueryMsg};
     use crate::state::{
         AirdropRegistryInfo, Config, TmpManagerStore, AIRDROP_REGISTRY, CONFIG, TMP_MANAGER_STORE,
     };
@@ -10,7 +10,13 @@ mod tests {
     use cosmwasm_std::testing::{
         mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
     };
-    use cosmwasm_std::{from_binary, Addr, Empty, Env, MessageInfo, OwnedDeps, Response};
+    use cosmwasm_std::{
+        from_binary, to_binary, Addr, Empty, Env, MessageInfo, OwnedDeps, Response, SubMsg,
+        Uint128, WasmMsg,
+    };
+    use cw2::set_contract_version;
+    use cw20::Cw20ExecuteMsg;
+    use stader_utils::sweep::{DenomOrCw20, SweepHistoryResponse, SweepRecord};
 
     fn instantiate_contract(
         deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
@@ -19,7 +25,7 @@ mod tests {
     ) -> Response<Empty> {
         let msg = InstantiateMsg {};
 
-        return instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap()
     }
 
     #[test]
@@ -127,6 +133,7 @@ mod tests {
             mock_info("not-creator", &[]),
             ExecuteMsg::SetManager {
                 manager: "test_manager".to_string(),
+                expires_in: None,
             },
         )
         .unwrap_err();
@@ -141,6 +148,7 @@ mod tests {
             mock_info("creator", &[]),
             ExecuteMsg::SetManager {
                 manager: "test_manager".to_string(),
+                expires_in: None,
             },
         )
         .unwrap();
@@ -173,6 +181,7 @@ mod tests {
                 deps.as_mut().storage,
                 &TmpManagerStore {
                     manager: "new_manager".to_string(),
+                    expires_at: None,
                 },
             )
             .unwrap();
@@ -204,4 +213,97 @@ mod tests {
         let tmp_manager_store = TMP_MANAGER_STORE.may_load(deps.as_mut().storage).unwrap();
         assert_eq!(tmp_manager_store, None);
     }
+
+    #[test]
+    fn test_sweep() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+
+        instantiate_contract(&mut deps, &info, &env);
+
+        /*
+            Unauthorized
+        */
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("not-creator", &[]),
+            ExecuteMsg::Sweep {
+                denom_or_cw20: DenomOrCw20::Cw20(Addr::unchecked("token")),
+                amount: Uint128::new(100),
+                recipient: "user".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        /*
+           Successful
+        */
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::Sweep {
+                denom_or_cw20: DenomOrCw20::Cw20(Addr::unchecked("token")),
+                amount: Uint128::new(100),
+                recipient: "user".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: "token".to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "user".to_string(),
+                    amount: Uint128::new(100),
+                })
+                .unwrap(),
+                funds: vec![],
+            })]
+        );
+
+        let res: SweepHistoryResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::SweepHistory {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.sweeps,
+            vec![SweepRecord {
+                id: 1,
+                asset: DenomOrCw20::Cw20(Addr::unchecked("token")),
+                amount: Uint128::new(100),
+                recipient: Addr::unchecked("user"),
+                time: env.block.time,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_migrate() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+        instantiate_contract(&mut deps, &info, &env);
+
+        migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
+
+        set_contract_version(deps.as_mut().storage, "airdrops-registry", "9.0.0").unwrap();
+        let err = migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap_err();
+        assert!(matches!(err, ContractError::MigrationDowngrade(_, _)));
+
+        set_contract_version(deps.as_mut().storage, "reward", "0.1.0").unwrap();
+        let err = migrate(deps.as_mut(), env, MigrateMsg {}).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMigrationContract(_)));
+    }
 }
@@ -2,9 +2,11 @@ use std::env::current_dir;
 use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
+use stader_utils::ownership::OwnershipResponse;
 
 use reward::msg::{
-    ExecuteMsg, GetConfigResponse, InstantiateMsg, QueryMsg, TmpManagerStoreResponse,
+    ExecuteMsg, GetConfigResponse, InstantiateMsg, QueryMsg, SwapConfigResponse,
+    TmpManagerStoreResponse, VaultsResponse,
 };
 use reward::state::*;
 
@@ -20,4 +22,7 @@ fn main() {
     export_schema(&schema_for!(Config), &out_dir);
     export_schema(&schema_for!(GetConfigResponse), &out_dir);
     export_schema(&schema_for!(TmpManagerStoreResponse), &out_dir);
+    export_schema(&schema_for!(OwnershipResponse), &out_dir);
+    export_schema(&schema_for!(SwapConfigResponse), &out_dir);
+    export_schema(&schema_for!(VaultsResponse), &out_dir);
 }
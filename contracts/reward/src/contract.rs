@@ -3,16 +3,28 @@ use cosmwasm_std::entry_point;
 
 use crate::error::ContractError;
 use crate::msg::{
-    ExecuteMsg, GetConfigResponse, InstantiateMsg, MigrateMsg, QueryMsg, TmpManagerStoreResponse,
+    AssetInfo, ExecuteMsg, GetConfigResponse, InstantiateMsg, MigrateMsg, ProtocolRevenueResponse,
+    QueryMsg, RouterExecuteMsg, SwapConfigResponse, SwapOperation, TmpManagerStoreResponse,
+    TransferHistoryResponse, VaultBalance, VaultResponse, VaultsResponse,
 };
+use stader_utils::events::{lunax_event, LunaxEventExt};
+use stader_utils::ownership::{self, OwnershipResponse};
 
-use crate::state::{Config, TmpManagerStore, CONFIG, TMP_MANAGER_STORE};
+use crate::state::{
+    Config, RewardsWithdrawal, TransferRecord, CONFIG, PROTOCOL_REVENUE, REWARDS_WITHDRAWAL,
+    SWAP_CONFIG, TMP_MANAGER_STORE, TOTAL_VAULT_BALANCE, TRANSFER_HISTORY, VAULT_BALANCES,
+};
 use cw2::set_contract_version;
+use cw_storage_plus::Bound;
 
 use cosmwasm_std::{
-    to_binary, Addr, Binary, Coin, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128,
+    to_binary, Addr, Binary, Coin, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult,
+    Uint128, WasmMsg,
 };
+use stader_utils::fee_split::{fee_split_msgs, FeeSplitEntry};
 use stader_utils::helpers::send_funds_msg;
+use stader_utils::migration::{needs_migration, start_migration};
+use stader_utils::sweep::{self, DenomOrCw20, SweepHistoryResponse};
 
 const CONTRACT_NAME: &str = "reward";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -30,14 +42,52 @@ pub fn instantiate(
         staking_contract: deps.api.addr_validate(msg.staking_contract.as_str())?,
     };
     CONFIG.save(deps.storage, &config)?;
+    VAULT_BALANCES.save(deps.storage, &config.staking_contract, &Uint128::zero())?;
 
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     Ok(Response::default())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    Ok(Response::default())
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let from_version = start_migration(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let config = CONFIG.load(deps.storage)?;
+
+    // Transforms run oldest first and must be safe to re-run. Each one is gated on the last version
+    // without the state it sets up.
+    if needs_migration(&from_version, "0.1.0")? {
+        // Contracts from before vault balances only served the staking contract, so it owns the
+        // whole balance.
+        if !VAULT_BALANCES.has(deps.storage, &config.staking_contract) {
+            let balance = deps
+                .querier
+                .query_balance(env.contract.address, config.reward_denom.clone())?
+                .amount;
+            VAULT_BALANCES.save(deps.storage, &config.staking_contract, &balance)?;
+            TOTAL_VAULT_BALANCE.save(deps.storage, &balance)?;
+        }
+    }
+
+    let mut swap_config = SWAP_CONFIG.may_load(deps.storage)?.unwrap_or_default();
+    if let Some(swap_router) = msg.swap_router {
+        swap_config.router = Some(deps.api.addr_validate(swap_router.as_str())?);
+    }
+    if let Some(allowed_swap_denoms) = msg.allowed_swap_denoms {
+        if let Some(denom) = allowed_swap_denoms
+            .iter()
+            .find(|x| x.eq(&&config.reward_denom))
+        {
+            return Err(ContractError::SwapDenomNotAllowed(denom.clone()));
+        }
+        swap_config.allowed_denoms = allowed_swap_denoms;
+    }
+    SWAP_CONFIG.save(deps.storage, &swap_config)?;
+
+    Ok(Response::new().add_event(
+        lunax_event("migrate")
+            .add_attribute("from_version", from_version.to_string())
+            .add_attribute("to_version", CONTRACT_VERSION),
+    ))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -53,6 +103,7 @@ pub fn execute(
             reward_withdraw_contract,
             protocol_fee_amount: protocol_fee,
             protocol_fee_contract,
+            protocol_fee_split,
         } => transfer(
             deps,
             info,
@@ -61,59 +112,280 @@ pub fn execute(
             reward_withdraw_contract,
             protocol_fee,
             protocol_fee_contract,
+            protocol_fee_split,
         ),
+        ExecuteMsg::BeginRewardsWithdrawal {} => begin_rewards_withdrawal(deps, info, env),
+        ExecuteMsg::RecordRewards {} => record_rewards(deps, info, env),
+        ExecuteMsg::ClearRewardsWithdrawal {} => clear_rewards_withdrawal(deps, info, env),
+        ExecuteMsg::RegisterVault { vault } => register_vault(deps, info, env, vault),
+        ExecuteMsg::DeregisterVault { vault } => deregister_vault(deps, info, env, vault),
         ExecuteMsg::UpdateConfig {
             staking_contract: pools_contract,
         } => update_config(deps, info, env, pools_contract),
-        ExecuteMsg::SetManager { manager } => set_manager(deps, info, env, manager),
+        ExecuteMsg::SetManager {
+            manager,
+            expires_in,
+        } => set_manager(deps, info, env, manager, expires_in),
         ExecuteMsg::AcceptManager {} => accept_manager(deps, info, env),
+        ExecuteMsg::CancelManagerUpdate {} => cancel_manager_update(deps, info, env),
+        ExecuteMsg::RenounceManager {} => renounce_manager(deps, info, env),
+        ExecuteMsg::UpdateSwapConfig {
+            router,
+            allowed_denoms,
+        } => update_swap_config(deps, info, env, router, allowed_denoms),
+        ExecuteMsg::SwapRewards {
+            denoms,
+            routes,
+            min_out,
+            vault,
+        } => swap_rewards(deps, info, env, denoms, routes, min_out, vault),
+        ExecuteMsg::Sweep {
+            denom_or_cw20,
+            amount,
+            recipient,
+        } => sweep_funds(deps, info, env, denom_or_cw20, amount, recipient),
+        ExecuteMsg::SweepUnallocated { amount, recipient } => {
+            sweep_unallocated(deps, info, env, amount, recipient)
+        }
     }
 }
 
 pub fn set_manager(
     deps: DepsMut,
     info: MessageInfo,
-    _env: Env,
+    env: Env,
     manager: String,
+    expires_in: Option<u64>,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    if info.sender != config.manager {
-        return Err(ContractError::Unauthorized {});
-    }
 
-    TMP_MANAGER_STORE.save(deps.storage, &TmpManagerStore { manager: manager })?;
+    let event = ownership::propose_manager(
+        deps.storage,
+        deps.api,
+        &env,
+        &info.sender,
+        &config.manager,
+        manager,
+        expires_in,
+    )?;
 
-    Ok(Response::default())
+    Ok(Response::new().add_event(event))
 }
 
 pub fn accept_manager(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    let (manager, event) = ownership::accept_manager(deps.storage, deps.api, &env, &info.sender)?;
+    config.manager = manager;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_event(event))
+}
+
+pub fn cancel_manager_update(
     deps: DepsMut,
     info: MessageInfo,
     _env: Env,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let event = ownership::cancel_manager_proposal(deps.storage, &info.sender, &config.manager)?;
+
+    Ok(Response::new().add_event(event))
+}
+
+pub fn renounce_manager(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
 ) -> Result<Response, ContractError> {
     let mut config = CONFIG.load(deps.storage)?;
 
-    let tmp_manager_store =
-        if let Some(tmp_manager_store) = TMP_MANAGER_STORE.may_load(deps.storage)? {
-            tmp_manager_store
-        } else {
-            return Err(ContractError::TmpManagerStoreEmpty {});
-        };
+    let (manager, event) =
+        ownership::renounce_manager(deps.storage, &env, &info.sender, &config.manager)?;
+    config.manager = manager;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_event(event))
+}
+
+fn get_unallocated_rewards(deps: Deps, env: &Env, config: &Config) -> StdResult<Uint128> {
+    let balance = deps
+        .querier
+        .query_balance(env.contract.address.clone(), config.reward_denom.clone())?;
+    let total_vault_balance = TOTAL_VAULT_BALANCE
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    Ok(balance.amount.saturating_sub(total_vault_balance))
+}
 
-    let manager = deps.api.addr_validate(tmp_manager_store.manager.as_str())?;
-    if info.sender != manager {
+pub fn begin_rewards_withdrawal(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !VAULT_BALANCES.has(deps.storage, &info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
-    config.manager = deps.api.addr_validate(tmp_manager_store.manager.as_str())?;
+    let balance = save_rewards_withdrawal(deps, &env, &config, &info.sender)?;
 
-    TMP_MANAGER_STORE.remove(deps.storage);
-    CONFIG.save(deps.storage, &config)?;
+    Ok(Response::new().add_event(
+        lunax_event("begin-rewards-withdrawal")
+            .add_attribute("vault", info.sender.as_str())
+            .add_amount("balance", balance),
+    ))
+}
 
-    Ok(Response::default())
+// Withdrawals cannot be nested, otherwise one vault could claim what another withdraws.
+fn save_rewards_withdrawal(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    vault: &Addr,
+) -> Result<Uint128, ContractError> {
+    if REWARDS_WITHDRAWAL.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::RewardsWithdrawalInProgress {});
+    }
+    let balance = deps
+        .querier
+        .query_balance(env.contract.address.clone(), config.reward_denom.clone())?
+        .amount;
+    REWARDS_WITHDRAWAL.save(
+        deps.storage,
+        &RewardsWithdrawal {
+            vault: vault.clone(),
+            balance,
+        },
+    )?;
+    Ok(balance)
 }
 
-// Transfers luna to SCC at the behest of Pools contract
+// Called by the vault which began the withdrawal, or by this contract for swaps on a vault's behalf.
+pub fn record_rewards(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let rewards_withdrawal = REWARDS_WITHDRAWAL
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoRewardsWithdrawal {})?;
+    if info.sender != rewards_withdrawal.vault && info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    REWARDS_WITHDRAWAL.remove(deps.storage);
+
+    let vault = rewards_withdrawal.vault;
+    let amount = deps
+        .querier
+        .query_balance(env.contract.address, config.reward_denom)?
+        .amount
+        .saturating_sub(rewards_withdrawal.balance);
+    let vault_balance = VAULT_BALANCES
+        .may_load(deps.storage, &vault)?
+        .ok_or(ContractError::VaultNotRegistered {})?
+        .checked_add(amount)
+        .unwrap();
+    VAULT_BALANCES.save(deps.storage, &vault, &vault_balance)?;
+    let total_vault_balance = TOTAL_VAULT_BALANCE
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    TOTAL_VAULT_BALANCE.save(
+        deps.storage,
+        &total_vault_balance.checked_add(amount).unwrap(),
+    )?;
+
+    Ok(Response::new().add_event(
+        lunax_event("record-rewards")
+            .add_attribute("vault", vault.as_str())
+            .add_amount("amount", amount)
+            .add_amount("vault_balance", vault_balance),
+    ))
+}
+
+pub fn clear_rewards_withdrawal(
+    deps: DepsMut,
+    info: MessageInfo,
+    _env: Env,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.manager {
+        return Err(ContractError::Unauthorized {});
+    }
+    let rewards_withdrawal = REWARDS_WITHDRAWAL
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoRewardsWithdrawal {})?;
+    REWARDS_WITHDRAWAL.remove(deps.storage);
+
+    Ok(Response::new().add_event(
+        lunax_event("clear-rewards-withdrawal")
+            .add_attribute("vault", rewards_withdrawal.vault.as_str())
+            .add_amount("balance", rewards_withdrawal.balance),
+    ))
+}
+
+pub fn register_vault(
+    deps: DepsMut,
+    info: MessageInfo,
+    _env: Env,
+    vault: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.manager {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let vault = deps.api.addr_validate(vault.as_str())?;
+    if VAULT_BALANCES.has(deps.storage, &vault) {
+        return Err(ContractError::VaultAlreadyRegistered {});
+    }
+    VAULT_BALANCES.save(deps.storage, &vault, &Uint128::zero())?;
+
+    Ok(Response::new().add_event(lunax_event("register-vault").add_attribute("vault", vault)))
+}
+
+pub fn deregister_vault(
+    deps: DepsMut,
+    info: MessageInfo,
+    _env: Env,
+    vault: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.manager {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let vault = deps.api.addr_validate(vault.as_str())?;
+    let vault_balance = VAULT_BALANCES
+        .may_load(deps.storage, &vault)?
+        .ok_or(ContractError::VaultNotRegistered {})?;
+    VAULT_BALANCES.remove(deps.storage, &vault);
+    let total_vault_balance = TOTAL_VAULT_BALANCE
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    TOTAL_VAULT_BALANCE.save(
+        deps.storage,
+        &total_vault_balance.saturating_sub(vault_balance),
+    )?;
+
+    Ok(Response::new().add_event(
+        lunax_event("deregister-vault")
+            .add_attribute("vault", vault)
+            .add_amount("unallocated_balance", vault_balance),
+    ))
+}
+
+// Transfers luna out of the calling vault's balance
+#[allow(clippy::too_many_arguments)]
 pub fn transfer(
     deps: DepsMut,
     info: MessageInfo,
@@ -122,22 +394,64 @@ pub fn transfer(
     reward_withdraw_contract: Addr,
     protocol_fee: Uint128,
     protocol_fee_contract: Addr,
+    protocol_fee_split: Vec<FeeSplitEntry>,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-
-    if info.sender != config.staking_contract {
-        return Err(ContractError::Unauthorized {});
-    }
+    let vault_balance = VAULT_BALANCES
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::Unauthorized {})?;
 
     let total_withdrawal_amount = reward_amount.checked_add(protocol_fee).unwrap();
-    if deps
-        .querier
-        .query_balance(env.contract.address, config.reward_denom.clone())?
-        .amount
-        .lt(&total_withdrawal_amount)
+    if vault_balance.lt(&total_withdrawal_amount)
+        || deps
+            .querier
+            .query_balance(env.contract.address.clone(), config.reward_denom.clone())?
+            .amount
+            .lt(&total_withdrawal_amount)
     {
         return Err(ContractError::InSufficientFunds {});
     }
+    if !total_withdrawal_amount.is_zero() {
+        VAULT_BALANCES.save(
+            deps.storage,
+            &info.sender,
+            &(vault_balance - total_withdrawal_amount),
+        )?;
+        let total_vault_balance = TOTAL_VAULT_BALANCE
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        TOTAL_VAULT_BALANCE.save(
+            deps.storage,
+            &total_vault_balance.saturating_sub(total_withdrawal_amount),
+        )?;
+    }
+    let mut protocol_revenue = PROTOCOL_REVENUE.may_load(deps.storage)?.unwrap_or_default();
+    protocol_revenue.rewards_transferred = protocol_revenue
+        .rewards_transferred
+        .checked_add(reward_amount)
+        .unwrap();
+    protocol_revenue.protocol_fees = protocol_revenue
+        .protocol_fees
+        .checked_add(protocol_fee)
+        .unwrap();
+    // Empty transfers move nothing, so they are left out of the history.
+    if !total_withdrawal_amount.is_zero() {
+        protocol_revenue.transfer_count = protocol_revenue.transfer_count.checked_add(1).unwrap();
+        TRANSFER_HISTORY.save(
+            deps.storage,
+            protocol_revenue.transfer_count,
+            &TransferRecord {
+                id: protocol_revenue.transfer_count,
+                vault: info.sender.clone(),
+                block_height: env.block.height,
+                time: env.block.time,
+                reward_amount,
+                protocol_fee_amount: protocol_fee,
+            },
+        )?;
+    }
+    PROTOCOL_REVENUE.save(deps.storage, &protocol_revenue)?;
+
     let mut msgs = vec![];
     if !reward_amount.is_zero() {
         msgs.push(send_funds_msg(
@@ -147,12 +461,24 @@ pub fn transfer(
     }
 
     if !protocol_fee.is_zero() {
-        msgs.push(send_funds_msg(
+        msgs.extend(fee_split_msgs(
+            &protocol_fee_split,
             &protocol_fee_contract,
-            &[Coin::new(protocol_fee.u128(), config.reward_denom)],
+            protocol_fee,
+            &config.reward_denom,
         ));
     }
-    Ok(Response::new().add_messages(msgs))
+    Ok(Response::new().add_messages(msgs).add_event(
+        lunax_event("transfer-rewards")
+            .add_attribute("vault", info.sender.as_str())
+            .add_amount("reward_amount", reward_amount)
+            .add_attribute(
+                "reward_withdraw_contract",
+                reward_withdraw_contract.as_str(),
+            )
+            .add_amount("protocol_fee", protocol_fee)
+            .add_attribute("protocol_fee_contract", protocol_fee_contract.as_str()),
+    ))
 }
 
 pub fn update_config(
@@ -167,27 +493,320 @@ pub fn update_config(
         return Err(ContractError::Unauthorized {});
     }
 
-    if pools_contract.is_some() {
-        config.staking_contract = deps.api.addr_validate(pools_contract.unwrap().as_str())?;
+    if let Some(pools_contract) = pools_contract {
+        config.staking_contract = deps.api.addr_validate(pools_contract.as_str())?;
+        if !VAULT_BALANCES.has(deps.storage, &config.staking_contract) {
+            VAULT_BALANCES.save(deps.storage, &config.staking_contract, &Uint128::zero())?;
+        }
     }
 
     CONFIG.save(deps.storage, &config)?;
-    Ok(Response::default())
+    Ok(Response::new().add_event(
+        lunax_event("update-config")
+            .add_attribute("staking_contract", config.staking_contract.as_str()),
+    ))
+}
+
+pub fn update_swap_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    _env: Env,
+    router: Option<String>,
+    allowed_denoms: Option<Vec<String>>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.manager {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut swap_config = SWAP_CONFIG.may_load(deps.storage)?.unwrap_or_default();
+    if let Some(router) = router {
+        swap_config.router = Some(deps.api.addr_validate(router.as_str())?);
+    }
+    if let Some(allowed_denoms) = allowed_denoms {
+        if let Some(denom) = allowed_denoms.iter().find(|x| x.eq(&&config.reward_denom)) {
+            return Err(ContractError::SwapDenomNotAllowed(denom.clone()));
+        }
+        swap_config.allowed_denoms = allowed_denoms;
+    }
+    SWAP_CONFIG.save(deps.storage, &swap_config)?;
+
+    let mut event = lunax_event("update-swap-config")
+        .add_attribute("allowed_denoms", swap_config.allowed_denoms.join(","));
+    if let Some(router) = swap_config.router {
+        event = event.add_attribute("router", router.as_str());
+    }
+    Ok(Response::new().add_event(event))
+}
+
+// A route is valid if it starts from the swapped denom, every hop continues from the previous one
+// and it ends in the reward denom.
+fn get_swap_operations(
+    denom: &str,
+    route: Vec<SwapOperation>,
+    reward_denom: &str,
+) -> Result<Vec<SwapOperation>, ContractError> {
+    if route.is_empty() {
+        return Ok(vec![SwapOperation::NativeSwap {
+            offer_denom: denom.to_string(),
+            ask_denom: reward_denom.to_string(),
+        }]);
+    }
+
+    let mut offer_asset_info = AssetInfo::NativeToken {
+        denom: denom.to_string(),
+    };
+    for operation in route.iter() {
+        if operation.offer_asset_info().ne(&offer_asset_info) {
+            return Err(ContractError::InvalidSwapRoutes {});
+        }
+        offer_asset_info = operation.ask_asset_info();
+    }
+    if offer_asset_info.ne(&AssetInfo::NativeToken {
+        denom: reward_denom.to_string(),
+    }) {
+        return Err(ContractError::InvalidSwapRoutes {});
+    }
+    Ok(route)
+}
+
+// Only the manager can swap. The contract does not know which vault other denoms belong to, so the
+// manager picks the vault credited with the output.
+pub fn swap_rewards(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    denoms: Vec<String>,
+    routes: Vec<Vec<SwapOperation>>,
+    min_out: Vec<Uint128>,
+    vault: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.manager {
+        return Err(ContractError::Unauthorized {});
+    }
+    let vault = match vault {
+        Some(vault) => deps.api.addr_validate(vault.as_str())?,
+        None => config.staking_contract.clone(),
+    };
+    if !VAULT_BALANCES.has(deps.storage, &vault) {
+        return Err(ContractError::VaultNotRegistered {});
+    }
+
+    let swap_config = SWAP_CONFIG.may_load(deps.storage)?.unwrap_or_default();
+    let router = swap_config
+        .router
+        .ok_or(ContractError::SwapRouterNotSet {})?;
+    if denoms.len() != routes.len() || denoms.len() != min_out.len() {
+        return Err(ContractError::InvalidSwapRoutes {});
+    }
+
+    let mut msgs = vec![];
+    let mut event = lunax_event("swap-rewards")
+        .add_attribute("router", router.as_str())
+        .add_attribute("vault", vault.as_str());
+    for ((denom, route), min_out) in denoms.into_iter().zip(routes).zip(min_out) {
+        if !swap_config.allowed_denoms.contains(&denom) {
+            return Err(ContractError::SwapDenomNotAllowed(denom));
+        }
+        let operations = get_swap_operations(&denom, route, &config.reward_denom)?;
+
+        let balance = deps
+            .querier
+            .query_balance(env.contract.address.clone(), denom.clone())?;
+        if balance.amount.is_zero() {
+            continue;
+        }
+        msgs.push(WasmMsg::Execute {
+            contract_addr: router.to_string(),
+            msg: to_binary(&RouterExecuteMsg::ExecuteSwapOperations {
+                operations,
+                minimum_receive: Some(min_out),
+                to: None,
+            })?,
+            funds: vec![balance.clone()],
+        });
+        event = event.add_amount(&denom, balance.amount);
+    }
+    // The swap output is credited to the vault once all swaps have run.
+    if !msgs.is_empty() {
+        save_rewards_withdrawal(deps, &env, &config, &vault)?;
+        msgs.push(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            msg: to_binary(&ExecuteMsg::RecordRewards {})?,
+            funds: vec![],
+        });
+    }
+
+    Ok(Response::new().add_messages(msgs).add_event(event))
+}
+
+pub fn sweep_funds(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    denom_or_cw20: DenomOrCw20,
+    amount: Uint128,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.manager {
+        return Err(ContractError::Unauthorized {});
+    }
+    if amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+
+    let denom_or_cw20 = denom_or_cw20.validate(deps.api)?;
+    if let DenomOrCw20::Denom(denom) = &denom_or_cw20 {
+        let swap_config = SWAP_CONFIG.may_load(deps.storage)?.unwrap_or_default();
+        if denom.eq(&config.reward_denom) || swap_config.allowed_denoms.contains(denom) {
+            return Err(ContractError::SweepReservedAsset {});
+        }
+    }
+    let recipient = deps.api.addr_validate(recipient.as_str())?;
+
+    let (msg, event) = sweep::sweep(deps.storage, &env, denom_or_cw20, amount, recipient)?;
+    Ok(Response::new().add_message(msg).add_event(event))
+}
+
+// Only the manager can sweep, unallocated funds may still be credited to a vault by a swap.
+// Recorded in the sweep history, not as a transfer.
+pub fn sweep_unallocated(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    amount: Uint128,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.manager {
+        return Err(ContractError::Unauthorized {});
+    }
+    if amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+    if get_unallocated_rewards(deps.as_ref(), &env, &config)?.lt(&amount) {
+        return Err(ContractError::InSufficientFunds {});
+    }
+    let recipient = deps.api.addr_validate(recipient.as_str())?;
+
+    let (msg, event) = sweep::sweep(
+        deps.storage,
+        &env,
+        DenomOrCw20::Denom(config.reward_denom),
+        amount,
+        recipient,
+    )?;
+    Ok(Response::new().add_message(msg).add_event(event))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::TmpManagerStore {} => to_binary(&query_tmp_manager_store(deps)?),
+        QueryMsg::Ownership {} => to_binary(&query_ownership(deps, env)?),
+        QueryMsg::ProtocolRevenue {} => to_binary(&query_protocol_revenue(deps)?),
+        QueryMsg::SwapConfig {} => to_binary(&query_swap_config(deps)?),
+        QueryMsg::TransferHistory { start_after, limit } => {
+            to_binary(&query_transfer_history(deps, start_after, limit)?)
+        }
+        QueryMsg::Vault { vault } => to_binary(&query_vault(deps, env, vault)?),
+        QueryMsg::SweepHistory { start_after, limit } => {
+            to_binary(&query_sweep_history(deps, start_after, limit)?)
+        }
+        QueryMsg::Vaults { start_after, limit } => {
+            to_binary(&query_vaults(deps, env, start_after, limit)?)
+        }
     }
 }
 
+pub fn query_ownership(deps: Deps, env: Env) -> StdResult<OwnershipResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    ownership::query_ownership(deps.storage, &env, config.manager)
+}
+
 pub fn query_tmp_manager_store(deps: Deps) -> StdResult<TmpManagerStoreResponse> {
     let tmp_manager_store = TMP_MANAGER_STORE.may_load(deps.storage)?;
     Ok(TmpManagerStoreResponse { tmp_manager_store })
 }
 
+pub fn query_protocol_revenue(deps: Deps) -> StdResult<ProtocolRevenueResponse> {
+    let protocol_revenue = PROTOCOL_REVENUE.may_load(deps.storage)?.unwrap_or_default();
+    Ok(ProtocolRevenueResponse { protocol_revenue })
+}
+
+pub fn query_sweep_history(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u64>,
+) -> StdResult<SweepHistoryResponse> {
+    sweep::query_sweep_history(deps.storage, start_after, limit)
+}
+
+pub fn query_vault(deps: Deps, env: Env, vault: String) -> StdResult<VaultResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let vault = deps.api.addr_validate(vault.as_str())?;
+    let balance = VAULT_BALANCES.load(deps.storage, &vault)?;
+    Ok(VaultResponse {
+        vault,
+        balance,
+        unallocated_rewards: get_unallocated_rewards(deps, &env, &config)?,
+    })
+}
+
+pub fn query_vaults(
+    deps: Deps,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u64>,
+) -> StdResult<VaultsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let limit = limit.unwrap_or(10).min(30) as usize;
+    let start_after = start_after
+        .map(|x| deps.api.addr_validate(x.as_str()))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let vaults = VAULT_BALANCES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(vault, balance)| VaultBalance { vault, balance }))
+        .collect::<StdResult<Vec<VaultBalance>>>()?;
+
+    Ok(VaultsResponse {
+        vaults,
+        unallocated_rewards: get_unallocated_rewards(deps, &env, &config)?,
+    })
+}
+
+pub fn query_transfer_history(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u64>,
+) -> StdResult<TransferHistoryResponse> {
+    let limit = limit.unwrap_or(10).min(30) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let transfers = TRANSFER_HISTORY
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, transfer)| transfer))
+        .collect::<StdResult<Vec<TransferRecord>>>()?;
+
+    Ok(TransferHistoryResponse { transfers })
+}
+
+pub fn query_swap_config(deps: Deps) -> StdResult<SwapConfigResponse> {
+    let swap_config = SWAP_CONFIG.may_load(deps.storage)?.unwrap_or_default();
+    Ok(SwapConfigResponse { swap_config })
+}
+
 pub fn query_config(deps: Deps) -> StdResult<GetConfigResponse> {
     let config: Config = CONFIG.load(deps.storage)?;
     Ok(GetConfigResponse { config })
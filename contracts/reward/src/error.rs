@@ -1,4 +1,6 @@
 use cosmwasm_std::StdError;
+use stader_utils::migration::MigrationError;
+use stader_utils::ownership::OwnershipError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -17,4 +19,80 @@ pub enum ContractError {
 
     #[error("Reward-Contract: Tmp manager store is empty")]
     TmpManagerStoreEmpty {},
+
+    #[error("Reward-Contract: Manager proposal has expired")]
+    ManagerProposalExpired {},
+
+    #[error("Reward-Contract: Manager proposal expiry should be non-zero")]
+    InvalidManagerProposalExpiry {},
+
+    #[error("Reward-Contract: Swap router is not configured")]
+    SwapRouterNotSet {},
+
+    #[error("Reward-Contract: Denom '{0}' is not allowed for swaps")]
+    SwapDenomNotAllowed(String),
+
+    #[error("Reward-Contract: Rewards and swappable denoms are reserved for the vaults and cannot be swept")]
+    SweepReservedAsset {},
+
+    #[error("Reward-Contract: Vault is already registered")]
+    VaultAlreadyRegistered {},
+
+    #[error("Reward-Contract: Vault is not registered")]
+    VaultNotRegistered {},
+
+    #[error("Reward-Contract: A rewards withdrawal is already in progress")]
+    RewardsWithdrawalInProgress {},
+
+    #[error("Reward-Contract: No rewards withdrawal in progress")]
+    NoRewardsWithdrawal {},
+
+    #[error("Reward-Contract: Invalid swap routes. Expected one route and min out per denom, each ending in the reward denom")]
+    InvalidSwapRoutes {},
+
+    #[error("Reward-Contract: Cannot migrate from contract {0}")]
+    InvalidMigrationContract(String),
+
+    #[error("Reward-Contract: Cannot migrate from version {0} to older version {1}")]
+    MigrationDowngrade(String, String),
+
+    #[error("Reward-Contract: Invalid contract version {0}")]
+    InvalidContractVersion(String),
+
+    #[error("Reward-Contract: No contract version stored for {0}")]
+    MissingContractVersion(String),
+}
+
+impl From<OwnershipError> for ContractError {
+    fn from(err: OwnershipError) -> Self {
+        match err {
+            OwnershipError::Std(err) => ContractError::Std(err),
+            OwnershipError::Unauthorized {} => ContractError::Unauthorized {},
+            OwnershipError::TmpManagerStoreEmpty {} => ContractError::TmpManagerStoreEmpty {},
+            OwnershipError::ManagerProposalExpired {} => ContractError::ManagerProposalExpired {},
+            OwnershipError::InvalidManagerProposalExpiry {} => {
+                ContractError::InvalidManagerProposalExpiry {}
+            }
+        }
+    }
+}
+
+impl From<MigrationError> for ContractError {
+    fn from(err: MigrationError) -> Self {
+        match err {
+            MigrationError::Std(err) => ContractError::Std(err),
+            MigrationError::InvalidContractName(name) => {
+                ContractError::InvalidMigrationContract(name)
+            }
+            MigrationError::Downgrade(stored, new) => {
+                ContractError::MigrationDowngrade(stored, new)
+            }
+            MigrationError::InvalidVersion(version) => {
+                ContractError::InvalidContractVersion(version)
+            }
+            MigrationError::MissingContractVersion(name) => {
+                ContractError::MissingContractVersion(name)
+            }
+        }
+    }
 }
@@ -1,7 +1,9 @@
-use crate::state::{Config, TmpManagerStore};
+use crate::state::{Config, ProtocolRevenue, SwapConfig, TmpManagerStore, TransferRecord};
 use cosmwasm_std::{Addr, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use stader_utils::fee_split::FeeSplitEntry;
+use stader_utils::sweep::DenomOrCw20;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -9,7 +11,13 @@ pub struct InstantiateMsg {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
+pub struct MigrateMsg {
+    // Only set when initializing or overriding fields added since the deployed version.
+    #[serde(default)]
+    pub swap_router: Option<String>,
+    #[serde(default)]
+    pub allowed_swap_denoms: Option<Vec<String>>,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -19,14 +27,112 @@ pub enum ExecuteMsg {
         reward_withdraw_contract: Addr,
         protocol_fee_amount: Uint128,
         protocol_fee_contract: Addr,
-    }, // Transfer swapped rewards to SCC.
+        // Protocol fee goes to protocol_fee_contract if empty.
+        #[serde(default)]
+        protocol_fee_split: Vec<FeeSplitEntry>,
+    }, // Transfer rewards out of the calling vault's balance.
+    // Sent by a vault before the messages which withdraw its rewards to this contract.
+    BeginRewardsWithdrawal {},
+    // Sent after the withdrawals. Credits everything received since BeginRewardsWithdrawal to the vault.
+    RecordRewards {},
+    // Manager only. Drops a withdrawal which was begun and never recorded, so other vaults can
+    // withdraw again. Anything received since is left unallocated.
+    ClearRewardsWithdrawal {},
+    RegisterVault {
+        vault: String,
+    },
+    // Any balance left on the vault becomes unallocated.
+    DeregisterVault {
+        vault: String,
+    },
     UpdateConfig {
         staking_contract: Option<String>,
     },
     SetManager {
         manager: String,
+        expires_in: Option<u64>, // Seconds. Defaults to stader_utils::ownership::DEFAULT_OWNERSHIP_PROPOSAL_EXPIRY
     },
     AcceptManager {},
+    CancelManagerUpdate {},
+    RenounceManager {},
+    UpdateSwapConfig {
+        router: Option<String>,
+        allowed_denoms: Option<Vec<String>>,
+    },
+    // Swaps the whole balance of each denom into reward_denom. routes and min_out are per denom, an
+    // empty route is a single native swap into reward_denom. Manager only, the output is recorded
+    // for vault (default staking_contract).
+    SwapRewards {
+        denoms: Vec<String>,
+        routes: Vec<Vec<SwapOperation>>,
+        min_out: Vec<Uint128>,
+        #[serde(default)]
+        vault: Option<String>,
+    },
+    // Returns tokens sent here by mistake.
+    Sweep {
+        denom_or_cw20: DenomOrCw20,
+        amount: Uint128,
+        recipient: String,
+    },
+    // Manager only. Sends reward denom which no vault has recorded, up to the unallocated balance.
+    SweepUnallocated {
+        amount: Uint128,
+        recipient: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetInfo {
+    Token { contract_addr: Addr },
+    NativeToken { denom: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapOperation {
+    NativeSwap {
+        offer_denom: String,
+        ask_denom: String,
+    },
+    AstroSwap {
+        offer_asset_info: AssetInfo,
+        ask_asset_info: AssetInfo,
+    },
+}
+
+impl SwapOperation {
+    pub fn offer_asset_info(&self) -> AssetInfo {
+        match self {
+            SwapOperation::NativeSwap { offer_denom, .. } => AssetInfo::NativeToken {
+                denom: offer_denom.clone(),
+            },
+            SwapOperation::AstroSwap {
+                offer_asset_info, ..
+            } => offer_asset_info.clone(),
+        }
+    }
+
+    pub fn ask_asset_info(&self) -> AssetInfo {
+        match self {
+            SwapOperation::NativeSwap { ask_denom, .. } => AssetInfo::NativeToken {
+                denom: ask_denom.clone(),
+            },
+            SwapOperation::AstroSwap { ask_asset_info, .. } => ask_asset_info.clone(),
+        }
+    }
+}
+
+// Subset of the Astroport router interface used by SwapRewards.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RouterExecuteMsg {
+    ExecuteSwapOperations {
+        operations: Vec<SwapOperation>,
+        minimum_receive: Option<Uint128>,
+        to: Option<String>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -34,6 +140,24 @@ pub enum ExecuteMsg {
 pub enum QueryMsg {
     Config {},
     TmpManagerStore {},
+    Ownership {},
+    ProtocolRevenue {},
+    SwapConfig {},
+    TransferHistory {
+        start_after: Option<u64>,
+        limit: Option<u64>,
+    },
+    Vault {
+        vault: String,
+    },
+    Vaults {
+        start_after: Option<String>,
+        limit: Option<u64>,
+    },
+    SweepHistory {
+        start_after: Option<u64>,
+        limit: Option<u64>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -41,6 +165,40 @@ pub struct GetConfigResponse {
     pub config: Config,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProtocolRevenueResponse {
+    pub protocol_revenue: ProtocolRevenue,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VaultBalance {
+    pub vault: Addr,
+    pub balance: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VaultResponse {
+    pub vault: Addr,
+    pub balance: Uint128,
+    pub unallocated_rewards: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VaultsResponse {
+    pub vaults: Vec<VaultBalance>,
+    pub unallocated_rewards: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TransferHistoryResponse {
+    pub transfers: Vec<TransferRecord>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapConfigResponse {
+    pub swap_config: SwapConfig,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct TmpManagerStoreResponse {
     pub tmp_manager_store: Option<TmpManagerStore>,
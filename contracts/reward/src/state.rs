@@ -1,24 +1,66 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::Addr;
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, Timestamp, Uint128};
+use cw_storage_plus::{Item, Map};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     pub manager: Addr,          // Expect update config to be called from manager.
     pub reward_denom: String,   // Reward denom is expected to be Luna
-    pub staking_contract: Addr, // Expect swap and transfer to be called from pools contract
+    pub staking_contract: Addr, // Default vault. Registered on instantiate and config updates
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
 
-// this is a tmp store to store the intermediate values of manager updates.
-// manager updates are 2 phase, we set it and then accept it. This is done to
-// add a greater assurance of the update.
+// (vault) -> rewards recorded by the vault and not transferred out yet. Only registered vaults have
+// an entry, and a vault can only transfer its own balance.
+pub const VAULT_BALANCES: Map<&Addr, Uint128> = Map::new("vault_balances");
+// Sum of VAULT_BALANCES. Anything above it in the contract balance is unallocated.
+pub const TOTAL_VAULT_BALANCE: Item<Uint128> = Item::new("total_vault_balance");
+
+// Balance before a vault withdraws its rewards here. Whatever arrives until the rewards are recorded
+// in the same transaction is credited to the vault.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardsWithdrawal {
+    pub vault: Addr,
+    pub balance: Uint128,
+}
+
+pub const REWARDS_WITHDRAWAL: Item<RewardsWithdrawal> = Item::new("rewards_withdrawal");
+
+// Lifetime totals of everything sent out by transfer.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct ProtocolRevenue {
+    pub rewards_transferred: Uint128,
+    pub protocol_fees: Uint128,
+    #[serde(default)]
+    pub transfer_count: u64,
+}
+
+pub const PROTOCOL_REVENUE: Item<ProtocolRevenue> = Item::new("protocol_revenue");
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct TmpManagerStore {
-    pub manager: String,
+pub struct TransferRecord {
+    pub id: u64,
+    pub vault: Addr,
+    pub block_height: u64,
+    pub time: Timestamp,
+    pub reward_amount: Uint128,
+    pub protocol_fee_amount: Uint128,
+}
+
+// (transfer id) -> TransferRecord. Ids are the transfer_count at the time of the transfer.
+pub const TRANSFER_HISTORY: Map<u64, TransferRecord> = Map::new("transfer_history");
+
+// Rewards paid out in other denoms are swapped into reward_denom through an Astroport style router.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct SwapConfig {
+    pub router: Option<Addr>,
+    pub allowed_denoms: Vec<String>,
 }
 
-pub const TMP_MANAGER_STORE: Item<TmpManagerStore> = Item::new("tmp_manager_store");
+pub const SWAP_CONFIG: Item<SwapConfig> = Item::new("swap_config");
+
+// Manager updates are 2 phase and live in stader_utils::ownership, shared by all the contracts.
+pub use stader_utils::ownership::{TmpManagerStore, TMP_MANAGER_STORE};
@@ -1,15 +1,27 @@
 #[cfg(test)]
 mod tests {
-    use crate::contract::{execute, instantiate, query};
+    use crate::contract::{execute, instantiate, migrate, query};
     use crate::error::ContractError;
-    use crate::msg::{ExecuteMsg, GetConfigResponse, InstantiateMsg, QueryMsg};
-    use crate::state::{Config, TmpManagerStore, CONFIG, TMP_MANAGER_STORE};
+    use crate::msg::{
+        AssetInfo, ExecuteMsg, GetConfigResponse, InstantiateMsg, MigrateMsg,
+        ProtocolRevenueResponse, QueryMsg, RouterExecuteMsg, SwapConfigResponse, SwapOperation,
+        TransferHistoryResponse, VaultBalance, VaultResponse, VaultsResponse,
+    };
+    use crate::state::{
+        Config, ProtocolRevenue, SwapConfig, TmpManagerStore, TransferRecord, CONFIG,
+        TMP_MANAGER_STORE, VAULT_BALANCES,
+    };
     use cosmwasm_std::testing::{
         mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
     };
     use cosmwasm_std::{
-        from_binary, Addr, BankMsg, Coin, Env, MessageInfo, OwnedDeps, Response, SubMsg, Uint128,
+        attr, from_binary, to_binary, Addr, Attribute, BankMsg, Coin, Decimal, Env, MessageInfo,
+        OwnedDeps, Response, SubMsg, Uint128, WasmMsg,
     };
+    use cw2::set_contract_version;
+    use stader_utils::fee_split::FeeSplitEntry;
+    use stader_utils::ownership::OwnershipResponse;
+    use stader_utils::sweep::{DenomOrCw20, SweepHistoryResponse};
 
     pub fn instantiate_contract(
         deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
@@ -21,7 +33,34 @@ mod tests {
             staking_contract: "pools_addr".to_string(),
         };
 
-        return instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg).unwrap();
+        instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg).unwrap()
+    }
+
+    // Withdraws rewards for the vault, which moves the contract balance to balance_after.
+    fn withdraw_rewards(
+        deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+        env: &Env,
+        vault: &str,
+        balance_after: u128,
+    ) {
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(vault, &[]),
+            ExecuteMsg::BeginRewardsWithdrawal {},
+        )
+        .unwrap();
+        deps.querier.update_balance(
+            env.contract.address.clone(),
+            vec![Coin::new(balance_after, "uluna")],
+        );
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(vault, &[]),
+            ExecuteMsg::RecordRewards {},
+        )
+        .unwrap();
     }
 
     #[test]
@@ -65,6 +104,7 @@ mod tests {
                 reward_withdraw_contract: reward_withdraw_contract.clone(),
                 protocol_fee_amount: Uint128::zero(),
                 protocol_fee_contract: protocol_fee_contract.clone(),
+                protocol_fee_split: vec![],
             },
         )
         .unwrap_err();
@@ -81,6 +121,7 @@ mod tests {
                 reward_withdraw_contract: reward_withdraw_contract.clone(),
                 protocol_fee_amount: Uint128::zero(),
                 protocol_fee_contract: protocol_fee_contract.clone(),
+                protocol_fee_split: vec![],
             },
         )
         .unwrap_err();
@@ -97,11 +138,29 @@ mod tests {
                 reward_withdraw_contract: reward_withdraw_contract.clone(),
                 protocol_fee_amount: Uint128::zero(),
                 protocol_fee_contract: protocol_fee_contract.clone(),
+                protocol_fee_split: vec![],
             },
         )
         .unwrap();
         assert!(res.messages.is_empty());
 
+        // Vaults can only transfer the rewards they recorded.
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("pools_addr", &[]),
+            ExecuteMsg::Transfer {
+                reward_amount: Uint128::new(200),
+                reward_withdraw_contract: reward_withdraw_contract.clone(),
+                protocol_fee_amount: Uint128::new(2),
+                protocol_fee_contract: protocol_fee_contract.clone(),
+                protocol_fee_split: vec![],
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InSufficientFunds {}));
+        withdraw_rewards(&mut deps, &env, "pools_addr", 2407);
+
         let res = execute(
             deps.as_mut(),
             env.clone(),
@@ -111,6 +170,7 @@ mod tests {
                 reward_withdraw_contract: reward_withdraw_contract.clone(),
                 protocol_fee_amount: Uint128::new(2),
                 protocol_fee_contract: protocol_fee_contract.clone(),
+                protocol_fee_split: vec![],
             },
         )
         .unwrap();
@@ -129,6 +189,109 @@ mod tests {
                 amount: vec![Coin::new(2, "uluna")]
             })
         );
+        assert_eq!(res.events[0].ty, "lunax-transfer-rewards");
+        assert_eq!(res.events[0].attributes[0], attr("vault", "pools_addr"));
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("pools_addr", &[]),
+            ExecuteMsg::Transfer {
+                reward_amount: Uint128::new(200),
+                reward_withdraw_contract: reward_withdraw_contract.clone(),
+                protocol_fee_amount: Uint128::new(5),
+                protocol_fee_contract: protocol_fee_contract.clone(),
+                protocol_fee_split: vec![
+                    FeeSplitEntry {
+                        recipient: Addr::unchecked("treasury"),
+                        weight: Decimal::from_ratio(1_u128, 2_u128),
+                    },
+                    FeeSplitEntry {
+                        recipient: Addr::unchecked("insurance_fund"),
+                        weight: Decimal::from_ratio(1_u128, 2_u128),
+                    },
+                ],
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: reward_withdraw_contract.to_string(),
+                    amount: vec![Coin::new(200, "uluna")]
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "treasury".to_string(),
+                    amount: vec![Coin::new(3, "uluna")]
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "insurance_fund".to_string(),
+                    amount: vec![Coin::new(2, "uluna")]
+                }),
+            ]
+        );
+
+        let res: ProtocolRevenueResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::ProtocolRevenue {}).unwrap())
+                .unwrap();
+        assert_eq!(
+            res.protocol_revenue,
+            ProtocolRevenue {
+                rewards_transferred: Uint128::new(400),
+                protocol_fees: Uint128::new(7),
+                transfer_count: 2,
+            }
+        );
+
+        let res: TransferHistoryResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::TransferHistory {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.transfers,
+            vec![
+                TransferRecord {
+                    id: 1,
+                    vault: Addr::unchecked("pools_addr"),
+                    block_height: env.block.height,
+                    time: env.block.time,
+                    reward_amount: Uint128::new(200),
+                    protocol_fee_amount: Uint128::new(2),
+                },
+                TransferRecord {
+                    id: 2,
+                    vault: Addr::unchecked("pools_addr"),
+                    block_height: env.block.height,
+                    time: env.block.time,
+                    reward_amount: Uint128::new(200),
+                    protocol_fee_amount: Uint128::new(5),
+                },
+            ]
+        );
+
+        let res: TransferHistoryResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env,
+                QueryMsg::TransferHistory {
+                    start_after: Some(1),
+                    limit: Some(1),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.transfers.len(), 1);
+        assert_eq!(res.transfers[0].id, 2);
     }
 
     #[test]
@@ -148,6 +311,7 @@ mod tests {
             mock_info("not-creator", &[]),
             ExecuteMsg::SetManager {
                 manager: "test_manager".to_string(),
+                expires_in: None,
             },
         )
         .unwrap_err();
@@ -162,6 +326,7 @@ mod tests {
             mock_info("creator", &[]),
             ExecuteMsg::SetManager {
                 manager: "test_manager".to_string(),
+                expires_in: None,
             },
         )
         .unwrap();
@@ -197,6 +362,7 @@ mod tests {
                 deps.as_mut().storage,
                 &TmpManagerStore {
                     manager: "new_manager".to_string(),
+                    expires_at: None,
                 },
             )
             .unwrap();
@@ -228,6 +394,107 @@ mod tests {
         assert_eq!(tmp_manager_store, None);
     }
 
+    #[test]
+    fn test_cancel_and_renounce_manager() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+
+        instantiate_contract(&mut deps, &info, &env, None);
+
+        /*
+            Pending manager declines the proposal
+        */
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::SetManager {
+                manager: "new_manager".to_string(),
+                expires_in: Some(100),
+            },
+        )
+        .unwrap();
+        let res: OwnershipResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::Ownership {}).unwrap())
+                .unwrap();
+        assert_eq!(
+            res,
+            OwnershipResponse {
+                manager: Addr::unchecked("creator"),
+                pending_manager: Some(TmpManagerStore {
+                    manager: "new_manager".to_string(),
+                    expires_at: Some(env.block.time.seconds() + 100),
+                }),
+            }
+        );
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::CancelManagerUpdate {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("new_manager", &[]),
+            ExecuteMsg::CancelManagerUpdate {},
+        )
+        .unwrap();
+        let tmp_manager_store = TMP_MANAGER_STORE.may_load(deps.as_mut().storage).unwrap();
+        assert_eq!(tmp_manager_store, None);
+
+        /*
+            Expired proposals cannot be accepted
+        */
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::SetManager {
+                manager: "new_manager".to_string(),
+                expires_in: Some(100),
+            },
+        )
+        .unwrap();
+        let mut expired_env = env.clone();
+        expired_env.block.time = expired_env.block.time.plus_seconds(100);
+        let err = execute(
+            deps.as_mut(),
+            expired_env,
+            mock_info("new_manager", &[]),
+            ExecuteMsg::AcceptManager {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ManagerProposalExpired {}));
+
+        /*
+            Renounce
+        */
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("new_manager", &[]),
+            ExecuteMsg::RenounceManager {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::RenounceManager {},
+        )
+        .unwrap();
+        let config = CONFIG.load(deps.as_mut().storage).unwrap();
+        assert_eq!(config.manager, env.contract.address);
+        let tmp_manager_store = TMP_MANAGER_STORE.may_load(deps.as_mut().storage).unwrap();
+        assert_eq!(tmp_manager_store, None);
+    }
+
     #[test]
     fn test_update_config() {
         let mut deps = mock_dependencies();
@@ -287,4 +554,862 @@ mod tests {
         let config = CONFIG.load(deps.as_mut().storage).unwrap();
         assert_eq!(config, expected_config);
     }
+
+    #[test]
+    fn test_swap_rewards() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+        instantiate_contract(&mut deps, &info, &env, None);
+        deps.querier.update_balance(
+            env.contract.address.clone(),
+            vec![Coin::new(100, "uusd"), Coin::new(50, "ukrw")],
+        );
+        let swap_uusd = ExecuteMsg::SwapRewards {
+            denoms: vec!["uusd".to_string()],
+            routes: vec![vec![]],
+            min_out: vec![Uint128::new(90)],
+            vault: None,
+        };
+
+        /*
+           Test - 1. Only the manager configures swaps
+        */
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            swap_uusd.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SwapRouterNotSet {}));
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::UpdateSwapConfig {
+                router: Some("router".to_string()),
+                allowed_denoms: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateSwapConfig {
+                router: None,
+                allowed_denoms: Some(vec!["uluna".to_string()]),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SwapDenomNotAllowed(_)));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateSwapConfig {
+                router: Some("router".to_string()),
+                allowed_denoms: Some(vec!["uusd".to_string(), "ukrw".to_string()]),
+            },
+        )
+        .unwrap();
+        let res: SwapConfigResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::SwapConfig {}).unwrap())
+                .unwrap();
+        assert_eq!(
+            res.swap_config,
+            SwapConfig {
+                router: Some(Addr::unchecked("router")),
+                allowed_denoms: vec!["uusd".to_string(), "ukrw".to_string()],
+            }
+        );
+
+        /*
+           Test - 2. Denoms and routes are validated
+        */
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            swap_uusd.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::SwapRewards {
+                denoms: vec!["ueur".to_string()],
+                routes: vec![vec![]],
+                min_out: vec![Uint128::zero()],
+                vault: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SwapDenomNotAllowed(_)));
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::SwapRewards {
+                denoms: vec!["uusd".to_string()],
+                routes: vec![],
+                min_out: vec![Uint128::zero()],
+                vault: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSwapRoutes {}));
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::SwapRewards {
+                denoms: vec!["ukrw".to_string()],
+                routes: vec![vec![SwapOperation::NativeSwap {
+                    offer_denom: "ukrw".to_string(),
+                    ask_denom: "uusd".to_string(),
+                }]],
+                min_out: vec![Uint128::zero()],
+                vault: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSwapRoutes {}));
+
+        /*
+           Test - 3. Successful swaps through the router
+        */
+        let ukrw_route = vec![
+            SwapOperation::NativeSwap {
+                offer_denom: "ukrw".to_string(),
+                ask_denom: "uusd".to_string(),
+            },
+            SwapOperation::AstroSwap {
+                offer_asset_info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                ask_asset_info: AssetInfo::NativeToken {
+                    denom: "uluna".to_string(),
+                },
+            },
+        ];
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::SwapRewards {
+                denoms: vec!["uusd".to_string(), "ukrw".to_string()],
+                routes: vec![vec![], ukrw_route.clone()],
+                min_out: vec![Uint128::new(90), Uint128::new(40)],
+                vault: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(WasmMsg::Execute {
+                    contract_addr: "router".to_string(),
+                    msg: to_binary(&RouterExecuteMsg::ExecuteSwapOperations {
+                        operations: vec![SwapOperation::NativeSwap {
+                            offer_denom: "uusd".to_string(),
+                            ask_denom: "uluna".to_string(),
+                        }],
+                        minimum_receive: Some(Uint128::new(90)),
+                        to: None,
+                    })
+                    .unwrap(),
+                    funds: vec![Coin::new(100, "uusd")],
+                }),
+                SubMsg::new(WasmMsg::Execute {
+                    contract_addr: "router".to_string(),
+                    msg: to_binary(&RouterExecuteMsg::ExecuteSwapOperations {
+                        operations: ukrw_route,
+                        minimum_receive: Some(Uint128::new(40)),
+                        to: None,
+                    })
+                    .unwrap(),
+                    funds: vec![Coin::new(50, "ukrw")],
+                }),
+                SubMsg::new(WasmMsg::Execute {
+                    contract_addr: env.contract.address.to_string(),
+                    msg: to_binary(&ExecuteMsg::RecordRewards {}).unwrap(),
+                    funds: vec![],
+                }),
+            ]
+        );
+
+        /*
+           Test - 4. The swap output is recorded for the vault and can be transferred by it
+        */
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("pools_addr", &[]),
+            ExecuteMsg::BeginRewardsWithdrawal {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::RewardsWithdrawalInProgress {}));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::RecordRewards {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        deps.querier
+            .update_balance(env.contract.address.clone(), vec![Coin::new(140, "uluna")]);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(env.contract.address.as_str(), &[]),
+            ExecuteMsg::RecordRewards {},
+        )
+        .unwrap();
+        let res: VaultResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Vault {
+                    vault: "pools_addr".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.balance, Uint128::new(140));
+        assert_eq!(res.unallocated_rewards, Uint128::zero());
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("pools_addr", &[]),
+            ExecuteMsg::Transfer {
+                reward_amount: Uint128::new(140),
+                reward_withdraw_contract: Addr::unchecked("pools_addr"),
+                protocol_fee_amount: Uint128::zero(),
+                protocol_fee_contract: Addr::unchecked("protocol_fee_contract"),
+                protocol_fee_split: vec![],
+            },
+        )
+        .unwrap();
+
+        /*
+           Test - 5. The manager swaps for a registered vault, vaults cannot swap
+        */
+        deps.querier.update_balance(
+            env.contract.address.clone(),
+            vec![Coin::new(100, "uusd"), Coin::new(140, "uluna")],
+        );
+        let swap_uusd_for = |vault: &str| ExecuteMsg::SwapRewards {
+            denoms: vec!["uusd".to_string()],
+            routes: vec![vec![]],
+            min_out: vec![Uint128::new(90)],
+            vault: Some(vault.to_string()),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            swap_uusd_for("vault2"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::VaultNotRegistered {}));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::RegisterVault {
+                vault: "vault2".to_string(),
+            },
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("pools_addr", &[]),
+            swap_uusd_for("pools_addr"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("vault2", &[]),
+            swap_uusd_for("vault2"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            swap_uusd_for("vault2"),
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 2);
+        deps.querier
+            .update_balance(env.contract.address.clone(), vec![Coin::new(230, "uluna")]);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(env.contract.address.as_str(), &[]),
+            ExecuteMsg::RecordRewards {},
+        )
+        .unwrap();
+        let res: VaultResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Vault {
+                    vault: "vault2".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.balance, Uint128::new(90));
+    }
+
+    #[test]
+    fn test_vaults() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+        instantiate_contract(&mut deps, &info, &env, None);
+        deps.querier
+            .update_balance(env.contract.address.clone(), vec![Coin::new(100, "uluna")]);
+
+        /*
+           Test - 1. Only the manager registers vaults
+        */
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::RegisterVault {
+                vault: "vault2".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("vault2", &[]),
+            ExecuteMsg::BeginRewardsWithdrawal {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::RegisterVault {
+                vault: "vault2".to_string(),
+            },
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::RegisterVault {
+                vault: "vault2".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::VaultAlreadyRegistered {}));
+
+        /*
+           Test - 2. Vaults are credited what arrives during their withdrawal and spend only their
+           own balance
+        */
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("pools_addr", &[]),
+            ExecuteMsg::RecordRewards {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoRewardsWithdrawal {}));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("pools_addr", &[]),
+            ExecuteMsg::BeginRewardsWithdrawal {},
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("vault2", &[]),
+            ExecuteMsg::BeginRewardsWithdrawal {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::RewardsWithdrawalInProgress {}));
+        deps.querier
+            .update_balance(env.contract.address.clone(), vec![Coin::new(700, "uluna")]);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("vault2", &[]),
+            ExecuteMsg::RecordRewards {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("pools_addr", &[]),
+            ExecuteMsg::RecordRewards {},
+        )
+        .unwrap();
+        assert_eq!(res.events[0].attributes[1], Attribute::new("amount", "600"));
+        withdraw_rewards(&mut deps, &env, "vault2", 1000);
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("vault2", &[]),
+            ExecuteMsg::Transfer {
+                reward_amount: Uint128::new(400),
+                reward_withdraw_contract: Addr::unchecked("vault2"),
+                protocol_fee_amount: Uint128::zero(),
+                protocol_fee_contract: Addr::unchecked("protocol_fee_contract"),
+                protocol_fee_split: vec![],
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InSufficientFunds {}));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("vault2", &[]),
+            ExecuteMsg::Transfer {
+                reward_amount: Uint128::new(290),
+                reward_withdraw_contract: Addr::unchecked("vault2"),
+                protocol_fee_amount: Uint128::new(10),
+                protocol_fee_contract: Addr::unchecked("protocol_fee_contract"),
+                protocol_fee_split: vec![],
+            },
+        )
+        .unwrap();
+        deps.querier
+            .update_balance(env.contract.address.clone(), vec![Coin::new(700, "uluna")]);
+
+        let res: VaultsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Vaults {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            res,
+            VaultsResponse {
+                vaults: vec![
+                    VaultBalance {
+                        vault: Addr::unchecked("pools_addr"),
+                        balance: Uint128::new(600),
+                    },
+                    VaultBalance {
+                        vault: Addr::unchecked("vault2"),
+                        balance: Uint128::zero(),
+                    },
+                ],
+                unallocated_rewards: Uint128::new(100),
+            }
+        );
+
+        /*
+           Test - 3. Unallocated funds are swept without counting as a transfer
+        */
+        for sender in ["other", "pools_addr"] {
+            let err = execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(sender, &[]),
+                ExecuteMsg::SweepUnallocated {
+                    amount: Uint128::new(100),
+                    recipient: "treasury".to_string(),
+                },
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::Unauthorized {}));
+        }
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::SweepUnallocated {
+                amount: Uint128::new(101),
+                recipient: "treasury".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InSufficientFunds {}));
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::SweepUnallocated {
+                amount: Uint128::new(100),
+                recipient: "treasury".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "treasury".to_string(),
+                amount: vec![Coin::new(100, "uluna")]
+            })]
+        );
+        let res: ProtocolRevenueResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::ProtocolRevenue {}).unwrap())
+                .unwrap();
+        assert_eq!(res.protocol_revenue.protocol_fees, Uint128::new(10));
+        assert_eq!(res.protocol_revenue.transfer_count, 1);
+
+        /*
+           Test - 4. Deregistered balances become unallocated
+        */
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::DeregisterVault {
+                vault: "pools_addr".to_string(),
+            },
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::DeregisterVault {
+                vault: "pools_addr".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::VaultNotRegistered {}));
+
+        let res: VaultsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Vaults {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.vaults.len(), 1);
+        assert_eq!(res.unallocated_rewards, Uint128::new(700));
+
+        /*
+           Test - 5. The manager clears a withdrawal which was never recorded
+        */
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::ClearRewardsWithdrawal {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoRewardsWithdrawal {}));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::RegisterVault {
+                vault: "vault3".to_string(),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("vault2", &[]),
+            ExecuteMsg::BeginRewardsWithdrawal {},
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("vault3", &[]),
+            ExecuteMsg::BeginRewardsWithdrawal {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::RewardsWithdrawalInProgress {}));
+
+        for sender in ["vault2", "vault3"] {
+            let err = execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(sender, &[]),
+                ExecuteMsg::ClearRewardsWithdrawal {},
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::Unauthorized {}));
+        }
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::ClearRewardsWithdrawal {},
+        )
+        .unwrap();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("vault3", &[]),
+            ExecuteMsg::BeginRewardsWithdrawal {},
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("vault2", &[]),
+            ExecuteMsg::RecordRewards {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("vault3", &[]),
+            ExecuteMsg::RecordRewards {},
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_sweep() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+        instantiate_contract(&mut deps, &info, &env, None);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateSwapConfig {
+                router: Some("router".to_string()),
+                allowed_denoms: Some(vec!["uusd".to_string()]),
+            },
+        )
+        .unwrap();
+
+        /*
+           Test - 1. Only the manager sweeps, and never the reward or swappable denoms
+        */
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("pools_addr", &[]),
+            ExecuteMsg::Sweep {
+                denom_or_cw20: DenomOrCw20::Denom("ukrw".to_string()),
+                amount: Uint128::new(100),
+                recipient: "user".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        for denom in ["uluna", "uusd"] {
+            let err = execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("creator", &[]),
+                ExecuteMsg::Sweep {
+                    denom_or_cw20: DenomOrCw20::Denom(denom.to_string()),
+                    amount: Uint128::new(100),
+                    recipient: "user".to_string(),
+                },
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::SweepReservedAsset {}));
+        }
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::Sweep {
+                denom_or_cw20: DenomOrCw20::Denom("ukrw".to_string()),
+                amount: Uint128::zero(),
+                recipient: "user".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ZeroAmount {}));
+
+        /*
+           Test - 2. Successful sweep
+        */
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::Sweep {
+                denom_or_cw20: DenomOrCw20::Denom("ukrw".to_string()),
+                amount: Uint128::new(100),
+                recipient: "user".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "user".to_string(),
+                amount: vec![Coin::new(100, "ukrw")]
+            })]
+        );
+
+        let res: SweepHistoryResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env,
+                QueryMsg::SweepHistory {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.sweeps.len(), 1);
+        assert_eq!(res.sweeps[0].asset, DenomOrCw20::Denom("ukrw".to_string()));
+    }
+
+    #[test]
+    fn test_migrate() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+        instantiate_contract(&mut deps, &info, &env, None);
+
+        /*
+           Test - 1. Swap fields can be set on migration, but never for the reward denom
+        */
+        let err = migrate(
+            deps.as_mut(),
+            env.clone(),
+            MigrateMsg {
+                swap_router: None,
+                allowed_swap_denoms: Some(vec!["uluna".to_string()]),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SwapDenomNotAllowed(_)));
+
+        migrate(
+            deps.as_mut(),
+            env.clone(),
+            MigrateMsg {
+                swap_router: Some("router".to_string()),
+                allowed_swap_denoms: Some(vec!["uusd".to_string()]),
+            },
+        )
+        .unwrap();
+        let res: SwapConfigResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::SwapConfig {}).unwrap())
+                .unwrap();
+        assert_eq!(
+            res.swap_config,
+            SwapConfig {
+                router: Some(Addr::unchecked("router")),
+                allowed_denoms: vec!["uusd".to_string()],
+            }
+        );
+
+        /*
+           Test - 2. Contracts from before vault balances credit their balance to the staking contract
+        */
+        let no_params = MigrateMsg {
+            swap_router: None,
+            allowed_swap_denoms: None,
+        };
+        set_contract_version(deps.as_mut().storage, "reward", "0.1.0").unwrap();
+        VAULT_BALANCES.remove(deps.as_mut().storage, &Addr::unchecked("pools_addr"));
+        deps.querier
+            .update_balance(env.contract.address.clone(), vec![Coin::new(500, "uluna")]);
+        let res = migrate(deps.as_mut(), env.clone(), no_params.clone()).unwrap();
+        assert_eq!(
+            res.events[0].attributes,
+            vec![
+                Attribute::new("from_version", "0.1.0"),
+                Attribute::new("to_version", "0.2.0"),
+            ]
+        );
+        let res: VaultResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Vault {
+                    vault: "pools_addr".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.balance, Uint128::new(500));
+        assert_eq!(res.unallocated_rewards, Uint128::zero());
+
+        // Migrating from the current version leaves vault balances alone.
+        deps.querier
+            .update_balance(env.contract.address.clone(), vec![Coin::new(700, "uluna")]);
+        migrate(deps.as_mut(), env.clone(), no_params.clone()).unwrap();
+        let res: VaultResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Vault {
+                    vault: "pools_addr".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.balance, Uint128::new(500));
+        assert_eq!(res.unallocated_rewards, Uint128::new(200));
+
+        /*
+           Test - 3. Downgrades and other contracts are refused
+        */
+        set_contract_version(deps.as_mut().storage, "reward", "9.0.0").unwrap();
+        let err = migrate(deps.as_mut(), env.clone(), no_params.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::MigrationDowngrade(_, _)));
+
+        set_contract_version(deps.as_mut().storage, "staking", "0.1.0").unwrap();
+        let err = migrate(deps.as_mut(), env, no_params).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMigrationContract(_)));
+    }
 }
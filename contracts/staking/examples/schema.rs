@@ -2,6 +2,7 @@ use std::env::current_dir;
 use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
+use stader_utils::ownership::OwnershipResponse;
 use staking::msg::*;
 use staking::state::*;
 
@@ -24,4 +25,5 @@ fn main() {
     export_schema(&schema_for!(GetFundsClaimRecord), &out_dir);
     export_schema(&schema_for!(UserInfoResponse), &out_dir);
     export_schema(&schema_for!(TmpManagerStoreResponse), &out_dir);
+    export_schema(&schema_for!(OwnershipResponse), &out_dir);
 }
@@ -11,3 +11,27 @@ pub fn get_withdraw_fee_cap() -> Decimal {
 pub fn get_reward_fee_cap() -> Decimal {
     Decimal::from_ratio(10_u128, 100_u128)
 }
+
+pub fn get_max_config_timelock() -> u64 {
+    30 * 24 * 3600
+}
+
+pub fn get_max_reinvest_split_limit() -> u64 {
+    10
+}
+
+pub fn get_max_reward_vesting_period() -> u64 {
+    7 * 24 * 3600
+}
+
+pub fn get_min_holding_period_cap() -> u64 {
+    7 * 24 * 3600
+}
+
+pub fn get_revenue_epoch_length() -> u64 {
+    24 * 3600
+}
+
+pub fn get_seconds_per_year() -> u64 {
+    365 * 24 * 3600
+}
@@ -1,30 +1,54 @@
-use crate::constants::{get_deposit_fee_cap, get_reward_fee_cap, get_withdraw_fee_cap};
+use crate::constants::{
+    get_deposit_fee_cap, get_max_config_timelock, get_revenue_epoch_length, get_reward_fee_cap,
+    get_withdraw_fee_cap,
+};
 use crate::helpers::{
-    burn_minted_tokens, calculate_exchange_rate, create_mint_message,
+    add_unvested_rewards, burn_minted_tokens, calculate_annualized_rate,
+    calculate_vested_exchange_rate, clear_expired_pauses, create_mint_message,
     create_new_undelegation_batch, decrease_tracked_stake, get_active_validators_sorted_by_stake,
-    get_airdrop_contracts, get_total_token_supply, get_user_balance, get_validator_for_deposit,
-    increase_tracked_stake, validate, validate_max_deposit, validate_min_deposit,
-    validate_unbonding_period, validate_undelegation_cooldown, Verify,
+    get_airdrop_contracts, get_effective_operation_controls, get_holding_period_end,
+    get_next_gross_exchange_rate, get_operation_flag, get_outstanding_unbonding,
+    get_reinvest_split, get_reinvest_split_limit, get_reward_surplus, get_reward_vault,
+    get_reward_vesting_period, get_total_token_supply, get_user_balance, get_validator_for_deposit,
+    get_withdraw_fee, has_role, increase_tracked_stake, record_audit_log,
+    record_audit_log_with_reason, record_pause, record_protocol_revenue, record_user_deposit,
+    record_user_outflow, to_audit_value, track_pending_rewards, validate, validate_max_deposit,
+    validate_min_deposit, validate_min_holding_period, validate_recovery,
+    validate_reinvest_split_limit, validate_reward_vesting_period, validate_role,
+    validate_unbonding_period, validate_undelegation_cooldown, validate_withdraw_fee_schedule,
+    vest_rewards, wrap_rewards_withdrawal, Verify,
 };
 use crate::msg::{
-    Cw20HookMsg, ExecuteMsg, GetFundsClaimRecord, GetFundsDepositRecord, GetValMetaResponse,
-    InstantiateMsg, MerkleAirdropMsg, MigrateMsg, QueryBatchUndelegationResponse,
-    QueryConfigResponse, QueryMsg, QueryStateResponse, TmpManagerStoreResponse, UserInfoResponse,
-    UserQueryInfo,
+    ActivePause, ActivePausesResponse, AprResponse, AuditLogResponse, BatchUserTokens,
+    CheckInvariantsResponse, CircuitBreakerResponse, Cw20HookMsg, EpochRevenue,
+    ExchangeRateHistoryResponse, ExecuteMsg, FeeSplitResponse, GetFundsClaimRecord,
+    GetFundsDepositRecord, GetValMetaResponse, HasRoleResponse, InflowTrackerResponse,
+    InstantiateMsg, InvariantViolation, MerkleAirdropMsg, MigrateMsg, PauseHistoryResponse,
+    PendingConfigChangesResponse, PendingRewardsResponse, ProtocolRevenueHistoryResponse,
+    ProtocolRevenueResponse, QueryBatchUndelegationResponse, QueryConfigResponse, QueryMsg,
+    QueryStateResponse, ReinvestSplitLimitResponse, RewardVestingResponse, RoleMembersResponse,
+    TmpManagerStoreResponse, UserInfoResponse, UserQueryInfo, ValidatorPendingRewards,
 };
 use crate::state::{
-    AirdropRate, Config, ConfigUpdateRequest, OperationControls, OperationControlsUpdateRequest,
-    State, TmpManagerStore, UndelegationInfo, VMeta, BATCH_UNDELEGATION_REGISTRY, CONFIG,
-    OPERATION_CONTROLS, STATE, TMP_MANAGER_STORE, USERS, VALIDATOR_META,
+    AirdropRate, AuditChange, AuditLogEntry, CircuitBreakerConfig, CircuitBreakerTrip, Config,
+    ConfigUpdateRequest, ExchangeRateSnapshot, InflowTracker, OperationControls,
+    OperationControlsUpdateRequest, PauseRecord, PendingConfigChange, ProtocolRevenue, Role, State,
+    UndelegationInfo, VMeta, WithdrawFeeSchedule, ACTIVE_PAUSES, AUDIT_LOG,
+    BATCH_UNDELEGATION_REGISTRY, CIRCUIT_BREAKER_CONFIG, CIRCUIT_BREAKER_TRIP, CONFIG,
+    CONFIG_TIMELOCK, EXCHANGE_RATE_SNAPSHOTS, FEE_SPLIT, INFLOW_TRACKER, MIN_HOLDING_PERIOD,
+    OPERATION_CONTROLS, PAUSE_HISTORY, PENDING_CONFIG_CHANGES, PENDING_CONFIG_CHANGE_COUNT,
+    PROTOCOL_REVENUE, PROTOCOL_REVENUE_EPOCHS, REINVEST_SPLIT_LIMIT, REWARD_VESTING_PERIOD,
+    ROLE_MEMBERS, STATE, TMP_MANAGER_STORE, USERS, USER_DEPOSITS, USER_HOLDING_PERIODS,
+    VALIDATOR_META, WITHDRAW_FEE_SCHEDULE,
 };
 use crate::ContractError;
 use airdrops_registry::msg::GetAirdropContractsResponse;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    from_binary, to_binary, Addr, Attribute, BankMsg, Binary, Coin, Decimal, Delegation, Deps,
-    DepsMut, DistributionMsg, Env, MessageInfo, Order, Response, StakingMsg, StdError, StdResult,
-    Storage, SubMsg, Uint128, WasmMsg,
+    from_binary, to_binary, Addr, Attribute, BankMsg, Binary, Coin, CosmosMsg, Decimal, Delegation,
+    Deps, DepsMut, DistributionMsg, Env, Event, MessageInfo, Order, Reply, ReplyOn, Response,
+    StakingMsg, StdError, StdResult, Storage, SubMsg, Uint128, WasmMsg,
 };
 use cw2::set_contract_version;
 use cw20::Cw20ReceiveMsg;
@@ -32,14 +56,21 @@ use cw20_base::msg::ExecuteMsg as Cw20ExecuteMsg;
 use cw_storage_plus::Bound;
 use reward::msg::ExecuteMsg as RewardExecuteMsg;
 use stader_utils::coin_utils::{
-    decimal_division, decimal_multiplication, get_decimal_from_uint128, multiply_u128_with_decimal,
-    uint128_from_decimal,
+    decimal_division, decimal_multiplication, decimal_subtraction, get_decimal_from_uint128,
+    multiply_u128_with_decimal, uint128_from_decimal,
 };
+use stader_utils::events::{lunax_event, LunaxEventExt};
+use stader_utils::fee_split::{fee_split_msgs, is_valid_fee_split, FeeSplitEntry};
+use stader_utils::migration::{needs_migration, start_migration};
+use stader_utils::ownership::{self, OwnershipResponse};
+use std::collections::BTreeMap;
 use std::ops::{Deref, Mul};
 
 const CONTRACT_NAME: &str = "staking";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+const AUTO_COMPOUND_REPLY_ID: u64 = 1;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -108,6 +139,9 @@ pub fn instantiate(
         last_reinvest_time: env.block.time.minus_seconds(msg.reinvest_cooldown),
         validators: vec![],
         reconciled_funds_to_withdraw: Uint128::zero(),
+        unvested_rewards: Uint128::zero(),
+        rewards_vesting_start: env.block.time,
+        rewards_vesting_end: env.block.time,
     };
     STATE.save(deps.storage, &state)?;
 
@@ -135,24 +169,74 @@ pub fn instantiate(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let from_version = start_migration(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // Transforms run oldest first and must be safe to re-run. Each one is gated on the last version
+    // without the state it sets up.
+    if needs_migration(&from_version, "0.1.0")? {
+        // Rewards the reward contract holds for this vault before inflow tracking existed are all
+        // withdrawn rewards. The reward contract has to be migrated first.
+        if INFLOW_TRACKER.may_load(deps.storage)?.is_none() {
+            let config = CONFIG.load(deps.storage)?;
+            let reward_vault =
+                get_reward_vault(deps.querier, &config.reward_contract, &env.contract.address)?;
+            INFLOW_TRACKER.save(
+                deps.storage,
+                &InflowTracker {
+                    expected_rewards: reward_vault.balance,
+                    quarantined_funds: Uint128::zero(),
+                    total_donations: Uint128::zero(),
+                },
+            )?;
+        }
 
-    OPERATION_CONTROLS.save(
-        deps.storage,
-        &OperationControls {
-            deposit_paused: false,
-            queue_undelegate_paused: false,
-            undelegate_paused: false,
-            withdraw_paused: false,
-            reinvest_paused: false,
-            reconcile_paused: false,
-            claim_airdrops_paused: false,
-            redeem_rewards_paused: false,
-        },
-    )?;
+        // Re-save so controls added since the stored version default to unpaused while existing
+        // pauses are kept.
+        let operation_controls = OPERATION_CONTROLS
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        OPERATION_CONTROLS.save(deps.storage, &operation_controls)?;
+    }
+
+    if let Some(config_timelock) = msg.config_timelock {
+        if config_timelock > get_max_config_timelock() {
+            return Err(ContractError::InvalidConfigTimelock {});
+        }
+        CONFIG_TIMELOCK.save(deps.storage, &config_timelock)?;
+    }
+    if let Some(circuit_breaker_config) = msg.circuit_breaker_config {
+        if let Some(max_drop) = circuit_breaker_config.max_exchange_rate_drop {
+            if max_drop.ge(&Decimal::one()) {
+                return Err(ContractError::InvalidCircuitBreakerThreshold {});
+            }
+        }
+        CIRCUIT_BREAKER_CONFIG.save(deps.storage, &circuit_breaker_config)?;
+    }
+    if let Some(reinvest_split_limit) = msg.reinvest_split_limit {
+        if !validate_reinvest_split_limit(reinvest_split_limit) {
+            return Err(ContractError::InvalidReinvestSplitLimit {});
+        }
+        REINVEST_SPLIT_LIMIT.save(deps.storage, &reinvest_split_limit)?;
+    }
+    if let Some(reward_vesting_period) = msg.reward_vesting_period {
+        if !validate_reward_vesting_period(reward_vesting_period) {
+            return Err(ContractError::InvalidRewardVestingPeriod {});
+        }
+        REWARD_VESTING_PERIOD.save(deps.storage, &reward_vesting_period)?;
+    }
+    if let Some(min_holding_period) = msg.min_holding_period {
+        if !validate_min_holding_period(min_holding_period) {
+            return Err(ContractError::InvalidMinHoldingPeriod {});
+        }
+        MIN_HOLDING_PERIOD.save(deps.storage, &min_holding_period)?;
+    }
 
-    Ok(Response::default())
+    Ok(Response::new().add_event(
+        lunax_event("migrate")
+            .add_attribute("from_version", from_version.to_string())
+            .add_attribute("to_version", CONTRACT_VERSION),
+    ))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -162,6 +246,8 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    clear_expired_pauses(deps.storage, &env)?;
+
     match msg {
         ExecuteMsg::AddValidator { val_addr } => add_validator(deps, info, env, val_addr),
         ExecuteMsg::RemoveValidator {
@@ -176,6 +262,7 @@ pub fn execute(
         ExecuteMsg::Deposit {} => deposit(deps, info, env),
         ExecuteMsg::RedeemRewards { validators } => redeem_rewards(deps, info, env, validators),
         ExecuteMsg::Reinvest {} => reinvest(deps, info, env),
+        ExecuteMsg::AutoCompound {} => auto_compound(deps, info, env),
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
         ExecuteMsg::Undelegate {} => undelegate_stake(deps, info, env),
         ExecuteMsg::ReconcileFunds {} => reconcile_funds(deps, info, env),
@@ -189,8 +276,47 @@ pub fn execute(
         ExecuteMsg::UpdateOperationFlags {
             operation_controls_update_request,
         } => update_operation_flags(deps, info, env, operation_controls_update_request),
-        ExecuteMsg::SetManager { manager } => set_manager(deps, info, env, manager),
+        ExecuteMsg::SetManager {
+            manager,
+            expires_in,
+        } => set_manager(deps, info, env, manager, expires_in),
         ExecuteMsg::AcceptManager {} => accept_manager(deps, info, env),
+        ExecuteMsg::CancelManagerUpdate {} => cancel_manager_update(deps, info, env),
+        ExecuteMsg::RenounceManager {} => renounce_manager(deps, info, env),
+        ExecuteMsg::UpdateCircuitBreaker {
+            circuit_breaker_config,
+        } => update_circuit_breaker(deps, info, env, circuit_breaker_config),
+        ExecuteMsg::ResetCircuitBreaker {} => reset_circuit_breaker(deps, info, env),
+        ExecuteMsg::Donate {} => donate(deps, info, env),
+        ExecuteMsg::SweepQuarantinedFunds { recipient } => {
+            sweep_quarantined_funds(deps, info, env, recipient)
+        }
+        ExecuteMsg::UpdateFeeSplit { fee_split } => update_fee_split(deps, info, env, fee_split),
+        ExecuteMsg::ExecutePendingConfig {} => execute_pending_config(deps, info, env),
+        ExecuteMsg::CancelPendingConfig { id } => cancel_pending_config(deps, info, env, id),
+        ExecuteMsg::GrantRole { role, address } => grant_role(deps, info, env, role, address),
+        ExecuteMsg::RevokeRole { role, address } => revoke_role(deps, info, env, role, address),
+        ExecuteMsg::PauseOperations {
+            operations,
+            reason,
+            expires_at,
+        } => pause_operations(deps, info, env, operations, reason, expires_at),
+        ExecuteMsg::OverrideValidatorMeta {
+            val_addr,
+            staked,
+            slashed,
+            filled,
+            reason,
+        } => override_validator_meta(deps, info, env, val_addr, staked, slashed, filled, reason),
+        ExecuteMsg::ForceReconcileBatch {
+            batch_id,
+            received_amount,
+            reason,
+        } => force_reconcile_batch(deps, info, env, batch_id, received_amount, reason),
+        ExecuteMsg::ResetReconciledFunds {
+            reconciled_funds_to_withdraw,
+            reason,
+        } => reset_reconciled_funds(deps, info, env, reconciled_funds_to_withdraw, reason),
     }
 }
 
@@ -199,40 +325,94 @@ pub fn set_manager(
     info: MessageInfo,
     env: Env,
     manager: String,
+    expires_in: Option<u64>,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    validate(&config, &info, &env, vec![Verify::SenderManager])?;
 
-    TMP_MANAGER_STORE.save(deps.storage, &TmpManagerStore { manager: manager })?;
+    let event = ownership::propose_manager(
+        deps.storage,
+        deps.api,
+        &env,
+        &info.sender,
+        &config.manager,
+        manager.clone(),
+        expires_in,
+    )?;
+    record_audit_log(
+        deps.storage,
+        &env,
+        &info.sender,
+        "set_manager",
+        vec![AuditChange::new("pending_manager", "", manager)],
+    )?;
 
-    Ok(Response::default())
+    Ok(Response::new().add_event(event))
 }
 
 pub fn accept_manager(
     deps: DepsMut,
     info: MessageInfo,
-    _env: Env,
+    env: Env,
 ) -> Result<Response, ContractError> {
     let mut config = CONFIG.load(deps.storage)?;
 
-    let tmp_manager_store =
-        if let Some(tmp_manager_store) = TMP_MANAGER_STORE.may_load(deps.storage)? {
-            tmp_manager_store
-        } else {
-            return Err(ContractError::TmpManagerStoreEmpty {});
-        };
+    let (manager, event) = ownership::accept_manager(deps.storage, deps.api, &env, &info.sender)?;
+    record_audit_log(
+        deps.storage,
+        &env,
+        &info.sender,
+        "accept_manager",
+        vec![AuditChange::new("manager", &config.manager, &manager)],
+    )?;
+    config.manager = manager;
+    CONFIG.save(deps.storage, &config)?;
 
-    let manager = deps.api.addr_validate(tmp_manager_store.manager.as_str())?;
-    if info.sender != manager {
-        return Err(ContractError::Unauthorized {});
-    }
+    Ok(Response::new().add_event(event))
+}
 
-    config.manager = manager;
-    TMP_MANAGER_STORE.remove(deps.storage);
+pub fn cancel_manager_update(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let pending_manager = TMP_MANAGER_STORE
+        .may_load(deps.storage)?
+        .map(|x| x.manager)
+        .unwrap_or_default();
+
+    let event = ownership::cancel_manager_proposal(deps.storage, &info.sender, &config.manager)?;
+    record_audit_log(
+        deps.storage,
+        &env,
+        &info.sender,
+        "cancel_manager_update",
+        vec![AuditChange::new("pending_manager", pending_manager, "")],
+    )?;
+
+    Ok(Response::new().add_event(event))
+}
+
+pub fn renounce_manager(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
 
+    let (manager, event) =
+        ownership::renounce_manager(deps.storage, &env, &info.sender, &config.manager)?;
+    record_audit_log(
+        deps.storage,
+        &env,
+        &info.sender,
+        "renounce_manager",
+        vec![AuditChange::new("manager", &config.manager, &manager)],
+    )?;
+    config.manager = manager;
     CONFIG.save(deps.storage, &config)?;
 
-    Ok(Response::default())
+    Ok(Response::new().add_event(event))
 }
 
 pub fn update_operation_flags(
@@ -242,117 +422,767 @@ pub fn update_operation_flags(
     operation_controls_update_request: OperationControlsUpdateRequest,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    validate(&config, &info, &env, vec![Verify::SenderManager])?;
+    validate_role(deps.storage, &config, &info, Role::Pauser)?;
     let mut operation_controls = OPERATION_CONTROLS.load(deps.storage)?;
 
-    operation_controls.deposit_paused = operation_controls_update_request
-        .deposit_paused
-        .unwrap_or(operation_controls.deposit_paused);
-    operation_controls.withdraw_paused = operation_controls_update_request
-        .withdraw_paused
-        .unwrap_or(operation_controls.withdraw_paused);
-    operation_controls.reconcile_paused = operation_controls_update_request
-        .reconcile_paused
-        .unwrap_or(operation_controls.reconcile_paused);
-    operation_controls.undelegate_paused = operation_controls_update_request
-        .undelegate_paused
-        .unwrap_or(operation_controls.undelegate_paused);
-    operation_controls.queue_undelegate_paused = operation_controls_update_request
-        .queue_undelegate_paused
-        .unwrap_or(operation_controls.queue_undelegate_paused);
-    operation_controls.reinvest_paused = operation_controls_update_request
-        .reinvest_paused
-        .unwrap_or(operation_controls.reinvest_paused);
-    operation_controls.redeem_rewards_paused = operation_controls_update_request
-        .redeem_rewards_paused
-        .unwrap_or(operation_controls.redeem_rewards_paused);
-    operation_controls.claim_airdrops_paused = operation_controls_update_request
-        .claim_airdrops_paused
-        .unwrap_or(operation_controls.claim_airdrops_paused);
+    let updates = [
+        ("deposit", operation_controls_update_request.deposit_paused),
+        (
+            "queue_undelegate",
+            operation_controls_update_request.queue_undelegate_paused,
+        ),
+        (
+            "undelegate",
+            operation_controls_update_request.undelegate_paused,
+        ),
+        (
+            "withdraw",
+            operation_controls_update_request.withdraw_paused,
+        ),
+        (
+            "reinvest",
+            operation_controls_update_request.reinvest_paused,
+        ),
+        (
+            "reconcile",
+            operation_controls_update_request.reconcile_paused,
+        ),
+        (
+            "claim_airdrops",
+            operation_controls_update_request.claim_airdrops_paused,
+        ),
+        (
+            "redeem_rewards",
+            operation_controls_update_request.redeem_rewards_paused,
+        ),
+    ];
+    let mut event = lunax_event("update-operation-flags");
+    let mut changes = vec![];
+    for (operation, paused) in updates {
+        if let Some(paused) = paused {
+            let flag = get_operation_flag(&mut operation_controls, operation)?;
+            changes.push(AuditChange::new(
+                &format!("{}_paused", operation),
+                *flag,
+                paused,
+            ));
+            *flag = paused;
+            // Explicit updates replace any recorded pause on the operation.
+            ACTIVE_PAUSES.remove(deps.storage, operation);
+            event = event.add_attribute(operation, paused.to_string());
+        }
+    }
     OPERATION_CONTROLS.save(deps.storage, &operation_controls)?;
+    record_audit_log(
+        deps.storage,
+        &env,
+        &info.sender,
+        "update_operation_flags",
+        changes,
+    )?;
 
-    Ok(Response::default())
+    Ok(Response::new().add_event(event))
 }
 
-pub fn update_config(
+// Guardians can pause with a reason and an optional expiry, but only pausers can unpause.
+pub fn pause_operations(
     deps: DepsMut,
     info: MessageInfo,
     env: Env,
-    update_config: ConfigUpdateRequest,
+    operations: Vec<String>,
+    reason: Option<String>,
+    expires_at: Option<u64>,
 ) -> Result<Response, ContractError> {
-    let mut config = CONFIG.load(deps.storage)?;
+    let config = CONFIG.load(deps.storage)?;
+    if !has_role(deps.storage, &config, &info.sender, Role::Guardian)?
+        && !has_role(deps.storage, &config, &info.sender, Role::Pauser)?
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if operations.is_empty() {
+        return Err(ContractError::NoOperationsToPause {});
+    }
+    if let Some(expires_at) = expires_at {
+        if expires_at <= env.block.time.seconds() {
+            return Err(ContractError::InvalidPauseExpiry {});
+        }
+    }
+
+    let pause = record_pause(
+        deps.storage,
+        &env,
+        &info.sender,
+        operations,
+        reason,
+        expires_at,
+    )?;
+    record_audit_log_with_reason(
+        deps.storage,
+        &env,
+        &info.sender,
+        "pause_operations",
+        vec![AuditChange::new(
+            "paused_operations",
+            "",
+            pause.operations.join(","),
+        )],
+        pause.reason.clone(),
+    )?;
+
+    let mut event = lunax_event("pause-operations")
+        .add_attribute("pause_id", pause.id.to_string())
+        .add_attribute("operations", pause.operations.join(","))
+        .add_attribute("reason", pause.reason.unwrap_or_default());
+    if let Some(expires_at) = pause.expires_at {
+        event = event.add_attribute("expires_at", expires_at.to_string());
+    }
+
+    Ok(Response::new().add_event(event))
+}
+
+pub fn update_circuit_breaker(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    circuit_breaker_config: CircuitBreakerConfig,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    validate_role(deps.storage, &config, &info, Role::Admin)?;
+
+    if let Some(max_drop) = circuit_breaker_config.max_exchange_rate_drop {
+        if max_drop.ge(&Decimal::one()) {
+            return Err(ContractError::InvalidCircuitBreakerThreshold {});
+        }
+    }
+    let old_circuit_breaker_config = CIRCUIT_BREAKER_CONFIG
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    CIRCUIT_BREAKER_CONFIG.save(deps.storage, &circuit_breaker_config)?;
+    record_audit_log(
+        deps.storage,
+        &env,
+        &info.sender,
+        "update_circuit_breaker",
+        vec![AuditChange::new(
+            "circuit_breaker_config",
+            to_audit_value(&old_circuit_breaker_config)?,
+            to_audit_value(&circuit_breaker_config)?,
+        )],
+    )?;
+
+    let mut event = lunax_event("update-circuit-breaker");
+    if let Some(max_drop) = circuit_breaker_config.max_exchange_rate_drop {
+        event = event.add_attribute("max_exchange_rate_drop", max_drop.to_string());
+    }
+    if let Some(max_rise) = circuit_breaker_config.max_exchange_rate_rise {
+        event = event.add_attribute("max_exchange_rate_rise", max_rise.to_string());
+    }
+
+    Ok(Response::new().add_event(event))
+}
+
+// An empty fee split sends all fees to the protocol_fee_contract.
+pub fn update_fee_split(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    fee_split: Vec<FeeSplitEntry>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    validate_role(deps.storage, &config, &info, Role::FeeManager)?;
+
+    for entry in fee_split.iter() {
+        deps.api.addr_validate(entry.recipient.as_str())?;
+    }
+    if !is_valid_fee_split(&fee_split) {
+        return Err(ContractError::InvalidFeeSplit {});
+    }
+    let old_fee_split = FEE_SPLIT.may_load(deps.storage)?.unwrap_or_default();
+    FEE_SPLIT.save(deps.storage, &fee_split)?;
+    record_audit_log(
+        deps.storage,
+        &env,
+        &info.sender,
+        "update_fee_split",
+        vec![AuditChange::new(
+            "fee_split",
+            to_audit_value(&old_fee_split)?,
+            to_audit_value(&fee_split)?,
+        )],
+    )?;
+
+    let mut event = lunax_event("update-fee-split");
+    for entry in fee_split.iter() {
+        event = event.add_attribute(entry.recipient.as_str(), entry.weight.to_string());
+    }
+
+    Ok(Response::new().add_event(event))
+}
+
+// Only the manager can call this. Clears the trip and unpauses only the operations which were
+// paused by it.
+pub fn reset_circuit_breaker(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
     validate(&config, &info, &env, vec![Verify::SenderManager])?;
 
-    if let Some(cw20_contract) = update_config.cw20_token_contract {
-        if config.cw20_token_contract == Addr::unchecked("0") {
-            config.cw20_token_contract = deps.api.addr_validate(cw20_contract.as_str())?;
+    let trip = if let Some(trip) = CIRCUIT_BREAKER_TRIP.may_load(deps.storage)? {
+        trip
+    } else {
+        return Err(ContractError::CircuitBreakerNotTripped {});
+    };
+
+    let mut operation_controls = OPERATION_CONTROLS.load(deps.storage)?;
+    for operation in trip.paused_operations.iter() {
+        match operation.as_str() {
+            "deposit" => operation_controls.deposit_paused = false,
+            "queue_undelegate" => operation_controls.queue_undelegate_paused = false,
+            "withdraw" => operation_controls.withdraw_paused = false,
+            _ => {}
         }
     }
+    OPERATION_CONTROLS.save(deps.storage, &operation_controls)?;
+    CIRCUIT_BREAKER_TRIP.remove(deps.storage);
+    record_audit_log(
+        deps.storage,
+        &env,
+        &info.sender,
+        "reset_circuit_breaker",
+        vec![AuditChange::new(
+            "paused_operations",
+            trip.paused_operations.join(","),
+            "",
+        )],
+    )?;
 
-    if let Some(arc) = update_config.airdrop_registry_contract {
-        config.airdrop_registry_contract = deps.api.addr_validate(arc.as_str())?;
+    Ok(Response::new().add_event(
+        lunax_event("reset-circuit-breaker")
+            .add_attribute("unpaused_operations", trip.paused_operations.join(",")),
+    ))
+}
+
+// Pauses deposits, undelegation queueing and withdrawals if a single update moved the exchange
+// rate beyond the configured thresholds. The caller must not fail the transaction after a trip,
+// otherwise the pause is reverted with it.
+pub fn check_circuit_breaker(
+    storage: &mut dyn Storage,
+    env: &Env,
+    operation: &str,
+    previous_er: Decimal,
+    new_er: Decimal,
+) -> Result<Option<Event>, ContractError> {
+    if previous_er.is_zero()
+        || previous_er.eq(&new_er)
+        || CIRCUIT_BREAKER_TRIP.may_load(storage)?.is_some()
+    {
+        return Ok(None);
+    }
+
+    let circuit_breaker_config = CIRCUIT_BREAKER_CONFIG
+        .may_load(storage)?
+        .unwrap_or_default();
+    let (change, max_change) = if new_er.lt(&previous_er) {
+        (
+            decimal_subtraction(previous_er, new_er),
+            circuit_breaker_config.max_exchange_rate_drop,
+        )
+    } else {
+        (
+            decimal_subtraction(new_er, previous_er),
+            circuit_breaker_config.max_exchange_rate_rise,
+        )
+    };
+    let breached = match max_change {
+        Some(max_change) => decimal_division(change, previous_er).gt(&max_change),
+        None => false,
+    };
+    if !breached {
+        return Ok(None);
+    }
+
+    let mut operation_controls = OPERATION_CONTROLS.load(storage)?;
+    let mut paused_operations = vec![];
+    for operation in ["deposit", "queue_undelegate", "withdraw"] {
+        let flag = get_operation_flag(&mut operation_controls, operation)?;
+        // The trip takes over guardian pauses which would expire, so that only a reset lifts it.
+        let expiring_pause = match ACTIVE_PAUSES.may_load(storage, operation)? {
+            Some(pause_id) => PAUSE_HISTORY.load(storage, pause_id)?.expires_at.is_some(),
+            None => false,
+        };
+        if expiring_pause {
+            ACTIVE_PAUSES.remove(storage, operation);
+        }
+        if !*flag || expiring_pause {
+            *flag = true;
+            paused_operations.push(operation.to_string());
+        }
+    }
+    OPERATION_CONTROLS.save(storage, &operation_controls)?;
+
+    CIRCUIT_BREAKER_TRIP.save(
+        storage,
+        &CircuitBreakerTrip {
+            operation: operation.to_string(),
+            previous_exchange_rate: previous_er,
+            new_exchange_rate: new_er,
+            time: env.block.time,
+            paused_operations: paused_operations.clone(),
+        },
+    )?;
+
+    Ok(Some(
+        lunax_event("circuit-breaker-tripped")
+            .add_attribute("operation", operation)
+            .add_attribute("previous_exchange_rate", previous_er.to_string())
+            .add_attribute("new_exchange_rate", new_er.to_string())
+            .add_attribute("paused_operations", paused_operations.join(",")),
+    ))
+}
+
+// Keeps a circuit breaker trip from the slashing check by succeeding without doing anything else.
+// Only for failures that cannot be detected before the slashing check.
+fn fail_unless_tripped(
+    slashing_res: Response,
+    err: ContractError,
+) -> Result<Response, ContractError> {
+    let tripped = slashing_res
+        .events
+        .iter()
+        .any(|x| x.ty.eq(&lunax_event("circuit-breaker-tripped").ty));
+    if tripped {
+        Ok(slashing_res)
+    } else {
+        Err(err)
+    }
+}
+
+pub fn update_config(
+    mut deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    mut update_config: ConfigUpdateRequest,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    validate_role(deps.storage, &config, &info, update_config.required_role())?;
+    validate_config_request(&update_config)?;
+
+    let mut event = lunax_event("update-config");
+    if let Some(protocol_deposit_fee) = update_config.protocol_deposit_fee {
+        event = event.add_attribute("protocol_deposit_fee", protocol_deposit_fee.to_string());
+    }
+    if let Some(protocol_withdraw_fee) = update_config.protocol_withdraw_fee {
+        event = event.add_attribute("protocol_withdraw_fee", protocol_withdraw_fee.to_string());
+    }
+    if let Some(protocol_reward_fee) = update_config.protocol_reward_fee {
+        event = event.add_attribute("protocol_reward_fee", protocol_reward_fee.to_string());
+    }
+
+    let mut pending_change_id = None;
+    let config_timelock = CONFIG_TIMELOCK.may_load(deps.storage)?.unwrap_or_default();
+    if config_timelock > 0 {
+        let timelocked_request = update_config.take_timelocked(config_timelock);
+        if !timelocked_request.is_empty() {
+            let id = PENDING_CONFIG_CHANGE_COUNT
+                .may_load(deps.storage)?
+                .unwrap_or_default()
+                + 1;
+            PENDING_CONFIG_CHANGES.save(
+                deps.storage,
+                id,
+                &PendingConfigChange {
+                    id,
+                    config_request: timelocked_request,
+                    submit_time: env.block.time,
+                    executable_time: env.block.time.plus_seconds(config_timelock),
+                },
+            )?;
+            PENDING_CONFIG_CHANGE_COUNT.save(deps.storage, &id)?;
+            event = event.add_attribute("pending_config_change", id.to_string());
+            pending_change_id = Some(id);
+        }
+    }
+
+    let mut changes = apply_config_request(&mut deps, &mut config, update_config)?;
+    CONFIG.save(deps.storage, &config)?;
+    if let Some(id) = pending_change_id {
+        changes.push(AuditChange::new("pending_config_change", "", id));
     }
+    record_audit_log(deps.storage, &env, &info.sender, "update_config", changes)?;
 
+    Ok(Response::new().add_event(event))
+}
+
+fn validate_config_request(update_config: &ConfigUpdateRequest) -> Result<(), ContractError> {
     if let Some(pdf) = update_config.protocol_deposit_fee {
         if pdf.gt(&get_deposit_fee_cap()) {
             return Err(ContractError::ProtocolFeeAboveLimit {});
         }
-        config.protocol_deposit_fee = pdf;
     }
 
     if let Some(pwf) = update_config.protocol_withdraw_fee {
         if pwf.gt(&get_withdraw_fee_cap()) {
             return Err(ContractError::ProtocolFeeAboveLimit {});
         }
-        config.protocol_withdraw_fee = pwf;
     }
 
     if let Some(prf) = update_config.protocol_reward_fee {
         if prf.gt(&get_reward_fee_cap()) {
             return Err(ContractError::ProtocolFeeAboveLimit {});
         }
-        config.protocol_reward_fee = prf;
     }
 
     if let Some(undelegation_cooldown) = update_config.undelegation_cooldown {
         if !validate_undelegation_cooldown(undelegation_cooldown) {
             return Err(ContractError::InvalidUndelegationCooldown {});
         }
-
-        config.undelegation_cooldown = undelegation_cooldown;
     }
 
     if let Some(unbonding_period) = update_config.unbonding_period {
         if !validate_unbonding_period(unbonding_period) {
             return Err(ContractError::InvalidUnbondingPeriod {});
         }
-
-        config.unbonding_period = unbonding_period;
     }
 
     if let Some(min_deposit) = update_config.min_deposit {
         if !validate_min_deposit(min_deposit) {
             return Err(ContractError::InvalidMinDeposit {});
         }
-
-        config.min_deposit = min_deposit;
     }
 
     if let Some(max_deposit) = update_config.max_deposit {
         if !validate_max_deposit(max_deposit) {
             return Err(ContractError::InvalidMaxDeposit {});
         }
+    }
+
+    if let Some(withdraw_fee_schedule) = &update_config.withdraw_fee_schedule {
+        if !validate_withdraw_fee_schedule(withdraw_fee_schedule) {
+            return Err(ContractError::InvalidWithdrawFeeSchedule {});
+        }
+    }
+
+    if let Some(config_timelock) = update_config.config_timelock {
+        if config_timelock > get_max_config_timelock() {
+            return Err(ContractError::InvalidConfigTimelock {});
+        }
+    }
 
-        config.max_deposit = max_deposit;
+    if let Some(reinvest_split_limit) = update_config.reinvest_split_limit {
+        if !validate_reinvest_split_limit(reinvest_split_limit) {
+            return Err(ContractError::InvalidReinvestSplitLimit {});
+        }
+    }
+
+    if let Some(reward_vesting_period) = update_config.reward_vesting_period {
+        if !validate_reward_vesting_period(reward_vesting_period) {
+            return Err(ContractError::InvalidRewardVestingPeriod {});
+        }
     }
 
+    if let Some(min_holding_period) = update_config.min_holding_period {
+        if !validate_min_holding_period(min_holding_period) {
+            return Err(ContractError::InvalidMinHoldingPeriod {});
+        }
+    }
+
+    Ok(())
+}
+
+// Expects a request which already passed validate_config_request. Returns the fields which changed.
+fn apply_config_request(
+    deps: &mut DepsMut,
+    config: &mut Config,
+    update_config: ConfigUpdateRequest,
+) -> Result<Vec<AuditChange>, ContractError> {
+    let old_config = config.clone();
+    let mut changes = vec![];
+
+    if let Some(cw20_contract) = update_config.cw20_token_contract {
+        if config.cw20_token_contract == Addr::unchecked("0") {
+            config.cw20_token_contract = deps.api.addr_validate(cw20_contract.as_str())?;
+        }
+    }
+
+    if let Some(arc) = update_config.airdrop_registry_contract {
+        config.airdrop_registry_contract = deps.api.addr_validate(arc.as_str())?;
+    }
+
+    config.protocol_deposit_fee = update_config
+        .protocol_deposit_fee
+        .unwrap_or(config.protocol_deposit_fee);
+    config.protocol_withdraw_fee = update_config
+        .protocol_withdraw_fee
+        .unwrap_or(config.protocol_withdraw_fee);
+    config.protocol_reward_fee = update_config
+        .protocol_reward_fee
+        .unwrap_or(config.protocol_reward_fee);
+    config.undelegation_cooldown = update_config
+        .undelegation_cooldown
+        .unwrap_or(config.undelegation_cooldown);
+    config.unbonding_period = update_config
+        .unbonding_period
+        .unwrap_or(config.unbonding_period);
+    config.min_deposit = update_config.min_deposit.unwrap_or(config.min_deposit);
+    config.max_deposit = update_config.max_deposit.unwrap_or(config.max_deposit);
     config.reinvest_cooldown = update_config
         .reinvest_cooldown
         .unwrap_or(config.reinvest_cooldown);
 
+    let fields = [
+        (
+            "cw20_token_contract",
+            old_config.cw20_token_contract.to_string(),
+            config.cw20_token_contract.to_string(),
+        ),
+        (
+            "airdrop_registry_contract",
+            old_config.airdrop_registry_contract.to_string(),
+            config.airdrop_registry_contract.to_string(),
+        ),
+        (
+            "protocol_deposit_fee",
+            old_config.protocol_deposit_fee.to_string(),
+            config.protocol_deposit_fee.to_string(),
+        ),
+        (
+            "protocol_withdraw_fee",
+            old_config.protocol_withdraw_fee.to_string(),
+            config.protocol_withdraw_fee.to_string(),
+        ),
+        (
+            "protocol_reward_fee",
+            old_config.protocol_reward_fee.to_string(),
+            config.protocol_reward_fee.to_string(),
+        ),
+        (
+            "undelegation_cooldown",
+            old_config.undelegation_cooldown.to_string(),
+            config.undelegation_cooldown.to_string(),
+        ),
+        (
+            "unbonding_period",
+            old_config.unbonding_period.to_string(),
+            config.unbonding_period.to_string(),
+        ),
+        (
+            "min_deposit",
+            old_config.min_deposit.to_string(),
+            config.min_deposit.to_string(),
+        ),
+        (
+            "max_deposit",
+            old_config.max_deposit.to_string(),
+            config.max_deposit.to_string(),
+        ),
+        (
+            "reinvest_cooldown",
+            old_config.reinvest_cooldown.to_string(),
+            config.reinvest_cooldown.to_string(),
+        ),
+    ];
+    for (field, old_value, new_value) in fields {
+        if old_value.ne(&new_value) {
+            changes.push(AuditChange::new(field, old_value, new_value));
+        }
+    }
+
+    if let Some(withdraw_fee_schedule) = update_config.withdraw_fee_schedule {
+        let old_withdraw_fee_schedule = WITHDRAW_FEE_SCHEDULE
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        changes.push(AuditChange::new(
+            "withdraw_fee_schedule",
+            to_audit_value(&old_withdraw_fee_schedule)?,
+            to_audit_value(&withdraw_fee_schedule)?,
+        ));
+        WITHDRAW_FEE_SCHEDULE.save(deps.storage, &withdraw_fee_schedule)?;
+    }
+
+    if let Some(config_timelock) = update_config.config_timelock {
+        let old_config_timelock = CONFIG_TIMELOCK.may_load(deps.storage)?.unwrap_or_default();
+        if old_config_timelock != config_timelock {
+            changes.push(AuditChange::new(
+                "config_timelock",
+                old_config_timelock,
+                config_timelock,
+            ));
+        }
+        CONFIG_TIMELOCK.save(deps.storage, &config_timelock)?;
+    }
+
+    if let Some(reinvest_split_limit) = update_config.reinvest_split_limit {
+        let old_reinvest_split_limit = get_reinvest_split_limit(deps.storage)?;
+        if old_reinvest_split_limit != reinvest_split_limit {
+            changes.push(AuditChange::new(
+                "reinvest_split_limit",
+                old_reinvest_split_limit,
+                reinvest_split_limit,
+            ));
+        }
+        REINVEST_SPLIT_LIMIT.save(deps.storage, &reinvest_split_limit)?;
+    }
+
+    // Rewards already vesting keep their schedule, the new period applies from the next reinvest.
+    if let Some(reward_vesting_period) = update_config.reward_vesting_period {
+        let old_reward_vesting_period = get_reward_vesting_period(deps.storage)?;
+        if old_reward_vesting_period != reward_vesting_period {
+            changes.push(AuditChange::new(
+                "reward_vesting_period",
+                old_reward_vesting_period,
+                reward_vesting_period,
+            ));
+        }
+        REWARD_VESTING_PERIOD.save(deps.storage, &reward_vesting_period)?;
+    }
+
+    if let Some(min_holding_period) = update_config.min_holding_period {
+        let old_min_holding_period = MIN_HOLDING_PERIOD
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        if old_min_holding_period != min_holding_period {
+            changes.push(AuditChange::new(
+                "min_holding_period",
+                old_min_holding_period,
+                min_holding_period,
+            ));
+        }
+        MIN_HOLDING_PERIOD.save(deps.storage, &min_holding_period)?;
+    }
+
+    Ok(changes)
+}
+
+// Any address can call this once the timelock of a pending change has passed.
+pub fn execute_pending_config(
+    mut deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    let executable_changes = PENDING_CONFIG_CHANGES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, pending_change)| pending_change))
+        .collect::<StdResult<Vec<PendingConfigChange>>>()?
+        .into_iter()
+        .filter(|x| x.executable_time.le(&env.block.time))
+        .collect::<Vec<PendingConfigChange>>();
+    if executable_changes.is_empty() {
+        return Err(ContractError::PendingConfigChangeNotReady {});
+    }
+
+    let mut executed_ids = vec![];
+    for pending_change in executable_changes {
+        let mut changes =
+            apply_config_request(&mut deps, &mut config, pending_change.config_request)?;
+        changes.push(AuditChange::new(
+            "pending_config_change",
+            pending_change.id,
+            "",
+        ));
+        record_audit_log(
+            deps.storage,
+            &env,
+            &info.sender,
+            "execute_pending_config",
+            changes,
+        )?;
+        PENDING_CONFIG_CHANGES.remove(deps.storage, pending_change.id);
+        executed_ids.push(pending_change.id.to_string());
+    }
     CONFIG.save(deps.storage, &config)?;
 
-    Ok(Response::default())
+    Ok(Response::new().add_event(
+        lunax_event("execute-pending-config")
+            .add_attribute("executed_config_changes", executed_ids.join(",")),
+    ))
+}
+
+pub fn cancel_pending_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let pending_change = PENDING_CONFIG_CHANGES
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::PendingConfigChangeNotFound {})?;
+    validate_role(
+        deps.storage,
+        &config,
+        &info,
+        pending_change.config_request.required_role(),
+    )?;
+    PENDING_CONFIG_CHANGES.remove(deps.storage, id);
+    record_audit_log(
+        deps.storage,
+        &env,
+        &info.sender,
+        "cancel_pending_config",
+        vec![AuditChange::new("pending_config_change", id, "")],
+    )?;
+
+    Ok(Response::new()
+        .add_event(lunax_event("cancel-pending-config").add_attribute("id", id.to_string())))
+}
+
+pub fn grant_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    validate_role(deps.storage, &config, &info, Role::Admin)?;
+
+    let address = deps.api.addr_validate(address.as_str())?;
+    ROLE_MEMBERS.save(deps.storage, (role.as_str(), &address), &true)?;
+    record_audit_log(
+        deps.storage,
+        &env,
+        &info.sender,
+        "grant_role",
+        vec![AuditChange::new(role.as_str(), "", &address)],
+    )?;
+
+    Ok(Response::new().add_event(
+        lunax_event("grant-role")
+            .add_attribute("role", role.as_str())
+            .add_attribute("address", address),
+    ))
+}
+
+pub fn revoke_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    validate_role(deps.storage, &config, &info, Role::Admin)?;
+
+    let address = deps.api.addr_validate(address.as_str())?;
+    ROLE_MEMBERS.remove(deps.storage, (role.as_str(), &address));
+    record_audit_log(
+        deps.storage,
+        &env,
+        &info.sender,
+        "revoke_role",
+        vec![AuditChange::new(role.as_str(), &address, "")],
+    )?;
+
+    Ok(Response::new().add_event(
+        lunax_event("revoke-role")
+            .add_attribute("role", role.as_str())
+            .add_attribute("address", address),
+    ))
 }
 
 pub fn add_validator(
@@ -363,7 +1193,7 @@ pub fn add_validator(
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
     let state = STATE.load(deps.storage)?;
-    validate(&config, &info, &env, vec![Verify::SenderManager])?;
+    validate_role(deps.storage, &config, &info, Role::ValidatorOperator)?;
 
     // lower case the addresses to avoid inconsistencies
     let val_addr = Addr::unchecked(val_addr.to_string().to_lowercase());
@@ -383,8 +1213,17 @@ pub fn add_validator(
     })?;
 
     VALIDATOR_META.save(deps.storage, &val_addr, &VMeta::new())?;
+    record_audit_log(
+        deps.storage,
+        &env,
+        &info.sender,
+        "add_validator",
+        vec![AuditChange::new("validator", "", &val_addr)],
+    )?;
 
-    Ok(Response::new().add_attribute("new_validator", val_addr.to_string()))
+    Ok(Response::new()
+        .add_attribute("new_validator", val_addr.to_string())
+        .add_event(lunax_event("add-validator").add_validator(&val_addr)))
 }
 
 pub fn remove_validator_from_pool(
@@ -395,18 +1234,13 @@ pub fn remove_validator_from_pool(
     redel_addr: Addr,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    validate(&config, &info, &env, vec![Verify::SenderManager])?;
+    validate_role(deps.storage, &config, &info, Role::ValidatorOperator)?;
 
     let val_addr = Addr::unchecked(val_addr.to_string().to_lowercase());
     let redel_addr = Addr::unchecked(redel_addr.to_string().to_lowercase());
 
-    let all_delegations = deps
-        .querier
-        .query_all_delegations(env.contract.address.clone())?;
-    check_slashing(&mut deps, &env, all_delegations.as_slice())?;
-
-    let mut state = STATE.load(deps.storage)?;
-
+    // Validate before the slashing check, a failure after a circuit breaker trip would revert it.
+    let state = STATE.load(deps.storage)?;
     if val_addr.eq(&redel_addr) {
         return Err(ContractError::ValidatorsCannotBeSame {});
     }
@@ -415,6 +1249,21 @@ pub fn remove_validator_from_pool(
         return Err(ContractError::ValidatorNotAdded {});
     }
 
+    let val_delegation = deps
+        .querier
+        .query_delegation(env.contract.address.clone(), val_addr.clone())?;
+    if let Some(full_delegation) = &val_delegation {
+        if full_delegation.can_redelegate.ne(&full_delegation.amount) {
+            return Err(ContractError::RedelegationInProgress {});
+        }
+    }
+
+    let all_delegations = deps
+        .querier
+        .query_all_delegations(env.contract.address.clone())?;
+    let slashing_res = check_slashing(&mut deps, &env, all_delegations.as_slice())?;
+
+    let mut state = STATE.load(deps.storage)?;
     state.validators = state
         .validators
         .into_iter()
@@ -422,29 +1271,62 @@ pub fn remove_validator_from_pool(
         .collect::<Vec<Addr>>();
 
     // Update validator tracking amounts
-    let val_delegation = deps
-        .querier
-        .query_delegation(env.contract.address, val_addr.clone())?;
-    let mut msgs = vec![];
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    let mut redelegated_amount = Uint128::zero();
+    let redel_staked = VALIDATOR_META.load(deps.storage, &redel_addr)?.staked;
     if let Some(full_delegation) = val_delegation {
-        if full_delegation.can_redelegate.ne(&full_delegation.amount) {
-            return Err(ContractError::RedelegationInProgress {});
-        }
-
         increase_tracked_stake(&mut deps, &redel_addr, full_delegation.amount.amount)?;
 
         if !full_delegation.amount.amount.is_zero() {
-            msgs.push(StakingMsg::Redelegate {
-                src_validator: val_addr.to_string(),
-                dst_validator: redel_addr.to_string(),
-                amount: full_delegation.amount,
-            });
+            track_pending_rewards(
+                &mut deps,
+                &env.contract.address,
+                &val_addr,
+                &config.vault_denom,
+            )?;
+            track_pending_rewards(
+                &mut deps,
+                &env.contract.address,
+                &redel_addr,
+                &config.vault_denom,
+            )?;
+            redelegated_amount = full_delegation.amount.amount;
+            msgs.push(
+                StakingMsg::Redelegate {
+                    src_validator: val_addr.to_string(),
+                    dst_validator: redel_addr.to_string(),
+                    amount: full_delegation.amount,
+                }
+                .into(),
+            );
         }
     }
     STATE.save(deps.storage, &state)?;
     VALIDATOR_META.remove(deps.storage, &val_addr);
+    let changes = vec![
+        AuditChange::new("validator", &val_addr, ""),
+        AuditChange::new(
+            &format!("{}_staked", redel_addr),
+            redel_staked,
+            VALIDATOR_META.load(deps.storage, &redel_addr)?.staked,
+        ),
+    ];
+    record_audit_log(
+        deps.storage,
+        &env,
+        &info.sender,
+        "remove_validator",
+        changes,
+    )?;
 
-    Ok(Response::new().add_messages(msgs))
+    Ok(slashing_res
+        .add_submessages(wrap_rewards_withdrawal(&config.reward_contract, msgs)?)
+        .add_event(
+            lunax_event("remove-validator")
+                .add_validator(&val_addr)
+                .add_attribute("redelegation_validator", redel_addr.as_str())
+                .add_amount("amount", redelegated_amount),
+        ))
 }
 
 pub fn rebalance_pool(
@@ -456,7 +1338,7 @@ pub fn rebalance_pool(
     redel_addr: Addr,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    validate(&config, &info, &env, vec![Verify::SenderManager])?;
+    validate_role(deps.storage, &config, &info, Role::ValidatorOperator)?;
 
     if amount.is_zero() {
         return Err(ContractError::ZeroAmount {});
@@ -466,11 +1348,7 @@ pub fn rebalance_pool(
     let val_addr = Addr::unchecked(val_addr.to_string().to_lowercase());
     let redel_addr = Addr::unchecked(redel_addr.to_string().to_lowercase());
 
-    let all_delegations = deps
-        .querier
-        .query_all_delegations(env.contract.address.clone())?;
-    check_slashing(&mut deps, &env, all_delegations.as_slice())?;
-
+    // Validate before the slashing check, a failure after a circuit breaker trip would revert it.
     let state = STATE.load(deps.storage)?;
     if val_addr.eq(&redel_addr) {
         return Err(ContractError::ValidatorsCannotBeSame {});
@@ -482,7 +1360,7 @@ pub fn rebalance_pool(
 
     let src_val_delegation_opt = deps
         .querier
-        .query_delegation(env.contract.address, val_addr.clone())?;
+        .query_delegation(env.contract.address.clone(), val_addr.clone())?;
     if let Some(src_val_delegation) = src_val_delegation_opt {
         if src_val_delegation.amount.amount.lt(&amount) {
             return Err(ContractError::InSufficientFunds {});
@@ -499,20 +1377,241 @@ pub fn rebalance_pool(
         return Err(ContractError::InSufficientFunds {});
     };
 
+    let all_delegations = deps
+        .querier
+        .query_all_delegations(env.contract.address.clone())?;
+    let slashing_res = check_slashing(&mut deps, &env, all_delegations.as_slice())?;
+
     // Update validator tracking amounts
+    let val_staked = VALIDATOR_META.load(deps.storage, &val_addr)?.staked;
+    let redel_staked = VALIDATOR_META.load(deps.storage, &redel_addr)?.staked;
     decrease_tracked_stake(&mut deps, &val_addr, amount)?;
     increase_tracked_stake(&mut deps, &redel_addr, amount)?;
+    let changes = vec![
+        AuditChange::new(
+            &format!("{}_staked", val_addr),
+            val_staked,
+            VALIDATOR_META.load(deps.storage, &val_addr)?.staked,
+        ),
+        AuditChange::new(
+            &format!("{}_staked", redel_addr),
+            redel_staked,
+            VALIDATOR_META.load(deps.storage, &redel_addr)?.staked,
+        ),
+    ];
+    record_audit_log(deps.storage, &env, &info.sender, "rebalance_pool", changes)?;
+    track_pending_rewards(
+        &mut deps,
+        &env.contract.address,
+        &val_addr,
+        &config.vault_denom,
+    )?;
+    track_pending_rewards(
+        &mut deps,
+        &env.contract.address,
+        &redel_addr,
+        &config.vault_denom,
+    )?;
+
+    Ok(slashing_res
+        .add_submessages(wrap_rewards_withdrawal(
+            &config.reward_contract,
+            vec![StakingMsg::Redelegate {
+                src_validator: val_addr.to_string(),
+                dst_validator: redel_addr.to_string(),
+                amount: Coin::new(amount.u128(), config.vault_denom),
+            }
+            .into()],
+        )?)
+        .add_event(
+            lunax_event("rebalance-pool")
+                .add_validator(&val_addr)
+                .add_attribute("redelegation_validator", redel_addr.as_str())
+                .add_amount("amount", amount),
+        ))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn override_validator_meta(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    val_addr: Addr,
+    staked: Option<Uint128>,
+    slashed: Option<Uint128>,
+    filled: Option<Uint128>,
+    reason: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    validate(&config, &info, &env, vec![Verify::SenderManager])?;
+    validate_recovery(deps.storage, reason.as_str())?;
+
+    let val_addr = Addr::unchecked(val_addr.to_string().to_lowercase());
+    let old_meta = VALIDATOR_META
+        .may_load(deps.storage, &val_addr)?
+        .ok_or(ContractError::ValidatorNotAdded {})?;
+    let new_meta = VMeta {
+        staked: staked.unwrap_or(old_meta.staked),
+        slashed: slashed.unwrap_or(old_meta.slashed),
+        filled: filled.unwrap_or(old_meta.filled),
+    };
+    VALIDATOR_META.save(deps.storage, &val_addr, &new_meta)?;
+
+    let changes = vec![
+        AuditChange::new(
+            &format!("{}_staked", val_addr),
+            old_meta.staked,
+            new_meta.staked,
+        ),
+        AuditChange::new(
+            &format!("{}_slashed", val_addr),
+            old_meta.slashed,
+            new_meta.slashed,
+        ),
+        AuditChange::new(
+            &format!("{}_filled", val_addr),
+            old_meta.filled,
+            new_meta.filled,
+        ),
+    ];
+    record_audit_log_with_reason(
+        deps.storage,
+        &env,
+        &info.sender,
+        "override_validator_meta",
+        changes,
+        Some(reason.clone()),
+    )?;
+
+    Ok(Response::new().add_event(
+        lunax_event("override-validator-meta")
+            .add_validator(&val_addr)
+            .add_amount("staked", new_meta.staked)
+            .add_amount("slashed", new_meta.slashed)
+            .add_amount("filled", new_meta.filled)
+            .add_attribute("reason", reason),
+    ))
+}
+
+// For a batch whose unbonding never arrived in full, so reconcile_funds cannot get past it.
+pub fn force_reconcile_batch(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    batch_id: u64,
+    received_amount: Uint128,
+    reason: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    validate(&config, &info, &env, vec![Verify::SenderManager])?;
+    validate_recovery(deps.storage, reason.as_str())?;
+
+    let mut state = STATE.load(deps.storage)?;
+    if batch_id != state.last_reconciled_batch_id + 1
+        || batch_id >= state.current_undelegation_batch_id
+    {
+        return Err(ContractError::InvalidRecoveryBatch {});
+    }
+    let mut batch_meta = BATCH_UNDELEGATION_REGISTRY.load(deps.storage, batch_id)?;
+    if batch_meta.reconciled || batch_meta.est_release_time.is_none() {
+        return Err(ContractError::InvalidRecoveryBatch {});
+    }
+
+    let unbonding_slashing_ratio = if batch_meta.undelegated_stake.is_zero() {
+        Decimal::one()
+    } else {
+        std::cmp::min(
+            Decimal::from_ratio(received_amount, batch_meta.undelegated_stake),
+            Decimal::one(),
+        )
+    };
+    let old_unbonding_slashing_ratio = batch_meta.unbonding_slashing_ratio;
+    batch_meta.unbonding_slashing_ratio = unbonding_slashing_ratio;
+    batch_meta.reconciled = true;
+    BATCH_UNDELEGATION_REGISTRY.save(deps.storage, batch_id, &batch_meta)?;
+
+    let old_reconciled_funds = state.reconciled_funds_to_withdraw;
+    let reconciled_funds = std::cmp::min(received_amount, batch_meta.undelegated_stake);
+    state.reconciled_funds_to_withdraw = state
+        .reconciled_funds_to_withdraw
+        .checked_add(reconciled_funds)
+        .unwrap();
+    state.last_reconciled_batch_id = batch_id;
+    STATE.save(deps.storage, &state)?;
+
+    let changes = vec![
+        AuditChange::new("last_reconciled_batch_id", batch_id - 1, batch_id),
+        AuditChange::new(
+            &format!("batch_{}_unbonding_slashing_ratio", batch_id),
+            old_unbonding_slashing_ratio,
+            unbonding_slashing_ratio,
+        ),
+        AuditChange::new(
+            "reconciled_funds_to_withdraw",
+            old_reconciled_funds,
+            state.reconciled_funds_to_withdraw,
+        ),
+    ];
+    record_audit_log_with_reason(
+        deps.storage,
+        &env,
+        &info.sender,
+        "force_reconcile_batch",
+        changes,
+        Some(reason.clone()),
+    )?;
+
+    Ok(Response::new().add_event(
+        lunax_event("force-reconcile-batch")
+            .add_batch_id(batch_id)
+            .add_amount("reconciled_funds", reconciled_funds)
+            .add_attribute(
+                "unbonding_slashing_ratio",
+                unbonding_slashing_ratio.to_string(),
+            )
+            .add_attribute("reason", reason),
+    ))
+}
+
+pub fn reset_reconciled_funds(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    reconciled_funds_to_withdraw: Uint128,
+    reason: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    validate(&config, &info, &env, vec![Verify::SenderManager])?;
+    validate_recovery(deps.storage, reason.as_str())?;
 
-    Ok(Response::new().add_message(StakingMsg::Redelegate {
-        src_validator: val_addr.to_string(),
-        dst_validator: redel_addr.to_string(),
-        amount: Coin::new(amount.u128(), config.vault_denom),
-    }))
+    let mut state = STATE.load(deps.storage)?;
+    let old_reconciled_funds = state.reconciled_funds_to_withdraw;
+    state.reconciled_funds_to_withdraw = reconciled_funds_to_withdraw;
+    STATE.save(deps.storage, &state)?;
+
+    record_audit_log_with_reason(
+        deps.storage,
+        &env,
+        &info.sender,
+        "reset_reconciled_funds",
+        vec![AuditChange::new(
+            "reconciled_funds_to_withdraw",
+            old_reconciled_funds,
+            reconciled_funds_to_withdraw,
+        )],
+        Some(reason.clone()),
+    )?;
+
+    Ok(Response::new().add_event(
+        lunax_event("reset-reconciled-funds")
+            .add_amount("reconciled_funds_to_withdraw", reconciled_funds_to_withdraw)
+            .add_attribute("reason", reason),
+    ))
 }
 
 pub fn check_slashing(
     deps: &mut DepsMut,
-    _env: &Env,
+    env: &Env,
     delegations: &[Delegation],
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
@@ -545,11 +1644,21 @@ pub fn check_slashing(
 
     let total_tokens = get_total_token_supply(deps.querier, config.cw20_token_contract)?;
 
+    let previous_er = state.exchange_rate;
     state.total_staked = total_staked_on_chain;
-    state.exchange_rate = calculate_exchange_rate(state.total_staked, total_tokens);
+    vest_rewards(&mut state, env.block.time);
+    state.exchange_rate = calculate_vested_exchange_rate(&state, total_tokens);
     STATE.save(deps.storage, &state)?;
 
-    Ok(Response::default())
+    let trip_event = check_circuit_breaker(
+        deps.storage,
+        env,
+        "check_slashing",
+        previous_er,
+        state.exchange_rate,
+    )?;
+
+    Ok(Response::new().add_events(trip_event))
 }
 
 // Any address can call this.
@@ -567,7 +1676,14 @@ pub fn deposit(mut deps: DepsMut, info: MessageInfo, env: Env) -> Result<Respons
         .querier
         .query_all_delegations(env.contract.address.clone())?;
 
-    check_slashing(&mut deps, &env, all_delegations.as_slice())?;
+    let slashing_res = check_slashing(&mut deps, &env, all_delegations.as_slice())?;
+    if OPERATION_CONTROLS.load(deps.storage)?.deposit_paused {
+        // Circuit breaker tripped. Refund instead of failing so that the trip is persisted.
+        return Ok(slashing_res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: info.funds,
+        }));
+    }
 
     let amount = info.funds.first().unwrap().amount;
     if amount.gt(&config.max_deposit) {
@@ -581,15 +1697,34 @@ pub fn deposit(mut deps: DepsMut, info: MessageInfo, env: Env) -> Result<Respons
 
     let mut msgs = vec![];
     let deposit_breakdown = compute_deposit_breakdown(deps.storage.deref(), amount)?;
+    let mut deposit_event = lunax_event("deposit")
+        .add_user(&sender)
+        .add_amount("amount", amount)
+        .add_amount("staked_amount", deposit_breakdown.staked_amount)
+        .add_amount("protocol_fee", deposit_breakdown.protocol_fee)
+        .add_amount("tokens_minted", deposit_breakdown.tokens_to_mint)
+        .add_exchange_rate(state.exchange_rate);
 
     if !deposit_breakdown.protocol_fee.is_zero() {
-        msgs.push(SubMsg::new(BankMsg::Send {
-            to_address: config.protocol_fee_contract.to_string(),
-            amount: vec![Coin::new(
-                deposit_breakdown.protocol_fee.u128(),
-                config.vault_denom.clone(),
-            )],
-        }));
+        record_protocol_revenue(
+            deps.storage,
+            env.block.time,
+            &ProtocolRevenue {
+                deposit_fees: deposit_breakdown.protocol_fee,
+                ..Default::default()
+            },
+        )?;
+        let fee_split = FEE_SPLIT.may_load(deps.storage)?.unwrap_or_default();
+        msgs.extend(
+            fee_split_msgs(
+                &fee_split,
+                &config.protocol_fee_contract,
+                deposit_breakdown.protocol_fee,
+                &config.vault_denom,
+            )
+            .into_iter()
+            .map(SubMsg::new),
+        );
     }
 
     if !deposit_breakdown.staked_amount.is_zero() {
@@ -604,15 +1739,32 @@ pub fn deposit(mut deps: DepsMut, info: MessageInfo, env: Env) -> Result<Respons
             .checked_add(deposit_breakdown.staked_amount)
             .unwrap();
         increase_tracked_stake(&mut deps, &val_addr, deposit_breakdown.staked_amount)?;
+        track_pending_rewards(
+            &mut deps,
+            &env.contract.address,
+            &val_addr,
+            &config.vault_denom,
+        )?;
 
-        msgs.push(SubMsg::new(StakingMsg::Delegate {
-            validator: val_addr.to_string(),
-            amount: Coin::new(deposit_breakdown.staked_amount.u128(), config.vault_denom),
-        }));
+        msgs.extend(wrap_rewards_withdrawal(
+            &config.reward_contract,
+            vec![StakingMsg::Delegate {
+                validator: val_addr.to_string(),
+                amount: Coin::new(deposit_breakdown.staked_amount.u128(), config.vault_denom),
+            }
+            .into()],
+        )?);
+        deposit_event = deposit_event.add_validator(&val_addr);
     }
 
     let mut mint_messages = vec![];
     if !deposit_breakdown.tokens_to_mint.is_zero() {
+        record_user_deposit(
+            deps.storage,
+            &sender,
+            deposit_breakdown.tokens_to_mint,
+            env.block.time,
+        )?;
         mint_messages.push(create_mint_message(
             config.cw20_token_contract,
             deposit_breakdown.tokens_to_mint,
@@ -622,9 +1774,91 @@ pub fn deposit(mut deps: DepsMut, info: MessageInfo, env: Env) -> Result<Respons
 
     STATE.save(deps.storage, &state)?;
 
-    Ok(Response::new()
+    Ok(slashing_res
         .add_submessages(msgs)
-        .add_messages(mint_messages))
+        .add_messages(mint_messages)
+        .add_event(deposit_event))
+}
+
+// Any address can call this. Adds stake without minting, which raises the exchange rate for all holders.
+pub fn donate(mut deps: DepsMut, info: MessageInfo, env: Env) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let operation_controls = OPERATION_CONTROLS.load(deps.storage)?;
+
+    if operation_controls.deposit_paused {
+        return Err(ContractError::OperationPaused("donate".to_string()));
+    }
+
+    validate(&config, &info, &env, vec![Verify::NonZeroSingleInfoFund])?;
+
+    let all_delegations = deps
+        .querier
+        .query_all_delegations(env.contract.address.clone())?;
+
+    let slashing_res = check_slashing(&mut deps, &env, all_delegations.as_slice())?;
+    if OPERATION_CONTROLS.load(deps.storage)?.deposit_paused {
+        return Ok(slashing_res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: info.funds,
+        }));
+    }
+
+    let amount = info.funds.first().unwrap().amount;
+    let mut state = STATE.load(deps.storage)?;
+    let val_addr = get_validator_for_deposit(
+        deps.querier,
+        state.validators.clone(),
+        all_delegations.as_slice(),
+    )?;
+
+    let previous_er = state.exchange_rate;
+    state.total_staked = state.total_staked.checked_add(amount).unwrap();
+    let total_token_supply =
+        get_total_token_supply(deps.querier, config.cw20_token_contract.clone())?;
+    state.exchange_rate = calculate_vested_exchange_rate(&state, total_token_supply);
+    if let Some(trip_event) = check_circuit_breaker(
+        deps.storage,
+        &env,
+        "donate",
+        previous_er,
+        state.exchange_rate,
+    )? {
+        return Ok(slashing_res
+            .add_event(trip_event)
+            .add_message(BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: info.funds,
+            }));
+    }
+    increase_tracked_stake(&mut deps, &val_addr, amount)?;
+    track_pending_rewards(
+        &mut deps,
+        &env.contract.address,
+        &val_addr,
+        &config.vault_denom,
+    )?;
+    STATE.save(deps.storage, &state)?;
+
+    let mut inflow_tracker = INFLOW_TRACKER.may_load(deps.storage)?.unwrap_or_default();
+    inflow_tracker.total_donations = inflow_tracker.total_donations.checked_add(amount).unwrap();
+    INFLOW_TRACKER.save(deps.storage, &inflow_tracker)?;
+
+    Ok(slashing_res
+        .add_submessages(wrap_rewards_withdrawal(
+            &config.reward_contract,
+            vec![StakingMsg::Delegate {
+                validator: val_addr.to_string(),
+                amount: Coin::new(amount.u128(), config.vault_denom),
+            }
+            .into()],
+        )?)
+        .add_event(
+            lunax_event("donate")
+                .add_user(&info.sender)
+                .add_amount("amount", amount)
+                .add_validator(&val_addr)
+                .add_exchange_rate(state.exchange_rate),
+        ))
 }
 
 pub fn compute_deposit_breakdown(
@@ -659,22 +1893,25 @@ pub fn redeem_rewards(
     env: Env,
     validators: Option<Vec<Addr>>,
 ) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let operation_controls = OPERATION_CONTROLS.load(deps.storage)?;
+    if operation_controls.redeem_rewards_paused {
+        return Err(ContractError::OperationPaused("redeem_rewards".to_string()));
+    }
+
     let all_delegations = deps
         .querier
         .query_all_delegations(env.contract.address.clone())?;
     let all_validators = deps.querier.query_all_validators()?;
 
-    check_slashing(&mut deps, &env, all_delegations.as_slice())?;
+    let slashing_res = check_slashing(&mut deps, &env, all_delegations.as_slice())?;
     let state = STATE.load(deps.storage)?;
 
     let validators = validators.unwrap_or(state.validators);
-    let operation_controls = OPERATION_CONTROLS.load(deps.storage)?;
-    if operation_controls.redeem_rewards_paused {
-        return Err(ContractError::OperationPaused("redeem_rewards".to_string()));
-    }
 
     let mut attrs = vec![];
     let mut messages = vec![];
+    let mut redeemed_vals: Vec<String> = vec![];
     let mut failed_vals: Vec<String> = vec![];
     for val_addr in validators {
         // Skip validators that are currently jailed.
@@ -686,11 +1923,26 @@ pub fn redeem_rewards(
             continue;
         }
 
-        messages.push(DistributionMsg::WithdrawDelegatorReward {
+        let withdraw_msg: CosmosMsg = DistributionMsg::WithdrawDelegatorReward {
             validator: val_addr.to_string(),
-        });
+        }
+        .into();
+        if messages.contains(&withdraw_msg) {
+            continue;
+        }
+        track_pending_rewards(
+            &mut deps,
+            &env.contract.address,
+            &val_addr,
+            &config.vault_denom,
+        )?;
+        messages.push(withdraw_msg);
+        redeemed_vals.push(val_addr.to_string());
     }
 
+    let event = lunax_event("redeem-rewards")
+        .add_attribute("validators", redeemed_vals.join(","))
+        .add_attribute("failed_validators", failed_vals.join(","));
     if !failed_vals.is_empty() {
         attrs = vec![Attribute {
             key: "failed_validators".to_string(),
@@ -698,58 +1950,163 @@ pub fn redeem_rewards(
         }];
     }
 
-    Ok(Response::new().add_messages(messages).add_attributes(attrs))
+    Ok(slashing_res
+        .add_submessages(wrap_rewards_withdrawal(&config.reward_contract, messages)?)
+        .add_attributes(attrs)
+        .add_event(event))
 }
 
-pub fn reinvest(mut deps: DepsMut, info: MessageInfo, env: Env) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
-    let operation_controls = OPERATION_CONTROLS.load(deps.storage)?;
+fn validate_reinvest(
+    storage: &dyn Storage,
+    info: &MessageInfo,
+    env: &Env,
+) -> Result<(), ContractError> {
+    let config = CONFIG.load(storage)?;
+    let operation_controls = OPERATION_CONTROLS.load(storage)?;
     if operation_controls.reinvest_paused {
         return Err(ContractError::OperationPaused("reinvest".to_string()));
     }
 
-    let all_delegations = deps
-        .querier
-        .query_all_delegations(env.contract.address.clone())?;
-
-    check_slashing(&mut deps, &env, all_delegations.as_slice())?;
-
-    let mut state = STATE.load(deps.storage)?;
-
-    if info.sender.ne(&config.manager)
+    let state = STATE.load(storage)?;
+    if !has_role(storage, &config, &info.sender, Role::Keeper)?
         && env.block.time.lt(&state
             .last_reinvest_time
             .plus_seconds(config.reinvest_cooldown))
     {
         return Err(ContractError::ReinvestInCooldown {});
     }
+    Ok(())
+}
 
-    let balance = deps.querier.query_balance(
-        config.reward_contract.to_string(),
-        config.vault_denom.clone(),
-    )?;
+pub fn reinvest(deps: DepsMut, info: MessageInfo, env: Env) -> Result<Response, ContractError> {
+    validate_reinvest(deps.storage, &info, &env)?;
+    reinvest_rewards(deps, env)
+}
+
+// Redeems rewards from all validators and reinvests them in the reply to the last message, once the
+// rewards have been recorded by the reward contract.
+pub fn auto_compound(
+    mut deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+) -> Result<Response, ContractError> {
+    validate_reinvest(deps.storage, &info, &env)?;
+
+    let mut res = redeem_rewards(deps.branch(), info, env.clone(), None)?;
+    match res.messages.last_mut() {
+        Some(record_rewards_msg) => {
+            record_rewards_msg.id = AUTO_COMPOUND_REPLY_ID;
+            record_rewards_msg.reply_on = ReplyOn::Success;
+            Ok(res)
+        }
+        None => {
+            // Nothing to redeem, so whatever has already been withdrawn is reinvested right away.
+            let reinvest_res = reinvest_rewards(deps, env)?;
+            Ok(reinvest_res
+                .add_attributes(res.attributes)
+                .add_events(res.events))
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        AUTO_COMPOUND_REPLY_ID => reinvest_rewards(deps, env),
+        id => Err(ContractError::UnknownReplyId(id)),
+    }
+}
+
+fn reinvest_rewards(mut deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let all_delegations = deps
+        .querier
+        .query_all_delegations(env.contract.address.clone())?;
+
+    // Only rewards recorded for this vault are reinvested. Anything else in the reward contract stays
+    // unallocated.
+    let rewards =
+        get_reward_vault(deps.querier, &config.reward_contract, &env.contract.address)?.balance;
+    let mut inflow_tracker = INFLOW_TRACKER.may_load(deps.storage)?.unwrap_or_default();
 
     let protocol_fee_amount = uint128_from_decimal(decimal_multiplication(
-        get_decimal_from_uint128(balance.amount),
+        get_decimal_from_uint128(rewards),
         config.protocol_reward_fee,
     ));
-    let transfer_amount = balance.amount.saturating_sub(protocol_fee_amount);
+    let transfer_amount = rewards.saturating_sub(protocol_fee_amount);
 
-    let val_addr = get_validator_for_deposit(
+    // Split before the slashing check, a failure after a circuit breaker trip would revert it.
+    let reinvest_split = get_reinvest_split(
         deps.querier,
-        state.validators.clone(),
+        STATE.load(deps.storage)?.validators,
         all_delegations.as_slice(),
+        transfer_amount,
+        get_reinvest_split_limit(deps.storage)?,
     )?;
+
+    let slashing_res = check_slashing(&mut deps, &env, all_delegations.as_slice())?;
+
+    let mut state = STATE.load(deps.storage)?;
+    let previous_er = state.exchange_rate;
+    let gross_exchange_rate =
+        get_next_gross_exchange_rate(deps.storage, rewards, state.total_staked)?;
     state.total_staked = state.total_staked.checked_add(transfer_amount).unwrap();
-    increase_tracked_stake(&mut deps, &val_addr, transfer_amount)?;
-    state.exchange_rate = calculate_exchange_rate(
-        state.total_staked,
-        get_total_token_supply(deps.querier, config.cw20_token_contract)?,
+    add_unvested_rewards(
+        &mut state,
+        env.block.time,
+        transfer_amount,
+        get_reward_vesting_period(deps.storage)?,
     );
+    let total_token_supply = get_total_token_supply(deps.querier, config.cw20_token_contract)?;
+    state.exchange_rate = calculate_vested_exchange_rate(&state, total_token_supply);
+    if let Some(trip_event) = check_circuit_breaker(
+        deps.storage,
+        &env,
+        "reinvest",
+        previous_er,
+        state.exchange_rate,
+    )? {
+        // Rewards stay in the reward contract until the manager has looked into the jump.
+        return Ok(slashing_res.add_event(trip_event));
+    }
+    for (val_addr, amount) in reinvest_split.iter() {
+        increase_tracked_stake(&mut deps, val_addr, *amount)?;
+    }
+    record_protocol_revenue(
+        deps.storage,
+        env.block.time,
+        &ProtocolRevenue {
+            reward_fees: protocol_fee_amount,
+            rewards_reinvested: transfer_amount,
+            ..Default::default()
+        },
+    )?;
+    inflow_tracker.expected_rewards = inflow_tracker.expected_rewards.saturating_sub(rewards);
+    INFLOW_TRACKER.save(deps.storage, &inflow_tracker)?;
+    for (val_addr, _) in reinvest_split.iter() {
+        track_pending_rewards(
+            &mut deps,
+            &env.contract.address,
+            val_addr,
+            &config.vault_denom,
+        )?;
+    }
 
     state.last_reinvest_time = env.block.time;
     STATE.save(deps.storage, &state)?;
 
+    EXCHANGE_RATE_SNAPSHOTS.save(
+        deps.storage,
+        env.block.time.seconds(),
+        &ExchangeRateSnapshot {
+            exchange_rate: state.exchange_rate,
+            total_staked: state.total_staked,
+            total_token_supply,
+            gross_exchange_rate,
+            time: env.block.time,
+        },
+    )?;
+
     let mut msgs = vec![SubMsg::new(WasmMsg::Execute {
         contract_addr: config.reward_contract.to_string(),
         msg: to_binary(&RewardExecuteMsg::Transfer {
@@ -757,19 +2114,40 @@ pub fn reinvest(mut deps: DepsMut, info: MessageInfo, env: Env) -> Result<Respon
             reward_withdraw_contract: env.contract.address,
             protocol_fee_amount,
             protocol_fee_contract: config.protocol_fee_contract,
+            protocol_fee_split: FEE_SPLIT.may_load(deps.storage)?.unwrap_or_default(),
         })?,
         funds: vec![],
     })];
-
-    if !transfer_amount.is_zero() {
-        msgs.push(SubMsg::new(StakingMsg::Delegate {
-            validator: val_addr.to_string(),
-            amount: Coin::new(transfer_amount.u128(), config.vault_denom),
-        }));
+
+    let mut event = lunax_event("reinvest")
+        .add_amount("rewards", rewards)
+        .add_amount("amount", transfer_amount)
+        .add_amount("protocol_fee", protocol_fee_amount)
+        .add_amount("unvested_rewards", state.unvested_rewards);
+    let mut delegate_msgs: Vec<CosmosMsg> = vec![];
+    for (val_addr, amount) in reinvest_split {
+        delegate_msgs.push(
+            StakingMsg::Delegate {
+                validator: val_addr.to_string(),
+                amount: Coin::new(amount.u128(), config.vault_denom.clone()),
+            }
+            .into(),
+        );
+        event = event
+            .add_validator(&val_addr)
+            .add_amount("validator_amount", amount);
     }
 
+    // Delegating withdraws the pending rewards of the validator, which are recorded for this vault.
+    msgs.extend(wrap_rewards_withdrawal(
+        &config.reward_contract,
+        delegate_msgs,
+    )?);
+
     // Reward contract throws an error if transfer_amount is not available to be sent over.
-    Ok(Response::new().add_submessages(msgs))
+    Ok(slashing_res
+        .add_submessages(msgs)
+        .add_event(event.add_exchange_rate(state.exchange_rate)))
 }
 
 pub fn receive_cw20(
@@ -815,15 +2193,44 @@ pub fn queue_undelegation(
         ));
     }
 
+    // Validate before the slashing check, a failure after a circuit breaker trip would revert it.
+    let user_addr = deps.api.addr_validate(user_addr_str.as_str())?;
+    let user_deposit = USER_DEPOSITS.may_load(deps.storage, &user_addr)?;
+    if let Some(holding_period_end) = get_holding_period_end(deps.storage, user_deposit.as_ref())? {
+        if env.block.time.lt(&holding_period_end) {
+            return Err(ContractError::InHoldingPeriod(holding_period_end));
+        }
+    }
+
     let all_delegations = deps
         .querier
         .query_all_delegations(env.contract.address.clone())?;
-    check_slashing(&mut deps, &env, all_delegations.as_slice())?;
+    let slashing_res = check_slashing(&mut deps, &env, all_delegations.as_slice())?;
+    if OPERATION_CONTROLS
+        .load(deps.storage)?
+        .queue_undelegate_paused
+    {
+        // Circuit breaker tripped. Return the tokens instead of failing so that the trip is persisted.
+        let config = CONFIG.load(deps.storage)?;
+        return Ok(slashing_res.add_message(WasmMsg::Execute {
+            contract_addr: config.cw20_token_contract.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: user_addr_str,
+                amount: amount_to_burn,
+            })?,
+            funds: vec![],
+        }));
+    }
 
     let state = STATE.load(deps.storage)?;
 
-    let user_addr = deps.api.addr_validate(user_addr_str.as_str())?;
-
+    let holding_period =
+        record_user_outflow(deps.storage, &user_addr, amount_to_burn, env.block.time)?;
+    USER_HOLDING_PERIODS.update(
+        deps.storage,
+        (&user_addr, state.current_undelegation_batch_id),
+        |x| -> StdResult<_> { Ok(x.map_or(holding_period, |x| x.min(holding_period))) },
+    )?;
     USERS.update(
         deps.storage,
         (&user_addr, state.current_undelegation_batch_id),
@@ -854,7 +2261,13 @@ pub fn queue_undelegation(
         },
     )?;
 
-    Ok(Response::default())
+    Ok(slashing_res.add_event(
+        lunax_event("queue-undelegation")
+            .add_user(&user_addr)
+            .add_amount("tokens", amount_to_burn)
+            .add_batch_id(state.current_undelegation_batch_id)
+            .add_exchange_rate(state.exchange_rate),
+    ))
 }
 
 pub fn undelegate_stake(
@@ -870,14 +2283,9 @@ pub fn undelegate_stake(
         ));
     }
 
-    let all_delegations = deps
-        .querier
-        .query_all_delegations(env.contract.address.clone())?;
-    check_slashing(&mut deps, &env, all_delegations.as_slice())?;
-
-    let mut state = STATE.load(deps.storage)?;
-
-    if info.sender.ne(&config.manager)
+    // Validate before the slashing check, a failure after a circuit breaker trip would revert it.
+    let state = STATE.load(deps.storage)?;
+    if !has_role(deps.storage, &config, &info.sender, Role::Keeper)?
         && env.block.time.lt(&state
             .last_undelegation_time
             .plus_seconds(config.undelegation_cooldown))
@@ -885,45 +2293,49 @@ pub fn undelegate_stake(
         return Err(ContractError::UndelegationInCooldown {});
     }
 
-    let mut burn_message: Vec<WasmMsg> = vec![];
-    let mut undelegate_message: Vec<StakingMsg> = vec![];
     // This is because a new batch would be created before this message is called.
     let undelegate_batch_id = state.current_undelegation_batch_id;
-    let batch_key = undelegate_batch_id;
-    let mut undel_amount = Uint128::zero(); // Amount to actually undelegate from blockchain
-    BATCH_UNDELEGATION_REGISTRY.update(
-        deps.storage,
-        batch_key,
-        |x| -> Result<_, ContractError> {
-            let mut batch_undel = x.unwrap();
+    let mut batch_undel = BATCH_UNDELEGATION_REGISTRY.load(deps.storage, undelegate_batch_id)?;
+    if batch_undel.undelegated_tokens.is_zero() {
+        return Err(ContractError::NoOp {});
+    }
 
-            if batch_undel.undelegated_tokens.is_zero() {
-                return Err(ContractError::NoOp {});
-            }
-            burn_message.push(burn_minted_tokens(
-                config.cw20_token_contract.clone(),
-                batch_undel.undelegated_tokens,
-            )?);
-
-            batch_undel.est_release_time =
-                Some(env.block.time.plus_seconds(config.unbonding_period));
-            batch_undel.undelegated_stake = Uint128::new(multiply_u128_with_decimal(
-                batch_undel.undelegated_tokens.u128(),
-                state.exchange_rate,
-            ));
-            batch_undel.undelegation_er = state.exchange_rate;
-            undel_amount = batch_undel.undelegated_stake;
-            Ok(batch_undel)
-        },
-    )?;
-    let validators = state.validators.clone();
-    let mut to_undelegate = undel_amount;
+    let all_delegations = deps
+        .querier
+        .query_all_delegations(env.contract.address.clone())?;
     let stake_tuples = get_active_validators_sorted_by_stake(
         deps.querier,
-        validators,
+        state.validators,
         all_delegations.as_slice(),
     )?;
+    let slashing_res = check_slashing(&mut deps, &env, all_delegations.as_slice())?;
+
+    let mut state = STATE.load(deps.storage)?;
+    // Amount to actually undelegate from blockchain
+    let undel_amount = Uint128::new(multiply_u128_with_decimal(
+        batch_undel.undelegated_tokens.u128(),
+        state.exchange_rate,
+    ));
+    let undel_tokens = batch_undel.undelegated_tokens;
+    let available_stake = stake_tuples
+        .iter()
+        .fold(Uint128::zero(), |acc, x| acc.checked_add(x.0).unwrap());
+    if available_stake.lt(&undel_amount) {
+        return fail_unless_tripped(slashing_res, ContractError::InSufficientFunds {});
+    }
+
+    let burn_message = vec![burn_minted_tokens(
+        config.cw20_token_contract.clone(),
+        undel_tokens,
+    )?];
+    batch_undel.est_release_time = Some(env.block.time.plus_seconds(config.unbonding_period));
+    batch_undel.undelegated_stake = undel_amount;
+    batch_undel.undelegation_er = state.exchange_rate;
+    BATCH_UNDELEGATION_REGISTRY.save(deps.storage, undelegate_batch_id, &batch_undel)?;
 
+    let mut undelegate_message: Vec<CosmosMsg> = vec![];
+    let mut to_undelegate = undel_amount;
+    let mut undelegated_vals: Vec<String> = vec![];
     for index in (0..stake_tuples.len()).rev() {
         let tuple_val = stake_tuples.get(index).unwrap().clone();
         if to_undelegate.is_zero() {
@@ -931,17 +2343,23 @@ pub fn undelegate_stake(
         }
         let val_addr = Addr::unchecked(tuple_val.1);
         let amount = std::cmp::min(to_undelegate, tuple_val.0);
-        undelegate_message.push(StakingMsg::Undelegate {
-            validator: val_addr.to_string(),
-            amount: Coin::new(amount.u128(), config.vault_denom.clone()),
-        });
+        undelegate_message.push(
+            StakingMsg::Undelegate {
+                validator: val_addr.to_string(),
+                amount: Coin::new(amount.u128(), config.vault_denom.clone()),
+            }
+            .into(),
+        );
 
         decrease_tracked_stake(&mut deps, &val_addr, amount)?;
+        track_pending_rewards(
+            &mut deps,
+            &env.contract.address,
+            &val_addr,
+            &config.vault_denom,
+        )?;
         to_undelegate = to_undelegate.saturating_sub(amount);
-    }
-
-    if !to_undelegate.is_zero() {
-        return Err(ContractError::InSufficientFunds {});
+        undelegated_vals.push(val_addr.to_string());
     }
 
     state.last_undelegation_time = env.block.time;
@@ -951,10 +2369,21 @@ pub fn undelegate_stake(
     // Loads the saved state.
     create_new_undelegation_batch(deps.storage, env)?;
 
-    Ok(Response::new()
-        .add_messages(undelegate_message)
+    Ok(slashing_res
+        .add_submessages(wrap_rewards_withdrawal(
+            &config.reward_contract,
+            undelegate_message,
+        )?)
         .add_messages(burn_message)
-        .add_attribute("Undelegation_amount", undel_amount.to_string()))
+        .add_attribute("Undelegation_amount", undel_amount.to_string())
+        .add_event(
+            lunax_event("undelegate-stake")
+                .add_batch_id(undelegate_batch_id)
+                .add_amount("amount", undel_amount)
+                .add_amount("tokens_burned", undel_tokens)
+                .add_exchange_rate(state.exchange_rate)
+                .add_attribute("validators", undelegated_vals.join(",")),
+        ))
 }
 
 // No need for regular slashing check here because these funds have been undelegated 21 days ago and
@@ -994,18 +2423,38 @@ pub fn reconcile_funds(
         last_reconciled_id = batch_id;
     }
 
-    if total_stake_expected.is_zero() {
-        return Ok(Response::default());
-    }
-
     // QUERY the base funds and check how much can be reconciled
     let contract_balance = deps
         .querier
         .query_balance(env.contract.address.to_string(), config.vault_denom)?;
 
+    let mut inflow_tracker = INFLOW_TRACKER.may_load(deps.storage)?.unwrap_or_default();
     let unaccounted_funds = contract_balance
         .amount
-        .saturating_sub(state.reconciled_funds_to_withdraw);
+        .saturating_sub(state.reconciled_funds_to_withdraw)
+        .saturating_sub(inflow_tracker.quarantined_funds);
+
+    if total_stake_expected.is_zero() {
+        // With no unbonding released, anything beyond the batches still unbonding is an unsolicited
+        // transfer. An unbonding can land a little before its estimated release time.
+        let quarantined_funds = unaccounted_funds.saturating_sub(get_outstanding_unbonding(
+            deps.storage,
+            state.last_reconciled_batch_id,
+        )?);
+        if !quarantined_funds.is_zero() {
+            inflow_tracker.quarantined_funds = inflow_tracker
+                .quarantined_funds
+                .checked_add(quarantined_funds)
+                .unwrap();
+            INFLOW_TRACKER.save(deps.storage, &inflow_tracker)?;
+        }
+        return Ok(Response::new().add_event(
+            lunax_event("reconcile-funds")
+                .add_amount("reconciled_funds", Uint128::zero())
+                .add_amount("quarantined_funds", quarantined_funds),
+        ));
+    }
+
     if unaccounted_funds.is_zero() {
         return Err(ContractError::ZeroAmount {});
     }
@@ -1029,21 +2478,87 @@ pub fn reconcile_funds(
         BATCH_UNDELEGATION_REGISTRY.save(deps.storage, batch_id, &batch_meta)?;
     }
 
+    let reconciled_funds = std::cmp::min(unaccounted_funds, total_stake_expected);
     state.reconciled_funds_to_withdraw = state
         .reconciled_funds_to_withdraw
-        .checked_add(std::cmp::min(unaccounted_funds, total_stake_expected))
+        .checked_add(reconciled_funds)
         .unwrap();
     state.last_reconciled_batch_id = last_reconciled_id;
     STATE.save(deps.storage, &state)?;
 
-    Ok(Response::default())
+    // Funds beyond the reconciled batches and the ones left for later, whether still unbonding or
+    // on the next page, cannot belong to any unbonding.
+    let quarantined_funds = unaccounted_funds
+        .saturating_sub(reconciled_funds)
+        .saturating_sub(get_outstanding_unbonding(deps.storage, last_reconciled_id)?);
+    if !quarantined_funds.is_zero() {
+        inflow_tracker.quarantined_funds = inflow_tracker
+            .quarantined_funds
+            .checked_add(quarantined_funds)
+            .unwrap();
+        INFLOW_TRACKER.save(deps.storage, &inflow_tracker)?;
+    }
+
+    Ok(Response::new().add_event(
+        lunax_event("reconcile-funds")
+            .add_batch_id(last_reconciled_id)
+            .add_amount("reconciled_funds", reconciled_funds)
+            .add_amount("quarantined_funds", quarantined_funds)
+            .add_attribute(
+                "unbonding_slashing_ratio",
+                unbonding_slashing_ratio.to_string(),
+            ),
+    ))
+}
+
+// Sends unsolicited transfers held by the staking contract to the recipient. Unallocated funds in
+// the reward contract are swept by its manager.
+pub fn sweep_quarantined_funds(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    validate_role(deps.storage, &config, &info, Role::Admin)?;
+    let recipient = deps.api.addr_validate(&recipient)?;
+
+    let mut inflow_tracker = INFLOW_TRACKER.may_load(deps.storage)?.unwrap_or_default();
+    let quarantined_funds = inflow_tracker.quarantined_funds;
+    if quarantined_funds.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+
+    inflow_tracker.quarantined_funds = Uint128::zero();
+    INFLOW_TRACKER.save(deps.storage, &inflow_tracker)?;
+    record_audit_log(
+        deps.storage,
+        &env,
+        &info.sender,
+        "sweep_quarantined_funds",
+        vec![
+            AuditChange::new("quarantined_funds", quarantined_funds, Uint128::zero()),
+            AuditChange::new("recipient", "", &recipient),
+        ],
+    )?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![Coin::new(quarantined_funds.u128(), config.vault_denom)],
+        })
+        .add_event(
+            lunax_event("sweep-quarantined-funds")
+                .add_attribute("recipient", recipient.as_str())
+                .add_amount("quarantined_funds", quarantined_funds),
+        ))
 }
 
 // Slashing check not required
 pub fn withdraw_funds_to_wallet(
     deps: DepsMut,
     info: MessageInfo,
-    _env: Env,
+    env: Env,
     batch_id: u64,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
@@ -1070,21 +2585,41 @@ pub fn withdraw_funds_to_wallet(
         });
     }
     if !funds_record.protocol_fee.is_zero() {
+        record_protocol_revenue(
+            deps.storage,
+            env.block.time,
+            &ProtocolRevenue {
+                withdraw_fees: funds_record.protocol_fee,
+                ..Default::default()
+            },
+        )?;
         state.reconciled_funds_to_withdraw = state
             .reconciled_funds_to_withdraw
             .saturating_sub(funds_record.protocol_fee);
-        msgs.push(BankMsg::Send {
-            to_address: config.protocol_fee_contract.to_string(),
-            amount: vec![Coin::new(
-                funds_record.protocol_fee.u128(),
-                config.vault_denom,
-            )],
-        });
+        let fee_split = FEE_SPLIT.may_load(deps.storage)?.unwrap_or_default();
+        msgs.extend(fee_split_msgs(
+            &fee_split,
+            &config.protocol_fee_contract,
+            funds_record.protocol_fee,
+            &config.vault_denom,
+        ));
     }
 
     STATE.save(deps.storage, &state)?;
     USERS.remove(deps.storage, (&user_addr, batch_id));
-    Ok(Response::new().add_messages(msgs))
+    USER_HOLDING_PERIODS.remove(deps.storage, (&user_addr, batch_id));
+    Ok(Response::new().add_messages(msgs).add_event(
+        lunax_event("withdraw-funds")
+            .add_user(&user_addr)
+            .add_batch_id(batch_id)
+            .add_amount("amount", funds_record.user_withdrawal_amount)
+            .add_amount("protocol_fee", funds_record.protocol_fee)
+            .add_attribute(
+                "protocol_withdraw_fee",
+                funds_record.protocol_withdraw_fee.to_string(),
+            )
+            .add_amount("tokens", funds_record.undelegated_tokens),
+    ))
 }
 
 // Does not change any state. Used for both messages & queries
@@ -1119,13 +2654,25 @@ pub fn compute_withdrawable_funds(
     let claimable_amount =
         multiply_u128_with_decimal(user_undelegated_amount, und_batch.unbonding_slashing_ratio);
 
-    let protocol_fee = multiply_u128_with_decimal(claimable_amount, config.protocol_withdraw_fee);
+    let withdraw_fee_schedule = WITHDRAW_FEE_SCHEDULE.may_load(storage)?.unwrap_or_default();
+    let holding_period = USER_HOLDING_PERIODS
+        .may_load(storage, key)?
+        .unwrap_or_default();
+    let protocol_withdraw_fee = get_withdraw_fee(
+        &config,
+        &withdraw_fee_schedule,
+        Uint128::new(claimable_amount),
+        holding_period,
+    );
+    let protocol_fee = multiply_u128_with_decimal(claimable_amount, protocol_withdraw_fee);
 
     let user_withdrawal_amount = claimable_amount.saturating_sub(protocol_fee);
     Ok(GetFundsClaimRecord {
         user_withdrawal_amount: Uint128::new(user_withdrawal_amount),
         protocol_fee: Uint128::new(protocol_fee),
         undelegated_tokens: user_undelegation.token_amount,
+        protocol_withdraw_fee,
+        holding_period,
     })
 }
 
@@ -1143,6 +2690,7 @@ pub fn claim_airdrops(
     }
 
     let mut msgs = vec![];
+    let mut events = vec![];
     let airdrop_withdrawal_contract = config.airdrop_withdrawal_contract;
     let airdrops_registry_contract = config.airdrop_registry_contract;
     for rate in airdrop_rates {
@@ -1162,6 +2710,12 @@ pub fn claim_airdrops(
             return Err(ContractError::AirdropNotRegistered(rate.denom));
         };
 
+        events.push(
+            lunax_event("claim-airdrop")
+                .add_attribute("denom", rate.denom.as_str())
+                .add_attribute("stage", rate.stage.to_string())
+                .add_amount("amount", rate.amount),
+        );
         let claim_msg = to_binary(&MerkleAirdropMsg::Claim {
             stage: rate.stage,
             amount: rate.amount,
@@ -1182,15 +2736,15 @@ pub fn claim_airdrops(
         })
     }
 
-    Ok(Response::new().add_messages(msgs))
+    Ok(Response::new().add_messages(msgs).add_events(events))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::State {} => to_binary(&query_state(deps)?),
-        QueryMsg::OperationControls {} => to_binary(&query_operation_controls(deps)?),
+        QueryMsg::OperationControls {} => to_binary(&query_operation_controls(deps, env)?),
         QueryMsg::BatchUndelegation { batch_id } => {
             to_binary(&query_batch_undelegate(deps, batch_id)?)
         }
@@ -1205,7 +2759,7 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             limit,
         )?),
         QueryMsg::GetValMeta { val_addr } => to_binary(&query_val_meta(deps, val_addr)?),
-        QueryMsg::GetUserInfo { user_addr } => to_binary(&query_user_info(deps, user_addr)?),
+        QueryMsg::GetUserInfo { user_addr } => to_binary(&query_user_info(deps, env, user_addr)?),
         QueryMsg::ComputeDepositBreakdown { amount } => {
             to_binary(&query_compute_deposit_breakdown(deps, amount)?)
         }
@@ -1214,24 +2768,225 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             batch_id,
         } => to_binary(&query_user_undelegation_info(deps, user_addr, batch_id)?),
         QueryMsg::TmpManagerStore {} => to_binary(&query_manager_tmp_store(deps)?),
+        QueryMsg::Ownership {} => to_binary(&query_ownership(deps, env)?),
+        QueryMsg::ExchangeRateHistory { start_after, limit } => {
+            to_binary(&query_exchange_rate_history(deps, start_after, limit)?)
+        }
+        QueryMsg::Apr { window_seconds } => to_binary(&query_apr(deps, env, window_seconds)?),
+        QueryMsg::CheckInvariants {
+            start_after,
+            limit,
+            batch_user_tokens,
+        } => to_binary(&query_check_invariants(
+            deps,
+            env,
+            start_after,
+            limit,
+            batch_user_tokens,
+        )?),
+        QueryMsg::CircuitBreaker {} => to_binary(&query_circuit_breaker(deps)?),
+        QueryMsg::InflowTracker {} => to_binary(&query_inflow_tracker(deps, env)?),
+        QueryMsg::FeeSplit {} => to_binary(&query_fee_split(deps)?),
+        QueryMsg::WithdrawFeeSchedule {} => to_binary(&query_withdraw_fee_schedule(deps)?),
+        QueryMsg::ReinvestSplitLimit {} => to_binary(&query_reinvest_split_limit(deps)?),
+        QueryMsg::RewardVesting {} => to_binary(&query_reward_vesting(deps, env)?),
+        QueryMsg::PendingConfigChanges {} => to_binary(&query_pending_config_changes(deps)?),
+        QueryMsg::ProtocolRevenue {} => to_binary(&query_protocol_revenue(deps, env)?),
+        QueryMsg::ProtocolRevenueHistory { start_after, limit } => {
+            to_binary(&query_protocol_revenue_history(deps, start_after, limit)?)
+        }
+        QueryMsg::RoleMembers { role } => to_binary(&query_role_members(deps, role)?),
+        QueryMsg::HasRole { role, address } => to_binary(&query_has_role(deps, role, address)?),
+        QueryMsg::ActivePauses {} => to_binary(&query_active_pauses(deps, env)?),
+        QueryMsg::PauseHistory { start_after, limit } => {
+            to_binary(&query_pause_history(deps, start_after, limit)?)
+        }
+        QueryMsg::AuditLog { start_after, limit } => {
+            to_binary(&query_audit_log(deps, start_after, limit)?)
+        }
+        QueryMsg::PendingRewards {} => to_binary(&query_pending_rewards(deps, env)?),
     }
 }
 
+pub fn query_ownership(deps: Deps, env: Env) -> StdResult<OwnershipResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    ownership::query_ownership(deps.storage, &env, config.manager)
+}
+
 pub fn query_manager_tmp_store(deps: Deps) -> StdResult<TmpManagerStoreResponse> {
     let tmp_manager_store = TMP_MANAGER_STORE.may_load(deps.storage)?;
     Ok(TmpManagerStoreResponse { tmp_manager_store })
 }
 
-pub fn query_operation_controls(deps: Deps) -> StdResult<OperationControls> {
-    let operation_controls = OPERATION_CONTROLS.load(deps.storage)?;
+pub fn query_circuit_breaker(deps: Deps) -> StdResult<CircuitBreakerResponse> {
+    let config = CIRCUIT_BREAKER_CONFIG
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let trip = CIRCUIT_BREAKER_TRIP.may_load(deps.storage)?;
+    Ok(CircuitBreakerResponse { config, trip })
+}
+
+pub fn query_protocol_revenue(deps: Deps, env: Env) -> StdResult<ProtocolRevenueResponse> {
+    let lifetime = PROTOCOL_REVENUE.may_load(deps.storage)?.unwrap_or_default();
+    Ok(ProtocolRevenueResponse {
+        lifetime,
+        current_epoch: env.block.time.seconds() / get_revenue_epoch_length(),
+        epoch_length: get_revenue_epoch_length(),
+    })
+}
+
+pub fn query_protocol_revenue_history(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u64>,
+) -> StdResult<ProtocolRevenueHistoryResponse> {
+    let limit = limit.unwrap_or(10).min(30) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let epochs = PROTOCOL_REVENUE_EPOCHS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(epoch, revenue)| EpochRevenue { epoch, revenue }))
+        .collect::<StdResult<Vec<EpochRevenue>>>()?;
+
+    Ok(ProtocolRevenueHistoryResponse { epochs })
+}
+
+pub fn query_pending_config_changes(deps: Deps) -> StdResult<PendingConfigChangesResponse> {
+    let config_timelock = CONFIG_TIMELOCK.may_load(deps.storage)?.unwrap_or_default();
+    let pending_config_changes = PENDING_CONFIG_CHANGES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, pending_change)| pending_change))
+        .collect::<StdResult<Vec<PendingConfigChange>>>()?;
+    Ok(PendingConfigChangesResponse {
+        config_timelock,
+        pending_config_changes,
+    })
+}
+
+pub fn query_withdraw_fee_schedule(deps: Deps) -> StdResult<WithdrawFeeSchedule> {
+    let withdraw_fee_schedule = WITHDRAW_FEE_SCHEDULE
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    Ok(withdraw_fee_schedule)
+}
+
+pub fn query_reinvest_split_limit(deps: Deps) -> StdResult<ReinvestSplitLimitResponse> {
+    Ok(ReinvestSplitLimitResponse {
+        reinvest_split_limit: get_reinvest_split_limit(deps.storage)?,
+    })
+}
+
+pub fn query_reward_vesting(deps: Deps, env: Env) -> StdResult<RewardVestingResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut state = STATE.load(deps.storage)?;
+    vest_rewards(&mut state, env.block.time);
+    let total_token_supply = get_total_token_supply(deps.querier, config.cw20_token_contract)?;
+    Ok(RewardVestingResponse {
+        reward_vesting_period: get_reward_vesting_period(deps.storage)?,
+        unvested_rewards: state.unvested_rewards,
+        rewards_vesting_end: state.rewards_vesting_end,
+        exchange_rate: calculate_vested_exchange_rate(&state, total_token_supply),
+    })
+}
+
+pub fn query_role_members(deps: Deps, role: Role) -> StdResult<RoleMembersResponse> {
+    let members = ROLE_MEMBERS
+        .prefix(role.as_str())
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<Addr>>>()?;
+    Ok(RoleMembersResponse { role, members })
+}
+
+pub fn query_has_role(deps: Deps, role: Role, address: String) -> StdResult<HasRoleResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let address = deps.api.addr_validate(address.as_str())?;
+    let has_role = has_role(deps.storage, &config, &address, role)?;
+    Ok(HasRoleResponse { has_role })
+}
+
+pub fn query_fee_split(deps: Deps) -> StdResult<FeeSplitResponse> {
+    let fee_split = FEE_SPLIT.may_load(deps.storage)?.unwrap_or_default();
+    Ok(FeeSplitResponse { fee_split })
+}
+
+pub fn query_inflow_tracker(deps: Deps, env: Env) -> StdResult<InflowTrackerResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let inflow_tracker = INFLOW_TRACKER.may_load(deps.storage)?.unwrap_or_default();
+    Ok(InflowTrackerResponse {
+        unaccounted_reward_funds: get_reward_surplus(
+            deps.querier,
+            &config.reward_contract,
+            &env.contract.address,
+            inflow_tracker.expected_rewards,
+        )?,
+        inflow_tracker,
+    })
+}
+
+pub fn query_operation_controls(deps: Deps, env: Env) -> StdResult<OperationControls> {
+    let (operation_controls, _) = get_effective_operation_controls(deps.storage, &env)?;
     Ok(operation_controls)
 }
 
-pub fn query_user_info(deps: Deps, user_addr: String) -> StdResult<UserInfoResponse> {
+pub fn query_active_pauses(deps: Deps, env: Env) -> StdResult<ActivePausesResponse> {
+    let (_, expired_operations) = get_effective_operation_controls(deps.storage, &env)?;
+    let active_pauses = ACTIVE_PAUSES
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<(String, u64)>>>()?
+        .into_iter()
+        .filter(|(operation, _)| !expired_operations.contains(operation))
+        .map(|(operation, pause_id)| {
+            PAUSE_HISTORY
+                .load(deps.storage, pause_id)
+                .map(|pause| ActivePause { operation, pause })
+        })
+        .collect::<StdResult<Vec<ActivePause>>>()?;
+    Ok(ActivePausesResponse { active_pauses })
+}
+
+pub fn query_pause_history(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u64>,
+) -> StdResult<PauseHistoryResponse> {
+    let limit = limit.unwrap_or(10).min(30) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let pauses = PAUSE_HISTORY
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, pause)| pause))
+        .collect::<StdResult<Vec<PauseRecord>>>()?;
+
+    Ok(PauseHistoryResponse { pauses })
+}
+
+pub fn query_audit_log(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u64>,
+) -> StdResult<AuditLogResponse> {
+    let limit = limit.unwrap_or(10).min(30) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let entries = AUDIT_LOG
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, entry)| entry))
+        .collect::<StdResult<Vec<AuditLogEntry>>>()?;
+
+    Ok(AuditLogResponse { entries })
+}
+
+pub fn query_user_info(deps: Deps, env: Env, user_addr: String) -> StdResult<UserInfoResponse> {
     let user_addr = deps.api.addr_validate(user_addr.as_str())?;
     let config = CONFIG.load(deps.storage)?;
     let state = STATE.load(deps.storage)?;
 
+    let user_deposit = USER_DEPOSITS.may_load(deps.storage, &user_addr)?;
+    let holding_period_end = get_holding_period_end(deps.storage, user_deposit.as_ref())?
+        .filter(|x| env.block.time.lt(x));
     let user_token_balance = get_user_balance(deps.querier, config.cw20_token_contract, user_addr)?;
     let user_amount = state.exchange_rate.mul(user_token_balance);
 
@@ -1239,6 +2994,14 @@ pub fn query_user_info(deps: Deps, user_addr: String) -> StdResult<UserInfoRespo
         user_info: UserQueryInfo {
             total_tokens: user_token_balance,
             total_amount: Coin::new(user_amount.u128(), config.vault_denom),
+            holding_period: user_deposit.as_ref().map_or(0, |x| {
+                env.block
+                    .time
+                    .seconds()
+                    .saturating_sub(x.deposit_time.seconds())
+            }),
+            last_deposit_time: user_deposit.and_then(|x| x.last_deposit_time),
+            holding_period_end,
         },
     })
 }
@@ -1281,6 +3044,318 @@ pub fn query_user_undelegation_records(
     Ok(user_undelegations)
 }
 
+pub fn query_exchange_rate_history(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u64>,
+) -> StdResult<ExchangeRateHistoryResponse> {
+    let limit = limit.unwrap_or(10).min(30) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let snapshots = EXCHANGE_RATE_SNAPSHOTS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, snapshot)| snapshot))
+        .collect::<StdResult<Vec<ExchangeRateSnapshot>>>()?;
+
+    Ok(ExchangeRateHistoryResponse { snapshots })
+}
+
+// Annualized exchange rate growth over the window, from the checkpoints taken on every reinvest.
+// Checkpoint exchange rates are net of the protocol reward fee, the gross figure comes from the
+// gross exchange rate recorded alongside them.
+pub fn query_apr(deps: Deps, env: Env, window_seconds: u64) -> StdResult<AprResponse> {
+    let window_start = env.block.time.seconds().saturating_sub(window_seconds);
+
+    let end_opt = EXCHANGE_RATE_SNAPSHOTS
+        .range(deps.storage, None, None, Order::Descending)
+        .next()
+        .transpose()?;
+    // Use the latest checkpoint before the window, or the oldest one if history is shorter.
+    let mut start_opt = EXCHANGE_RATE_SNAPSHOTS
+        .range(
+            deps.storage,
+            None,
+            Some(Bound::inclusive(window_start)),
+            Order::Descending,
+        )
+        .next()
+        .transpose()?;
+    if start_opt.is_none() {
+        start_opt = EXCHANGE_RATE_SNAPSHOTS
+            .range(deps.storage, None, None, Order::Ascending)
+            .next()
+            .transpose()?;
+    }
+
+    let (start, end) = match (start_opt, end_opt) {
+        (Some((_, start)), Some((_, end))) => (start, end),
+        _ => {
+            return Ok(AprResponse {
+                apr: Decimal::zero(),
+                gross_apr: Decimal::zero(),
+                start_time: None,
+                end_time: None,
+            })
+        }
+    };
+
+    let apr = calculate_annualized_rate(
+        start.exchange_rate,
+        end.exchange_rate,
+        end.time.seconds().saturating_sub(start.time.seconds()),
+    );
+    let gross_apr = calculate_annualized_rate(
+        start.gross_exchange_rate,
+        end.gross_exchange_rate,
+        end.time.seconds().saturating_sub(start.time.seconds()),
+    );
+
+    Ok(AprResponse {
+        apr,
+        gross_apr,
+        start_time: Some(start.time),
+        end_time: Some(end.time),
+    })
+}
+
+// Mirrors redeem_rewards followed by reinvest, ignoring slashing and the circuit breaker.
+pub fn query_pending_rewards(deps: Deps, env: Env) -> StdResult<PendingRewardsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut state = STATE.load(deps.storage)?;
+
+    let mut validators = vec![];
+    let mut total_pending_rewards = Uint128::zero();
+    for validator in state.validators.clone() {
+        let pending_rewards = match deps
+            .querier
+            .query_delegation(env.contract.address.clone(), validator.clone())?
+        {
+            Some(full_delegation) => full_delegation
+                .accumulated_rewards
+                .iter()
+                .filter(|x| x.denom.eq(&config.vault_denom))
+                .map(|x| x.amount)
+                .sum(),
+            None => Uint128::zero(),
+        };
+        total_pending_rewards = total_pending_rewards.checked_add(pending_rewards)?;
+        validators.push(ValidatorPendingRewards {
+            validator,
+            pending_rewards,
+        });
+    }
+
+    let reward_contract_balance = deps
+        .querier
+        .query_balance(config.reward_contract.to_string(), config.vault_denom)?
+        .amount;
+    let reinvestable_rewards =
+        get_reward_vault(deps.querier, &config.reward_contract, &env.contract.address)?
+            .balance
+            .checked_add(total_pending_rewards)?;
+    let protocol_fee = uint128_from_decimal(decimal_multiplication(
+        get_decimal_from_uint128(reinvestable_rewards),
+        config.protocol_reward_fee,
+    ));
+    let exchange_rate = state.exchange_rate;
+    let transfer_amount = reinvestable_rewards.saturating_sub(protocol_fee);
+    state.total_staked = state.total_staked.checked_add(transfer_amount)?;
+    add_unvested_rewards(
+        &mut state,
+        env.block.time,
+        transfer_amount,
+        get_reward_vesting_period(deps.storage)?,
+    );
+    let total_token_supply = get_total_token_supply(deps.querier, config.cw20_token_contract)?;
+
+    Ok(PendingRewardsResponse {
+        validators,
+        total_pending_rewards,
+        reward_contract_balance,
+        reinvestable_rewards,
+        protocol_fee,
+        exchange_rate,
+        projected_exchange_rate: calculate_vested_exchange_rate(&state, total_token_supply),
+    })
+}
+
+fn check_invariant(
+    violations: &mut Vec<InvariantViolation>,
+    invariant: String,
+    expected: Uint128,
+    actual: Uint128,
+) {
+    if expected.ne(&actual) {
+        violations.push(InvariantViolation {
+            invariant,
+            expected,
+            actual,
+            difference: std::cmp::max(expected, actual) - std::cmp::min(expected, actual),
+        });
+    }
+}
+
+// Cross-checks the derived totals against each other and against the chain. Returns every
+// discrepancy found; an empty list means the accounting is consistent.
+//
+// The undelegation entries are scanned a page at a time. The first page (no start_after) also
+// checks the stake and balance invariants; each page returns the running per-batch sums, which
+// are passed back in with next_start_after until the scan completes and the batches are checked.
+pub fn query_check_invariants(
+    deps: Deps,
+    env: Env,
+    start_after: Option<(String, u64)>,
+    limit: Option<u64>,
+    batch_user_tokens: Option<Vec<BatchUserTokens>>,
+) -> StdResult<CheckInvariantsResponse> {
+    let state = STATE.load(deps.storage)?;
+    let mut violations = vec![];
+    let start_after = start_after
+        .map(|(user_addr, batch_id)| -> StdResult<(Addr, u64)> {
+            Ok((deps.api.addr_validate(user_addr.as_str())?, batch_id))
+        })
+        .transpose()?;
+
+    if start_after.is_none() {
+        check_holdings_invariants(deps, &env, &state, &mut violations)?;
+    }
+
+    // Users cannot withdraw from unreconciled batches, so their entries must add up exactly.
+    let mut batch_user_tokens: BTreeMap<u64, Uint128> = batch_user_tokens
+        .unwrap_or_default()
+        .into_iter()
+        .map(|x| (x.batch_id, x.token_amount))
+        .collect();
+    let limit = limit.unwrap_or(10).min(30) as usize;
+    let start = start_after
+        .as_ref()
+        .map(|(user_addr, batch_id)| Bound::exclusive((user_addr, *batch_id)));
+    let mut scanned = 0;
+    let mut last_key = None;
+    for item in USERS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+    {
+        let ((user_addr, batch_id), undelegation_info) = item?;
+        if undelegation_info.batch_id > state.last_reconciled_batch_id {
+            let user_tokens = batch_user_tokens
+                .entry(undelegation_info.batch_id)
+                .or_default();
+            *user_tokens = user_tokens.checked_add(undelegation_info.token_amount)?;
+        }
+        scanned += 1;
+        last_key = Some((user_addr.to_string(), batch_id));
+    }
+    let next_start_after = if scanned == limit { last_key } else { None };
+
+    if next_start_after.is_none() {
+        for batch_id in state.last_reconciled_batch_id + 1..=state.current_undelegation_batch_id {
+            if let Some(batch) = BATCH_UNDELEGATION_REGISTRY.may_load(deps.storage, batch_id)? {
+                check_invariant(
+                    &mut violations,
+                    format!("batch_undelegated_tokens:{}", batch_id),
+                    batch.undelegated_tokens,
+                    batch_user_tokens
+                        .get(&batch_id)
+                        .cloned()
+                        .unwrap_or_default(),
+                );
+            }
+        }
+    }
+
+    Ok(CheckInvariantsResponse {
+        violations,
+        batch_user_tokens: batch_user_tokens
+            .into_iter()
+            .map(|(batch_id, token_amount)| BatchUserTokens {
+                batch_id,
+                token_amount,
+            })
+            .collect(),
+        next_start_after,
+    })
+}
+
+// Stake and balance invariants. Every delegation the contract holds is counted, including those
+// to validators no longer in the pool, since they are all backing the minted supply.
+fn check_holdings_invariants(
+    deps: Deps,
+    env: &Env,
+    state: &State,
+    violations: &mut Vec<InvariantViolation>,
+) -> StdResult<()> {
+    let config = CONFIG.load(deps.storage)?;
+    let all_delegations = deps
+        .querier
+        .query_all_delegations(env.contract.address.clone())?;
+    let mut validators = state.validators.clone();
+    for delegation in all_delegations.iter() {
+        let val_addr = deps.api.addr_validate(delegation.validator.as_str())?;
+        if !validators.contains(&val_addr) {
+            validators.push(val_addr);
+        }
+    }
+
+    let mut total_tracked_stake = Uint128::zero();
+    let mut total_delegated = Uint128::zero();
+    for val_addr in validators.iter() {
+        let tracked_stake = VALIDATOR_META
+            .may_load(deps.storage, val_addr)?
+            .unwrap_or_default()
+            .staked;
+        let delegated = all_delegations
+            .iter()
+            .find(|x| x.validator.eq(val_addr))
+            .map(|x| x.amount.amount)
+            .unwrap_or_default();
+        check_invariant(
+            violations,
+            format!("validator_meta_staked:{}", val_addr),
+            delegated,
+            tracked_stake,
+        );
+        total_tracked_stake = total_tracked_stake.checked_add(tracked_stake)?;
+        total_delegated = total_delegated.checked_add(delegated)?;
+    }
+    check_invariant(
+        violations,
+        "total_staked_vs_validator_meta".to_string(),
+        total_tracked_stake,
+        state.total_staked,
+    );
+    check_invariant(
+        violations,
+        "total_staked_vs_delegations".to_string(),
+        total_delegated,
+        state.total_staked,
+    );
+
+    // The contract may hold more than the reconciled and quarantined funds (unbondings in
+    // flight), never less.
+    let contract_balance = deps
+        .querier
+        .query_balance(env.contract.address.clone(), config.vault_denom)?;
+    let quarantined_funds = INFLOW_TRACKER
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        .quarantined_funds;
+    let held_funds = state
+        .reconciled_funds_to_withdraw
+        .checked_add(quarantined_funds)
+        .unwrap();
+    if contract_balance.amount.lt(&held_funds) {
+        check_invariant(
+            violations,
+            "reconciled_funds_vs_balance".to_string(),
+            held_funds,
+            contract_balance.amount,
+        );
+    }
+    Ok(())
+}
+
 pub fn query_val_meta(deps: Deps, val_addr: Addr) -> StdResult<GetValMetaResponse> {
     let val_meta_opt = VALIDATOR_META.may_load(deps.storage, &val_addr)?;
     Ok(GetValMetaResponse {
@@ -1,4 +1,6 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Timestamp};
+use stader_utils::migration::MigrationError;
+use stader_utils::ownership::OwnershipError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -107,4 +109,112 @@ pub enum ContractError {
 
     #[error("Staking-Contract: Invalid max deposit. Max deposit should be b/w 10Luna and 1 Million Luna")]
     InvalidMaxDeposit {},
+
+    #[error("Staking-Contract: Invalid circuit breaker threshold. Max exchange rate drop should be less than 100%")]
+    InvalidCircuitBreakerThreshold {},
+
+    #[error("Staking-Contract: Circuit breaker is not tripped")]
+    CircuitBreakerNotTripped {},
+
+    #[error("Staking-Contract: Invalid fee split. Weights should be non-zero, sum to 1 and recipients should be unique")]
+    InvalidFeeSplit {},
+
+    #[error("Staking-Contract: Invalid withdraw fee schedule")]
+    InvalidWithdrawFeeSchedule {},
+
+    #[error(
+        "Staking-Contract: Invalid config timelock. Config timelock should be at most 30 days"
+    )]
+    InvalidConfigTimelock {},
+
+    #[error("Staking-Contract: Invalid reinvest split limit. Should be between 1 and 10")]
+    InvalidReinvestSplitLimit {},
+
+    #[error("Staking-Contract: Invalid reward vesting period. Should be at most 7 days")]
+    InvalidRewardVestingPeriod {},
+
+    #[error("Staking-Contract: Invalid min holding period. Should be at most 7 days")]
+    InvalidMinHoldingPeriod {},
+
+    #[error("Staking-Contract: Cannot queue undelegation before the holding period of the last deposit ends at {0}")]
+    InHoldingPeriod(Timestamp),
+
+    #[error("Staking-Contract: Pending config change not found")]
+    PendingConfigChangeNotFound {},
+
+    #[error("Staking-Contract: No pending config change is executable yet")]
+    PendingConfigChangeNotReady {},
+
+    #[error("Staking-Contract: Unknown operation '{0}'")]
+    InvalidOperation(String),
+
+    #[error("Staking-Contract: No operations to pause")]
+    NoOperationsToPause {},
+
+    #[error("Staking-Contract: Pause expiry should be in the future")]
+    InvalidPauseExpiry {},
+
+    #[error("Staking-Contract: Manager proposal has expired")]
+    ManagerProposalExpired {},
+
+    #[error("Staking-Contract: Manager proposal expiry should be non-zero")]
+    InvalidManagerProposalExpiry {},
+
+    #[error("Staking-Contract: Recovery operations require a reason")]
+    RecoveryReasonRequired {},
+
+    #[error("Staking-Contract: Recovery operations require withdrawals to be paused")]
+    WithdrawNotPaused {},
+
+    #[error("Staking-Contract: Only the next unreconciled batch which has been undelegated can be force reconciled")]
+    InvalidRecoveryBatch {},
+
+    #[error("Staking-Contract: Cannot migrate from contract {0}")]
+    InvalidMigrationContract(String),
+
+    #[error("Staking-Contract: Cannot migrate from version {0} to older version {1}")]
+    MigrationDowngrade(String, String),
+
+    #[error("Staking-Contract: Invalid contract version {0}")]
+    InvalidContractVersion(String),
+
+    #[error("Staking-Contract: No contract version stored for {0}")]
+    MissingContractVersion(String),
+
+    #[error("Staking-Contract: Unknown reply id {0}")]
+    UnknownReplyId(u64),
+}
+
+impl From<OwnershipError> for ContractError {
+    fn from(err: OwnershipError) -> Self {
+        match err {
+            OwnershipError::Std(err) => ContractError::Std(err),
+            OwnershipError::Unauthorized {} => ContractError::Unauthorized {},
+            OwnershipError::TmpManagerStoreEmpty {} => ContractError::TmpManagerStoreEmpty {},
+            OwnershipError::ManagerProposalExpired {} => ContractError::ManagerProposalExpired {},
+            OwnershipError::InvalidManagerProposalExpiry {} => {
+                ContractError::InvalidManagerProposalExpiry {}
+            }
+        }
+    }
+}
+
+impl From<MigrationError> for ContractError {
+    fn from(err: MigrationError) -> Self {
+        match err {
+            MigrationError::Std(err) => ContractError::Std(err),
+            MigrationError::InvalidContractName(name) => {
+                ContractError::InvalidMigrationContract(name)
+            }
+            MigrationError::Downgrade(stored, new) => {
+                ContractError::MigrationDowngrade(stored, new)
+            }
+            MigrationError::InvalidVersion(version) => {
+                ContractError::InvalidContractVersion(version)
+            }
+            MigrationError::MissingContractVersion(name) => {
+                ContractError::MissingContractVersion(name)
+            }
+        }
+    }
 }
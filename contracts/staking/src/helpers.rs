@@ -1,18 +1,35 @@
 #![allow(dead_code)]
 
+use crate::constants::{
+    get_max_reinvest_split_limit, get_max_reward_vesting_period, get_min_holding_period_cap,
+    get_revenue_epoch_length, get_seconds_per_year, get_withdraw_fee_cap,
+};
 use crate::state::{
-    BatchUndelegationRecord, Config, VMeta, BATCH_UNDELEGATION_REGISTRY, STATE, VALIDATOR_META,
+    AuditChange, AuditLogEntry, BatchUndelegationRecord, Config, OperationControls, PauseRecord,
+    ProtocolRevenue, Role, State, UserDeposit, VMeta, WithdrawFeeSchedule, ACTIVE_PAUSES,
+    AUDIT_LOG, AUDIT_LOG_COUNT, BATCH_UNDELEGATION_REGISTRY, EXCHANGE_RATE_SNAPSHOTS,
+    INFLOW_TRACKER, MIN_HOLDING_PERIOD, OPERATION_CONTROLS, PAUSE_COUNT, PAUSE_HISTORY,
+    PROTOCOL_REVENUE, PROTOCOL_REVENUE_EPOCHS, REINVEST_SPLIT_LIMIT, REWARD_VESTING_PERIOD,
+    ROLE_MEMBERS, STATE, USER_DEPOSITS, VALIDATOR_META,
 };
 use crate::ContractError;
 use airdrops_registry::msg::GetAirdropContractsResponse;
 use airdrops_registry::msg::QueryMsg as AirdropsQueryMsg;
 use cosmwasm_std::{
-    to_binary, Addr, Decimal, Delegation, DepsMut, Env, MessageInfo, QuerierWrapper, StdResult,
-    Storage, Uint128, WasmMsg,
+    to_binary, to_vec, Addr, CosmosMsg, Decimal, Delegation, DepsMut, Env, MessageInfo, Order,
+    QuerierWrapper, StdResult, Storage, SubMsg, Timestamp, Uint128, WasmMsg,
 };
 use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, TokenInfoResponse};
+use cw_storage_plus::Bound;
+use reward::msg::{
+    ExecuteMsg as RewardExecuteMsg, QueryMsg as RewardQueryMsg,
+    VaultResponse as RewardVaultResponse,
+};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use stader_utils::coin_utils::{
+    decimal_division, decimal_multiplication, decimal_subtraction, decimal_summation,
+};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub enum Verify {
@@ -27,12 +44,12 @@ pub enum Verify {
 
 pub fn validate_unbonding_period(unbonding_period: u64) -> bool {
     // unbonding period should be in [21 days, 21 days + 30mins]
-    unbonding_period < 1816200 && unbonding_period >= 1814400
+    (1814400..1816200).contains(&unbonding_period)
 }
 
 pub fn validate_undelegation_cooldown(undelegation_cooldown: u64) -> bool {
     // undelegation cooldown should be in [3 days - 10mins, 3 days + 10mins]
-    undelegation_cooldown <= 259800 && undelegation_cooldown >= 258600
+    (258600..=259800).contains(&undelegation_cooldown)
 }
 
 pub fn validate_min_deposit(min_deposit: Uint128) -> bool {
@@ -45,6 +62,318 @@ pub fn validate_max_deposit(max_deposit: Uint128) -> bool {
     max_deposit.ge(&Uint128::new(100_000_000)) && max_deposit.le(&Uint128::new(1_000_000_000_000))
 }
 
+pub fn validate_reinvest_split_limit(reinvest_split_limit: u64) -> bool {
+    (1..=get_max_reinvest_split_limit()).contains(&reinvest_split_limit)
+}
+
+pub fn validate_withdraw_fee_schedule(schedule: &WithdrawFeeSchedule) -> bool {
+    // Tiers and discounts should be strictly increasing in their thresholds
+    schedule
+        .tiers
+        .iter()
+        .all(|x| x.fee.le(&get_withdraw_fee_cap()))
+        && schedule
+            .tiers
+            .windows(2)
+            .all(|x| x[0].min_amount.lt(&x[1].min_amount))
+        && schedule
+            .holding_period_discounts
+            .iter()
+            .all(|x| x.discount.le(&Decimal::one()))
+        && schedule
+            .holding_period_discounts
+            .windows(2)
+            .all(|x| x[0].min_holding_period < x[1].min_holding_period)
+}
+
+// Picks the highest tier the claim qualifies for and applies the largest holding period discount.
+pub fn get_withdraw_fee(
+    config: &Config,
+    schedule: &WithdrawFeeSchedule,
+    claim_amount: Uint128,
+    holding_period: u64,
+) -> Decimal {
+    let fee = schedule
+        .tiers
+        .iter()
+        .rev()
+        .find(|x| claim_amount.ge(&x.min_amount))
+        .map_or(config.protocol_withdraw_fee, |x| x.fee);
+    let discount = schedule
+        .holding_period_discounts
+        .iter()
+        .rev()
+        .find(|x| holding_period >= x.min_holding_period)
+        .map_or(Decimal::zero(), |x| x.discount);
+    std::cmp::min(
+        decimal_multiplication(fee, decimal_subtraction(Decimal::one(), discount)),
+        get_withdraw_fee_cap(),
+    )
+}
+
+// Adds to the lifetime totals and to the bucket of the current epoch.
+pub fn record_protocol_revenue(
+    storage: &mut dyn Storage,
+    time: Timestamp,
+    revenue: &ProtocolRevenue,
+) -> StdResult<()> {
+    let mut protocol_revenue = PROTOCOL_REVENUE.may_load(storage)?.unwrap_or_default();
+    protocol_revenue.add(revenue);
+    PROTOCOL_REVENUE.save(storage, &protocol_revenue)?;
+
+    let epoch = time.seconds() / get_revenue_epoch_length();
+    let mut epoch_revenue = PROTOCOL_REVENUE_EPOCHS
+        .may_load(storage, epoch)?
+        .unwrap_or_default();
+    epoch_revenue.add(revenue);
+    PROTOCOL_REVENUE_EPOCHS.save(storage, epoch, &epoch_revenue)
+}
+
+// Moves the user's deposit time towards now in proportion to the tokens minted.
+pub fn record_user_deposit(
+    storage: &mut dyn Storage,
+    user_addr: &Addr,
+    tokens_minted: Uint128,
+    time: Timestamp,
+) -> StdResult<()> {
+    let user_deposit = match USER_DEPOSITS.may_load(storage, user_addr)? {
+        Some(user_deposit) => {
+            let total_tokens = user_deposit.tokens_minted.checked_add(tokens_minted)?;
+            let weighted_seconds = Uint128::from(user_deposit.deposit_time.seconds())
+                .checked_mul(user_deposit.tokens_minted)?
+                .checked_add(Uint128::from(time.seconds()).checked_mul(tokens_minted)?)?;
+            UserDeposit {
+                tokens_minted: total_tokens,
+                deposit_time: Timestamp::from_seconds(
+                    weighted_seconds.checked_div(total_tokens)?.u128() as u64,
+                ),
+                last_deposit_time: Some(time),
+            }
+        }
+        None => UserDeposit {
+            tokens_minted,
+            deposit_time: time,
+            last_deposit_time: Some(time),
+        },
+    };
+    USER_DEPOSITS.save(storage, user_addr, &user_deposit)
+}
+
+// Takes queued tokens out of the user's deposit record and returns how long they were held. Only
+// the tokens minted to the user carry a deposit time, so tokens beyond those were received by
+// transfer and count as just acquired.
+pub fn record_user_outflow(
+    storage: &mut dyn Storage,
+    user_addr: &Addr,
+    tokens: Uint128,
+    time: Timestamp,
+) -> StdResult<u64> {
+    let mut user_deposit = match USER_DEPOSITS.may_load(storage, user_addr)? {
+        Some(user_deposit) => user_deposit,
+        None => return Ok(0),
+    };
+    let holding_period = if tokens.le(&user_deposit.tokens_minted) {
+        time.seconds()
+            .saturating_sub(user_deposit.deposit_time.seconds())
+    } else {
+        0
+    };
+    user_deposit.tokens_minted = user_deposit.tokens_minted.saturating_sub(tokens);
+    USER_DEPOSITS.save(storage, user_addr, &user_deposit)?;
+    Ok(holding_period)
+}
+
+// JSON for audit values which are not plain scalars.
+pub fn to_audit_value<T: Serialize>(value: &T) -> StdResult<String> {
+    Ok(String::from_utf8_lossy(&to_vec(value)?).into_owned())
+}
+
+pub fn record_audit_log(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+    operation: &str,
+    changes: Vec<AuditChange>,
+) -> StdResult<u64> {
+    record_audit_log_with_reason(storage, env, sender, operation, changes, None)
+}
+
+pub fn record_audit_log_with_reason(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+    operation: &str,
+    changes: Vec<AuditChange>,
+    reason: Option<String>,
+) -> StdResult<u64> {
+    let id = AUDIT_LOG_COUNT
+        .may_load(storage)?
+        .unwrap_or_default()
+        .checked_add(1)
+        .unwrap();
+    AUDIT_LOG.save(
+        storage,
+        id,
+        &AuditLogEntry {
+            id,
+            sender: sender.clone(),
+            block_height: env.block.height,
+            time: env.block.time,
+            operation: operation.to_string(),
+            changes,
+            reason,
+        },
+    )?;
+    AUDIT_LOG_COUNT.save(storage, &id)?;
+    Ok(id)
+}
+
+// Manual recovery overwrites accounting which withdrawals rely on, so it needs a reason and a
+// withdraw pause.
+pub fn validate_recovery(storage: &dyn Storage, reason: &str) -> Result<(), ContractError> {
+    if reason.trim().is_empty() {
+        return Err(ContractError::RecoveryReasonRequired {});
+    }
+    if !OPERATION_CONTROLS.load(storage)?.withdraw_paused {
+        return Err(ContractError::WithdrawNotPaused {});
+    }
+    Ok(())
+}
+
+pub fn has_role(
+    storage: &dyn Storage,
+    config: &Config,
+    addr: &Addr,
+    role: Role,
+) -> StdResult<bool> {
+    if addr.eq(&config.manager) {
+        return Ok(true);
+    }
+    Ok(ROLE_MEMBERS
+        .may_load(storage, (role.as_str(), addr))?
+        .unwrap_or(false))
+}
+
+pub fn validate_role(
+    storage: &dyn Storage,
+    config: &Config,
+    info: &MessageInfo,
+    role: Role,
+) -> Result<(), ContractError> {
+    if !has_role(storage, config, &info.sender, role)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+pub fn get_operation_flag<'a>(
+    operation_controls: &'a mut OperationControls,
+    operation: &str,
+) -> Result<&'a mut bool, ContractError> {
+    match operation {
+        "deposit" => Ok(&mut operation_controls.deposit_paused),
+        "queue_undelegate" => Ok(&mut operation_controls.queue_undelegate_paused),
+        "undelegate" => Ok(&mut operation_controls.undelegate_paused),
+        "withdraw" => Ok(&mut operation_controls.withdraw_paused),
+        "reinvest" => Ok(&mut operation_controls.reinvest_paused),
+        "reconcile" => Ok(&mut operation_controls.reconcile_paused),
+        "claim_airdrops" => Ok(&mut operation_controls.claim_airdrops_paused),
+        "redeem_rewards" => Ok(&mut operation_controls.redeem_rewards_paused),
+        _ => Err(ContractError::InvalidOperation(operation.to_string())),
+    }
+}
+
+// None never expires.
+fn pause_outlasts(expires_at: Option<u64>, other_expires_at: Option<u64>) -> bool {
+    match (expires_at, other_expires_at) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(expires_at), Some(other_expires_at)) => expires_at >= other_expires_at,
+    }
+}
+
+// Operation controls as of the block time, along with the operations whose pause has expired.
+pub fn get_effective_operation_controls(
+    storage: &dyn Storage,
+    env: &Env,
+) -> StdResult<(OperationControls, Vec<String>)> {
+    let mut operation_controls = OPERATION_CONTROLS.load(storage)?;
+    let active_pauses = ACTIVE_PAUSES
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<(String, u64)>>>()?;
+
+    let mut expired_operations = vec![];
+    for (operation, pause_id) in active_pauses {
+        let pause = PAUSE_HISTORY.load(storage, pause_id)?;
+        if pause
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= env.block.time.seconds())
+        {
+            if let Ok(flag) = get_operation_flag(&mut operation_controls, operation.as_str()) {
+                *flag = false;
+            }
+            expired_operations.push(operation);
+        }
+    }
+    Ok((operation_controls, expired_operations))
+}
+
+pub fn clear_expired_pauses(storage: &mut dyn Storage, env: &Env) -> StdResult<()> {
+    let (operation_controls, expired_operations) = get_effective_operation_controls(storage, env)?;
+    if expired_operations.is_empty() {
+        return Ok(());
+    }
+    for operation in expired_operations.iter() {
+        ACTIVE_PAUSES.remove(storage, operation.as_str());
+    }
+    OPERATION_CONTROLS.save(storage, &operation_controls)
+}
+
+// An operation keeps its current pause if that pause lasts at least as long as the new one. Operations
+// paused without a record, e.g. through update_operation_flags, are held until explicitly unpaused.
+pub fn record_pause(
+    storage: &mut dyn Storage,
+    env: &Env,
+    paused_by: &Addr,
+    operations: Vec<String>,
+    reason: Option<String>,
+    expires_at: Option<u64>,
+) -> Result<PauseRecord, ContractError> {
+    let id = PAUSE_COUNT
+        .may_load(storage)?
+        .unwrap_or_default()
+        .checked_add(1)
+        .unwrap();
+    let mut operation_controls = OPERATION_CONTROLS.load(storage)?;
+    for operation in operations.iter() {
+        let flag = get_operation_flag(&mut operation_controls, operation.as_str())?;
+        let held = match ACTIVE_PAUSES.may_load(storage, operation.as_str())? {
+            Some(active_id) => pause_outlasts(
+                PAUSE_HISTORY.load(storage, active_id)?.expires_at,
+                expires_at,
+            ),
+            None => *flag,
+        };
+        *flag = true;
+        if !held {
+            ACTIVE_PAUSES.save(storage, operation.as_str(), &id)?;
+        }
+    }
+    OPERATION_CONTROLS.save(storage, &operation_controls)?;
+
+    let pause = PauseRecord {
+        id,
+        operations,
+        reason,
+        paused_by: paused_by.clone(),
+        pause_time: env.block.time.seconds(),
+        expires_at,
+    };
+    PAUSE_HISTORY.save(storage, id, &pause)?;
+    PAUSE_COUNT.save(storage, &id)?;
+    Ok(pause)
+}
+
 // Let's not add assertions for these checks in other tests
 pub fn validate(
     config: &Config,
@@ -151,6 +480,135 @@ pub fn get_active_validators_sorted_by_stake(
     Ok(stake_tuples)
 }
 
+// Splits amount across at most split_limit of the validators with the least stake, filling the
+// smallest first so they end up level. Validators which get nothing are left out.
+pub fn get_reinvest_split(
+    querier: QuerierWrapper,
+    validators: Vec<Addr>,
+    all_delegations: &[Delegation],
+    amount: Uint128,
+    split_limit: u64,
+) -> Result<Vec<(Addr, Uint128)>, ContractError> {
+    let mut stake_tuples =
+        get_active_validators_sorted_by_stake(querier, validators, all_delegations)?;
+    stake_tuples.truncate(split_limit.max(1) as usize);
+
+    // Fill up to the highest level which still covers every validator below it.
+    let mut filled_count = stake_tuples.len();
+    let mut level = Uint128::zero();
+    while filled_count > 0 {
+        let total: Uint128 = stake_tuples[..filled_count].iter().map(|x| x.0).sum();
+        level = total.checked_add(amount).unwrap() / Uint128::from(filled_count as u128);
+        if level.ge(&stake_tuples[filled_count - 1].0) {
+            break;
+        }
+        filled_count -= 1;
+    }
+
+    let mut split: Vec<(Addr, Uint128)> = stake_tuples[..filled_count]
+        .iter()
+        .map(|(staked, val_addr)| (Addr::unchecked(val_addr), level - *staked))
+        .collect();
+    // Rounding leftovers go to the validator with the least stake.
+    let allocated: Uint128 = split.iter().map(|x| x.1).sum();
+    split[0].1 += amount - allocated;
+
+    Ok(split.into_iter().filter(|x| !x.1.is_zero()).collect())
+}
+
+pub fn validate_reward_vesting_period(reward_vesting_period: u64) -> bool {
+    reward_vesting_period <= get_max_reward_vesting_period()
+}
+
+// Drops the part of the unvested rewards which has vested by now.
+pub fn vest_rewards(state: &mut State, now: Timestamp) {
+    if now.ge(&state.rewards_vesting_end) {
+        state.unvested_rewards = Uint128::zero();
+    } else if now.gt(&state.rewards_vesting_start) {
+        state.unvested_rewards = state.unvested_rewards.multiply_ratio(
+            state.rewards_vesting_end.seconds() - now.seconds(),
+            state.rewards_vesting_end.seconds() - state.rewards_vesting_start.seconds(),
+        );
+    }
+    state.rewards_vesting_start = std::cmp::max(state.rewards_vesting_start, now);
+}
+
+// Anything still unvested vests over a new full period together with the new rewards.
+pub fn add_unvested_rewards(
+    state: &mut State,
+    now: Timestamp,
+    amount: Uint128,
+    reward_vesting_period: u64,
+) {
+    vest_rewards(state, now);
+    if reward_vesting_period == 0 {
+        return;
+    }
+    state.unvested_rewards = state.unvested_rewards.checked_add(amount).unwrap();
+    state.rewards_vesting_start = now;
+    state.rewards_vesting_end = now.plus_seconds(reward_vesting_period);
+}
+
+// Exchange rate with only the vested part of the reinvested rewards.
+pub fn calculate_vested_exchange_rate(state: &State, total_token_supply: Uint128) -> Decimal {
+    calculate_exchange_rate(
+        state.total_staked.saturating_sub(state.unvested_rewards),
+        total_token_supply,
+    )
+}
+
+pub fn validate_min_holding_period(min_holding_period: u64) -> bool {
+    min_holding_period <= get_min_holding_period_cap()
+}
+
+// End of the window after the user's last deposit in which they cannot queue undelegations. Only
+// deposits are tracked, so tokens without a deposit record count as past the window and pay the
+// regular withdraw fee.
+pub fn get_holding_period_end(
+    storage: &dyn Storage,
+    user_deposit: Option<&UserDeposit>,
+) -> StdResult<Option<Timestamp>> {
+    let min_holding_period = MIN_HOLDING_PERIOD.may_load(storage)?.unwrap_or_default();
+    if min_holding_period == 0 {
+        return Ok(None);
+    }
+    Ok(user_deposit
+        .and_then(|x| x.last_deposit_time)
+        .map(|x| x.plus_seconds(min_holding_period)))
+}
+
+pub fn get_reward_vesting_period(storage: &dyn Storage) -> StdResult<u64> {
+    Ok(REWARD_VESTING_PERIOD.may_load(storage)?.unwrap_or_default())
+}
+
+pub fn get_reinvest_split_limit(storage: &dyn Storage) -> StdResult<u64> {
+    Ok(REINVEST_SPLIT_LIMIT.may_load(storage)?.unwrap_or(1))
+}
+
+// Stake of the undelegated batches after `last_reconciled_batch_id` that are not reconciled yet.
+// Their unbonding may pay out before `est_release_time`, so these funds are never unsolicited.
+pub fn get_outstanding_unbonding(
+    storage: &dyn Storage,
+    last_reconciled_batch_id: u64,
+) -> StdResult<Uint128> {
+    let mut outstanding_unbonding = Uint128::zero();
+    for item in BATCH_UNDELEGATION_REGISTRY.range(
+        storage,
+        Some(Bound::exclusive(last_reconciled_batch_id)),
+        None,
+        Order::Ascending,
+    ) {
+        let (_, batch) = item?;
+        if batch.reconciled || batch.est_release_time.is_none() {
+            continue;
+        }
+        outstanding_unbonding = outstanding_unbonding
+            .checked_add(batch.undelegated_stake)
+            .unwrap();
+    }
+    Ok(outstanding_unbonding)
+}
+
 pub fn create_new_undelegation_batch(
     storage: &mut dyn Storage,
     env: Env,
@@ -214,6 +672,36 @@ pub fn decrease_tracked_slashing(
     Ok(())
 }
 
+// Any change to a delegation withdraws its pending rewards to the reward contract. Record them so
+// that reinvest can tell withdrawn rewards apart from funds sent to the reward contract directly.
+pub fn track_pending_rewards(
+    deps: &mut DepsMut,
+    delegator: &Addr,
+    val_addr: &Addr,
+    denom: &str,
+) -> Result<(), ContractError> {
+    let pending_rewards = match deps.querier.query_delegation(delegator, val_addr)? {
+        Some(full_delegation) => full_delegation
+            .accumulated_rewards
+            .iter()
+            .filter(|x| x.denom.eq(denom))
+            .map(|x| x.amount)
+            .sum(),
+        None => Uint128::zero(),
+    };
+    if pending_rewards.is_zero() {
+        return Ok(());
+    }
+
+    let mut inflow_tracker = INFLOW_TRACKER.may_load(deps.storage)?.unwrap_or_default();
+    inflow_tracker.expected_rewards = inflow_tracker
+        .expected_rewards
+        .checked_add(pending_rewards)
+        .unwrap();
+    INFLOW_TRACKER.save(deps.storage, &inflow_tracker)?;
+    Ok(())
+}
+
 pub fn calculate_exchange_rate(total_staked: Uint128, total_token_supply: Uint128) -> Decimal {
     if total_staked.is_zero() || total_token_supply.is_zero() {
         return Decimal::one();
@@ -221,6 +709,44 @@ pub fn calculate_exchange_rate(total_staked: Uint128, total_token_supply: Uint12
     Decimal::from_ratio(total_staked, total_token_supply)
 }
 
+// Annualizes the exchange rate growth between two checkpoints. A drop in the exchange rate
+// (slashing) is reported as zero since Decimal cannot be negative.
+pub fn calculate_annualized_rate(
+    start_er: Decimal,
+    end_er: Decimal,
+    elapsed_seconds: u64,
+) -> Decimal {
+    if elapsed_seconds == 0 || start_er.is_zero() || end_er.le(&start_er) {
+        return Decimal::zero();
+    }
+    let growth = decimal_division(decimal_subtraction(end_er, start_er), start_er);
+    decimal_multiplication(
+        growth,
+        Decimal::from_ratio(get_seconds_per_year(), elapsed_seconds),
+    )
+}
+
+// Grows the gross exchange rate of the latest snapshot by the rewards earned on the stake before
+// the reinvest.
+pub fn get_next_gross_exchange_rate(
+    storage: &dyn Storage,
+    rewards: Uint128,
+    total_staked: Uint128,
+) -> StdResult<Decimal> {
+    let gross_exchange_rate = EXCHANGE_RATE_SNAPSHOTS
+        .range(storage, None, None, Order::Descending)
+        .next()
+        .transpose()?
+        .map_or_else(Decimal::one, |(_, snapshot)| snapshot.gross_exchange_rate);
+    if total_staked.is_zero() {
+        return Ok(gross_exchange_rate);
+    }
+    Ok(decimal_multiplication(
+        gross_exchange_rate,
+        decimal_summation(Decimal::one(), Decimal::from_ratio(rewards, total_staked)),
+    ))
+}
+
 pub fn get_airdrop_contracts(
     querier_wrapper: QuerierWrapper,
     airdrop_registry_contract: Addr,
@@ -241,6 +767,57 @@ pub fn get_total_token_supply(
     Ok(token_info_res.total_supply)
 }
 
+pub fn get_reward_vault(
+    querier_wrapper: QuerierWrapper,
+    reward_contract: &Addr,
+    vault: &Addr,
+) -> StdResult<RewardVaultResponse> {
+    querier_wrapper.query_wasm_smart(
+        reward_contract.to_string(),
+        &RewardQueryMsg::Vault {
+            vault: vault.to_string(),
+        },
+    )
+}
+
+// Unallocated funds in the reward contract, less the expected rewards this vault has not recorded
+// yet.
+pub fn get_reward_surplus(
+    querier_wrapper: QuerierWrapper,
+    reward_contract: &Addr,
+    vault: &Addr,
+    expected_rewards: Uint128,
+) -> StdResult<Uint128> {
+    let reward_vault = get_reward_vault(querier_wrapper, reward_contract, vault)?;
+    Ok(reward_vault
+        .unallocated_rewards
+        .saturating_sub(expected_rewards.saturating_sub(reward_vault.balance)))
+}
+
+// The reward contract credits this vault with whatever it receives while the messages run, which
+// covers the rewards withdrawn by any delegation change among them.
+pub fn wrap_rewards_withdrawal(
+    reward_contract: &Addr,
+    msgs: Vec<CosmosMsg>,
+) -> StdResult<Vec<SubMsg>> {
+    if msgs.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut wrapped_msgs = vec![SubMsg::new(WasmMsg::Execute {
+        contract_addr: reward_contract.to_string(),
+        msg: to_binary(&RewardExecuteMsg::BeginRewardsWithdrawal {})?,
+        funds: vec![],
+    })];
+    wrapped_msgs.extend(msgs.into_iter().map(SubMsg::new));
+    wrapped_msgs.push(SubMsg::new(WasmMsg::Execute {
+        contract_addr: reward_contract.to_string(),
+        msg: to_binary(&RewardExecuteMsg::RecordRewards {})?,
+        funds: vec![],
+    }));
+    Ok(wrapped_msgs)
+}
+
 pub fn get_user_balance(
     querier_wrapper: QuerierWrapper,
     token_contract_addr: Addr,
@@ -1,11 +1,14 @@
 use crate::state::{
-    AirdropRate, BatchUndelegationRecord, Config, ConfigUpdateRequest,
-    OperationControlsUpdateRequest, State, TmpManagerStore, VMeta,
+    AirdropRate, AuditLogEntry, BatchUndelegationRecord, CircuitBreakerConfig, CircuitBreakerTrip,
+    Config, ConfigUpdateRequest, ExchangeRateSnapshot, InflowTracker,
+    OperationControlsUpdateRequest, PauseRecord, PendingConfigChange, ProtocolRevenue, Role, State,
+    TmpManagerStore, VMeta,
 };
-use cosmwasm_std::{Addr, Coin, Decimal, Uint128};
+use cosmwasm_std::{Addr, Coin, Decimal, Timestamp, Uint128};
 use cw20::Cw20ReceiveMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use stader_utils::fee_split::FeeSplitEntry;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -27,12 +30,27 @@ pub struct InstantiateMsg {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
+pub struct MigrateMsg {
+    // Only set when initializing or overriding fields added since the deployed version.
+    #[serde(default)]
+    pub config_timelock: Option<u64>,
+    #[serde(default)]
+    pub circuit_breaker_config: Option<CircuitBreakerConfig>,
+    #[serde(default)]
+    pub reinvest_split_limit: Option<u64>,
+    #[serde(default)]
+    pub reward_vesting_period: Option<u64>,
+    #[serde(default)]
+    pub min_holding_period: Option<u64>,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct UserQueryInfo {
     pub total_tokens: Uint128,
     pub total_amount: Coin, // value of tokens in luna with the exchange rate at that point
+    pub holding_period: u64, // seconds since the average deposit time, used for withdraw fee discounts
+    pub last_deposit_time: Option<Timestamp>,
+    pub holding_period_end: Option<Timestamp>, // queue undelegation is blocked until then for deposited tokens
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -43,6 +61,7 @@ pub enum Cw20HookMsg {
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
     AddValidator {
         val_addr: Addr,
@@ -62,6 +81,8 @@ pub enum ExecuteMsg {
     },
     Receive(Cw20ReceiveMsg),
     Reinvest {},
+    // Redeems rewards from all validators and reinvests them in the same transaction.
+    AutoCompound {},
     Undelegate {},
     ReconcileFunds {},
     WithdrawFundsToWallet {
@@ -78,8 +99,56 @@ pub enum ExecuteMsg {
     },
     SetManager {
         manager: String,
+        expires_in: Option<u64>, // Seconds. Defaults to stader_utils::ownership::DEFAULT_OWNERSHIP_PROPOSAL_EXPIRY
     },
     AcceptManager {},
+    CancelManagerUpdate {},
+    RenounceManager {},
+    UpdateCircuitBreaker {
+        circuit_breaker_config: CircuitBreakerConfig,
+    },
+    ResetCircuitBreaker {},
+    Donate {},
+    SweepQuarantinedFunds {
+        recipient: String,
+    },
+    UpdateFeeSplit {
+        fee_split: Vec<FeeSplitEntry>,
+    },
+    ExecutePendingConfig {},
+    CancelPendingConfig {
+        id: u64,
+    },
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+    RevokeRole {
+        role: Role,
+        address: String,
+    },
+    PauseOperations {
+        operations: Vec<String>,
+        reason: Option<String>,
+        expires_at: Option<u64>, // Block time in seconds
+    },
+    // Manual incident recovery. Manager only, and only while withdrawals are paused.
+    OverrideValidatorMeta {
+        val_addr: Addr,
+        staked: Option<Uint128>,
+        slashed: Option<Uint128>,
+        filled: Option<Uint128>,
+        reason: String,
+    },
+    ForceReconcileBatch {
+        batch_id: u64,
+        received_amount: Uint128,
+        reason: String,
+    },
+    ResetReconciledFunds {
+        reconciled_funds_to_withdraw: Uint128,
+        reason: String,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -99,6 +168,7 @@ pub enum QueryMsg {
     State {},
     OperationControls {},
     TmpManagerStore {},
+    Ownership {},
     BatchUndelegation {
         batch_id: u64,
     },
@@ -120,6 +190,47 @@ pub enum QueryMsg {
     ComputeDepositBreakdown {
         amount: Uint128,
     },
+    ExchangeRateHistory {
+        start_after: Option<u64>, // block time in seconds
+        limit: Option<u64>,
+    },
+    Apr {
+        window_seconds: u64,
+    },
+    CheckInvariants {
+        start_after: Option<(String, u64)>, // (user_addr, batch_id) from next_start_after
+        limit: Option<u64>,
+        batch_user_tokens: Option<Vec<BatchUserTokens>>, // Running sums from the previous page
+    },
+    CircuitBreaker {},
+    InflowTracker {},
+    FeeSplit {},
+    WithdrawFeeSchedule {},
+    ReinvestSplitLimit {},
+    RewardVesting {},
+    PendingConfigChanges {},
+    ProtocolRevenue {},
+    ProtocolRevenueHistory {
+        start_after: Option<u64>,
+        limit: Option<u64>,
+    },
+    RoleMembers {
+        role: Role,
+    },
+    HasRole {
+        role: Role,
+        address: String,
+    },
+    ActivePauses {},
+    PauseHistory {
+        start_after: Option<u64>,
+        limit: Option<u64>,
+    },
+    AuditLog {
+        start_after: Option<u64>,
+        limit: Option<u64>,
+    },
+    PendingRewards {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -152,6 +263,143 @@ pub struct GetValMetaResponse {
     pub val_meta: Option<VMeta>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExchangeRateHistoryResponse {
+    pub snapshots: Vec<ExchangeRateSnapshot>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AprResponse {
+    pub apr: Decimal,       // net of protocol reward fee. "1 is 100%, 0.02 is 2%"
+    pub gross_apr: Decimal, // before protocol reward fee
+    pub start_time: Option<Timestamp>, // checkpoints used for the computation
+    pub end_time: Option<Timestamp>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ValidatorPendingRewards {
+    pub validator: Addr,
+    pub pending_rewards: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingRewardsResponse {
+    pub validators: Vec<ValidatorPendingRewards>,
+    pub total_pending_rewards: Uint128, // Accumulated on the validators, not yet redeemed
+    pub reward_contract_balance: Uint128,
+    pub reinvestable_rewards: Uint128, // What reinvest would pick up after redeem_rewards
+    pub protocol_fee: Uint128,
+    pub exchange_rate: Decimal,
+    pub projected_exchange_rate: Decimal, // If redeem_rewards and reinvest ran now
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InvariantViolation {
+    pub invariant: String,
+    pub expected: Uint128,
+    pub actual: Uint128,
+    pub difference: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CheckInvariantsResponse {
+    pub violations: Vec<InvariantViolation>,
+    pub batch_user_tokens: Vec<BatchUserTokens>,
+    pub next_start_after: Option<(String, u64)>, // None once every undelegation was scanned
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BatchUserTokens {
+    pub batch_id: u64,
+    pub token_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CircuitBreakerResponse {
+    pub config: CircuitBreakerConfig,
+    pub trip: Option<CircuitBreakerTrip>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProtocolRevenueResponse {
+    pub lifetime: ProtocolRevenue,
+    pub current_epoch: u64,
+    pub epoch_length: u64, // seconds
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EpochRevenue {
+    pub epoch: u64,
+    pub revenue: ProtocolRevenue,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProtocolRevenueHistoryResponse {
+    pub epochs: Vec<EpochRevenue>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReinvestSplitLimitResponse {
+    pub reinvest_split_limit: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardVestingResponse {
+    pub reward_vesting_period: u64,
+    pub unvested_rewards: Uint128, // as of the query block time
+    pub rewards_vesting_end: Timestamp,
+    pub exchange_rate: Decimal, // as of the query block time
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingConfigChangesResponse {
+    pub config_timelock: u64,
+    pub pending_config_changes: Vec<PendingConfigChange>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ActivePause {
+    pub operation: String,
+    pub pause: PauseRecord,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ActivePausesResponse {
+    pub active_pauses: Vec<ActivePause>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PauseHistoryResponse {
+    pub pauses: Vec<PauseRecord>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuditLogResponse {
+    pub entries: Vec<AuditLogEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoleMembersResponse {
+    pub role: Role,
+    pub members: Vec<Addr>, // Explicit grants only, the manager holds every role implicitly
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HasRoleResponse {
+    pub has_role: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeeSplitResponse {
+    pub fee_split: Vec<FeeSplitEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InflowTrackerResponse {
+    pub inflow_tracker: InflowTracker,
+    pub unaccounted_reward_funds: Uint128, // Unallocated reward contract funds not backed by withdrawn rewards
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct GetFundsDepositRecord {
     pub user_deposit_amount: Uint128,
@@ -165,4 +413,6 @@ pub struct GetFundsClaimRecord {
     pub user_withdrawal_amount: Uint128,
     pub protocol_fee: Uint128,
     pub undelegated_tokens: Uint128,
+    pub protocol_withdraw_fee: Decimal,
+    pub holding_period: u64,
 }
@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{Addr, Decimal, Timestamp, Uint128};
 use cw_storage_plus::{Item, Map};
+use stader_utils::fee_split::FeeSplitEntry;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
@@ -36,9 +37,18 @@ pub struct State {
     pub last_reinvest_time: Timestamp,
     pub validators: Vec<Addr>,
     pub reconciled_funds_to_withdraw: Uint128,
+    // Reinvested rewards are part of total_staked but only count towards the exchange rate as they
+    // vest, linearly from rewards_vesting_start to rewards_vesting_end.
+    #[serde(default)]
+    pub unvested_rewards: Uint128,
+    #[serde(default)]
+    pub rewards_vesting_start: Timestamp,
+    #[serde(default)]
+    pub rewards_vesting_end: Timestamp,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(default)]
 pub struct OperationControls {
     pub deposit_paused: bool,
     pub queue_undelegate_paused: bool,
@@ -101,7 +111,7 @@ pub struct BatchUndelegationRecord {
 pub const BATCH_UNDELEGATION_REGISTRY: Map<u64, BatchUndelegationRecord> =
     Map::new("batch_undelegation_registry");
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
 pub struct ConfigUpdateRequest {
     pub(crate) min_deposit: Option<Uint128>,
     pub(crate) max_deposit: Option<Uint128>,
@@ -115,6 +125,57 @@ pub struct ConfigUpdateRequest {
     pub(crate) unbonding_period: Option<u64>,
     pub(crate) undelegation_cooldown: Option<u64>,
     pub(crate) reinvest_cooldown: Option<u64>,
+
+    pub(crate) withdraw_fee_schedule: Option<WithdrawFeeSchedule>,
+    pub(crate) config_timelock: Option<u64>, // Decreases are timelocked, increases are not.
+    pub(crate) reinvest_split_limit: Option<u64>,
+    pub(crate) reward_vesting_period: Option<u64>,
+    pub(crate) min_holding_period: Option<u64>,
+}
+
+impl ConfigUpdateRequest {
+    // Moves the fields which only take effect after the config timelock into a new request.
+    pub(crate) fn take_timelocked(&mut self, current_timelock: u64) -> ConfigUpdateRequest {
+        ConfigUpdateRequest {
+            min_deposit: self.min_deposit.take(),
+            max_deposit: self.max_deposit.take(),
+            protocol_reward_fee: self.protocol_reward_fee.take(),
+            protocol_withdraw_fee: self.protocol_withdraw_fee.take(),
+            protocol_deposit_fee: self.protocol_deposit_fee.take(),
+            unbonding_period: self.unbonding_period.take(),
+            undelegation_cooldown: self.undelegation_cooldown.take(),
+            reinvest_cooldown: self.reinvest_cooldown.take(),
+            withdraw_fee_schedule: self.withdraw_fee_schedule.take(),
+            min_holding_period: self.min_holding_period.take(),
+            config_timelock: if self.config_timelock.unwrap_or(current_timelock) < current_timelock
+            {
+                self.config_timelock.take()
+            } else {
+                None
+            },
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.eq(&ConfigUpdateRequest::default())
+    }
+
+    // Fee fields are left to the FeeManager, every other field needs an Admin.
+    pub(crate) fn required_role(&self) -> Role {
+        let fee_request = ConfigUpdateRequest {
+            protocol_reward_fee: self.protocol_reward_fee,
+            protocol_withdraw_fee: self.protocol_withdraw_fee,
+            protocol_deposit_fee: self.protocol_deposit_fee,
+            withdraw_fee_schedule: self.withdraw_fee_schedule.clone(),
+            ..Default::default()
+        };
+        if self.eq(&fee_request) {
+            Role::FeeManager
+        } else {
+            Role::Admin
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -129,6 +190,215 @@ pub struct OperationControlsUpdateRequest {
     pub(crate) redeem_rewards_paused: Option<bool>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExchangeRateSnapshot {
+    pub exchange_rate: Decimal,
+    pub total_staked: Uint128,
+    pub total_token_supply: Uint128, // LunaX supply at the time of the snapshot
+    // Compounded from the gross rewards of each reinvest only, starting at 1. Protocol fees and
+    // donations do not move it, so fee changes between snapshots don't skew the gross APR.
+    pub gross_exchange_rate: Decimal,
+    pub time: Timestamp,
+}
+
+// (block time in seconds) -> ExchangeRateSnapshot. A snapshot is taken on every reinvest.
+pub const EXCHANGE_RATE_SNAPSHOTS: Map<u64, ExchangeRateSnapshot> =
+    Map::new("exchange_rate_snapshots");
+
+// Thresholds are relative to the exchange rate before the update. "1 is 100%, 0.02 is 2%"
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct CircuitBreakerConfig {
+    pub max_exchange_rate_drop: Option<Decimal>, // None disables the check
+    pub max_exchange_rate_rise: Option<Decimal>, // None disables the check
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CircuitBreakerTrip {
+    pub operation: String, // operation which updated the exchange rate
+    pub previous_exchange_rate: Decimal,
+    pub new_exchange_rate: Decimal,
+    pub time: Timestamp,
+    pub paused_operations: Vec<String>, // operations paused by the trip, unpaused on reset
+}
+
+pub const CIRCUIT_BREAKER_CONFIG: Item<CircuitBreakerConfig> = Item::new("circuit_breaker_config");
+// Present only while the circuit breaker is tripped. Cleared by the manager.
+pub const CIRCUIT_BREAKER_TRIP: Item<CircuitBreakerTrip> = Item::new("circuit_breaker_trip");
+
+// Attribution of funds flowing into the staking and reward contracts which are not minted against.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct InflowTracker {
+    pub expected_rewards: Uint128, // Rewards withdrawn to the reward contract and not reinvested yet.
+    pub quarantined_funds: Uint128, // Unsolicited funds in this contract. Not credited to any batch.
+    pub total_donations: Uint128,
+}
+
+pub const INFLOW_TRACKER: Item<InflowTracker> = Item::new("inflow_tracker");
+
+// Recipients of all protocol fees. All fees go to the protocol_fee_contract while this is empty.
+pub const FEE_SPLIT: Item<Vec<FeeSplitEntry>> = Item::new("fee_split");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WithdrawFeeTier {
+    pub min_amount: Uint128, // Applies to claims of at least this amount.
+    pub fee: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HoldingPeriodDiscount {
+    pub min_holding_period: u64, // seconds
+    pub discount: Decimal,       // Fraction of the withdraw fee waived.
+}
+
+// protocol_withdraw_fee applies to claims below the smallest tier. The fee after discounts is
+// still capped at get_withdraw_fee_cap.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct WithdrawFeeSchedule {
+    pub tiers: Vec<WithdrawFeeTier>,
+    pub holding_period_discounts: Vec<HoldingPeriodDiscount>,
+}
+
+pub const WITHDRAW_FEE_SCHEDULE: Item<WithdrawFeeSchedule> = Item::new("withdraw_fee_schedule");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UserDeposit {
+    pub tokens_minted: Uint128, // Minted to the user and not queued for undelegation yet.
+    pub deposit_time: Timestamp, // Average over all deposits, weighted by tokens minted.
+    #[serde(default)]
+    pub last_deposit_time: Option<Timestamp>, // Not set for deposits from before it was tracked.
+}
+
+pub const USER_DEPOSITS: Map<&Addr, UserDeposit> = Map::new("user_deposits");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct ProtocolRevenue {
+    pub deposit_fees: Uint128,
+    pub withdraw_fees: Uint128,
+    pub reward_fees: Uint128,
+    pub rewards_reinvested: Uint128,
+}
+
+impl ProtocolRevenue {
+    pub fn add(&mut self, other: &ProtocolRevenue) {
+        self.deposit_fees = self.deposit_fees.checked_add(other.deposit_fees).unwrap();
+        self.withdraw_fees = self.withdraw_fees.checked_add(other.withdraw_fees).unwrap();
+        self.reward_fees = self.reward_fees.checked_add(other.reward_fees).unwrap();
+        self.rewards_reinvested = self
+            .rewards_reinvested
+            .checked_add(other.rewards_reinvested)
+            .unwrap();
+    }
+}
+
+pub const PROTOCOL_REVENUE: Item<ProtocolRevenue> = Item::new("protocol_revenue");
+// (epoch) -> revenue collected during the epoch. Epochs are get_revenue_epoch_length seconds long.
+pub const PROTOCOL_REVENUE_EPOCHS: Map<u64, ProtocolRevenue> = Map::new("protocol_revenue_epochs");
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Admin,             // Grants and revokes roles, non-fee config, circuit breaker config, sweeps.
+    ValidatorOperator, // Adds, removes and rebalances validators.
+    FeeManager,        // Fee config, fee split and pending fee config changes.
+    Pauser,            // Operation flags.
+    Keeper,            // Bypasses the undelegation and reinvest cooldowns.
+    Guardian,          // Pauses operations but cannot unpause them.
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::ValidatorOperator => "validator_operator",
+            Role::FeeManager => "fee_manager",
+            Role::Pauser => "pauser",
+            Role::Keeper => "keeper",
+            Role::Guardian => "guardian",
+        }
+    }
+}
+
+// (Role, Address) -> membership. The manager implicitly holds every role.
+pub const ROLE_MEMBERS: Map<(&str, &Addr), bool> = Map::new("role_members");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PauseRecord {
+    pub id: u64,
+    pub operations: Vec<String>,
+    pub reason: Option<String>,
+    pub paused_by: Addr,
+    pub pause_time: u64,
+    pub expires_at: Option<u64>, // Block time in seconds. None holds until explicitly unpaused.
+}
+
+// (id) -> pause. Kept after the pause expires or is lifted.
+pub const PAUSE_HISTORY: Map<u64, PauseRecord> = Map::new("pause_history");
+pub const PAUSE_COUNT: Item<u64> = Item::new("pause_count");
+
+// (operation) -> id of the pause holding it.
+pub const ACTIVE_PAUSES: Map<&str, u64> = Map::new("active_pauses");
+
+// Delay in seconds before timelocked config changes can be executed. Zero applies them immediately.
+pub const CONFIG_TIMELOCK: Item<u64> = Item::new("config_timelock");
+
+// Max number of validators reinvested rewards are split across. Defaults to 1.
+pub const REINVEST_SPLIT_LIMIT: Item<u64> = Item::new("reinvest_split_limit");
+
+// Seconds over which reinvested rewards are added to the exchange rate. Zero adds them at once.
+pub const REWARD_VESTING_PERIOD: Item<u64> = Item::new("reward_vesting_period");
+
+// Seconds after a user's last deposit before they can queue undelegations. Zero disables it. Tokens
+// without a deposit record are not held back.
+pub const MIN_HOLDING_PERIOD: Item<u64> = Item::new("min_holding_period");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingConfigChange {
+    pub id: u64,
+    pub config_request: ConfigUpdateRequest,
+    pub submit_time: Timestamp,
+    pub executable_time: Timestamp,
+}
+
+pub const PENDING_CONFIG_CHANGES: Map<u64, PendingConfigChange> =
+    Map::new("pending_config_changes");
+pub const PENDING_CONFIG_CHANGE_COUNT: Item<u64> = Item::new("pending_config_change_count");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuditChange {
+    pub field: String,
+    pub old_value: String, // Empty when the field did not exist before, e.g. an added validator
+    pub new_value: String,
+}
+
+impl AuditChange {
+    pub fn new(field: &str, old_value: impl ToString, new_value: impl ToString) -> Self {
+        AuditChange {
+            field: field.to_string(),
+            old_value: old_value.to_string(),
+            new_value: new_value.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuditLogEntry {
+    pub id: u64,
+    pub sender: Addr,
+    pub block_height: u64,
+    pub time: Timestamp,
+    pub operation: String,
+    pub changes: Vec<AuditChange>,
+    #[serde(default)]
+    pub reason: Option<String>, // Set for manual recovery operations
+}
+
+// (id) -> privileged change. Append only, entries are never updated or removed.
+pub const AUDIT_LOG: Map<u64, AuditLogEntry> = Map::new("audit_log");
+pub const AUDIT_LOG_COUNT: Item<u64> = Item::new("audit_log_count");
+
+// (User_Address, Undelegation Batch) -> seconds the user had held LunaX when queueing the undelegation.
+pub const USER_HOLDING_PERIODS: Map<(&Addr, u64), u64> = Map::new("user_holding_periods");
+
 pub const CONFIG: Item<Config> = Item::new("config");
 pub const STATE: Item<State> = Item::new("state");
 pub const OPERATION_CONTROLS: Item<OperationControls> = Item::new("operation_controls");
@@ -144,12 +414,5 @@ pub struct AirdropRate {
     pub proof: Vec<String>,
 }
 
-// this is a tmp store to store the intermediate values of manager updates.
-// manager updates are 2 phase, we set it and then accept it. This is done to
-// add a greater assurance of the update.
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct TmpManagerStore {
-    pub manager: String,
-}
-
-pub const TMP_MANAGER_STORE: Item<TmpManagerStore> = Item::new("tmp_manager_store");
+// Manager updates are 2 phase and live in stader_utils::ownership, shared by all the contracts.
+pub use stader_utils::ownership::{TmpManagerStore, TMP_MANAGER_STORE};
@@ -2,11 +2,12 @@ use airdrops_registry::msg::{GetAirdropContractsResponse, QueryMsg as AirdropsQu
 use airdrops_registry::state::AirdropRegistryInfo;
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage};
 use cosmwasm_std::{
-    from_binary, from_slice, to_binary, Addr, Binary, Coin, ContractResult, Empty, FullDelegation,
-    OwnedDeps, Querier, QuerierResult, QueryRequest, SystemError, SystemResult, Uint128, Validator,
-    WasmQuery,
+    from_binary, from_slice, to_binary, Addr, BalanceResponse as BankBalanceResponse, BankQuery,
+    Binary, Coin, ContractResult, Empty, FullDelegation, OwnedDeps, Querier, QuerierResult,
+    QueryRequest, SystemError, SystemResult, Uint128, Validator, WasmQuery,
 };
 use cw20::{BalanceResponse, TokenInfoResponse};
+use reward::msg::{QueryMsg as RewardQueryMsg, VaultResponse as RewardVaultResponse};
 use std::collections::HashMap;
 pub const MOCK_CONTRACT_ADDR: &str = "cosmos2contract";
 
@@ -28,6 +29,7 @@ pub fn mock_dependencies(
 pub struct WasmMockQuerier {
     base: MockQuerier<Empty>,
     stader_querier: StaderQuerier,
+    reward_vault_balance: Uint128,
 }
 
 impl Querier for WasmMockQuerier {
@@ -59,11 +61,10 @@ impl WasmMockQuerier {
                 if contract_addr.eq("airdrop_registry_contract") {
                     match from_binary(msg).unwrap() {
                         AirdropsQueryMsg::GetAirdropContracts { token } => {
-                            let res: GetAirdropContractsResponse;
-                            if token.eq(&String::from("unreg_token")) {
-                                res = GetAirdropContractsResponse { contracts: None };
+                            let res = if token.eq(&String::from("unreg_token")) {
+                                GetAirdropContractsResponse { contracts: None }
                             } else {
-                                res = GetAirdropContractsResponse {
+                                GetAirdropContractsResponse {
                                     contracts: Some(AirdropRegistryInfo {
                                         token: token.clone(),
                                         airdrop_contract: Addr::unchecked(format!(
@@ -75,8 +76,36 @@ impl WasmMockQuerier {
                                             token.clone()
                                         )),
                                     }),
-                                };
-                            }
+                                }
+                            };
+                            SystemResult::Ok(ContractResult::from(to_binary(&res)))
+                        }
+                        _ => {
+                            let out = Binary::default();
+                            SystemResult::Ok(ContractResult::from(to_binary(&out)))
+                        }
+                    }
+                } else if contract_addr.eq("reward_contract") {
+                    match from_binary(msg).unwrap() {
+                        RewardQueryMsg::Vault { vault } => {
+                            let reward_contract_balance = self
+                                .base
+                                .handle_query(&QueryRequest::Bank(BankQuery::Balance {
+                                    address: contract_addr.to_string(),
+                                    denom: "uluna".to_string(),
+                                }))
+                                .unwrap()
+                                .unwrap();
+                            let reward_contract_balance: BankBalanceResponse =
+                                from_binary(&reward_contract_balance).unwrap();
+                            let res = RewardVaultResponse {
+                                vault: Addr::unchecked(vault),
+                                balance: self.reward_vault_balance,
+                                unallocated_rewards: reward_contract_balance
+                                    .amount
+                                    .amount
+                                    .saturating_sub(self.reward_vault_balance),
+                            };
                             SystemResult::Ok(ContractResult::from(to_binary(&res)))
                         }
                         _ => {
@@ -158,6 +187,7 @@ impl WasmMockQuerier {
         WasmMockQuerier {
             base,
             stader_querier: StaderQuerier::default(),
+            reward_vault_balance: Uint128::zero(),
         }
     }
 
@@ -168,4 +198,9 @@ impl WasmMockQuerier {
     ) {
         self.stader_querier = StaderQuerier::new(total_reward_tokens, user_to_tokens);
     }
+
+    // Rewards the reward contract has recorded for the staking contract.
+    pub fn update_reward_vault_balance(&mut self, balance: Uint128) {
+        self.reward_vault_balance = balance;
+    }
 }
@@ -1,3 +1,4 @@
 mod mock_querier;
 mod test_helpers;
+#[allow(clippy::module_inception)]
 mod tests;
@@ -1,32 +1,47 @@
 #[cfg(test)]
 mod tests {
     use crate::contract::{
-        check_slashing, compute_withdrawable_funds, execute, instantiate, query,
+        check_slashing, compute_withdrawable_funds, execute, instantiate, migrate, query, reply,
     };
     use crate::error::ContractError;
     use crate::helpers::{
-        get_active_validators_sorted_by_stake, get_validator_for_deposit, validate, Verify,
+        get_active_validators_sorted_by_stake, get_reinvest_split, get_validator_for_deposit,
+        record_protocol_revenue, record_user_deposit, validate, wrap_rewards_withdrawal, Verify,
     };
     use crate::msg::{
-        Cw20HookMsg, ExecuteMsg, GetFundsClaimRecord, InstantiateMsg, MerkleAirdropMsg,
-        QueryConfigResponse, QueryMsg, QueryStateResponse,
+        ActivePausesResponse, AprResponse, AuditLogResponse, BatchUserTokens,
+        CheckInvariantsResponse, CircuitBreakerResponse, Cw20HookMsg, EpochRevenue,
+        ExchangeRateHistoryResponse, ExecuteMsg, FeeSplitResponse, GetFundsClaimRecord,
+        HasRoleResponse, InflowTrackerResponse, InstantiateMsg, InvariantViolation,
+        MerkleAirdropMsg, MigrateMsg, PauseHistoryResponse, PendingConfigChangesResponse,
+        PendingRewardsResponse, ProtocolRevenueHistoryResponse, ProtocolRevenueResponse,
+        QueryConfigResponse, QueryMsg, QueryStateResponse, ReinvestSplitLimitResponse,
+        RewardVestingResponse, RoleMembersResponse, UserInfoResponse, ValidatorPendingRewards,
     };
     use crate::state::{
-        AirdropRate, BatchUndelegationRecord, Config, ConfigUpdateRequest, OperationControls,
-        OperationControlsUpdateRequest, State, UndelegationInfo, VMeta,
-        BATCH_UNDELEGATION_REGISTRY, CONFIG, OPERATION_CONTROLS, STATE, USERS, VALIDATOR_META,
+        AirdropRate, AuditChange, AuditLogEntry, BatchUndelegationRecord, CircuitBreakerConfig,
+        CircuitBreakerTrip, Config, ConfigUpdateRequest, ExchangeRateSnapshot,
+        HoldingPeriodDiscount, InflowTracker, OperationControls, OperationControlsUpdateRequest,
+        PauseRecord, PendingConfigChange, ProtocolRevenue, Role, State, UndelegationInfo, VMeta,
+        WithdrawFeeSchedule, WithdrawFeeTier, ACTIVE_PAUSES, BATCH_UNDELEGATION_REGISTRY,
+        CIRCUIT_BREAKER_CONFIG, CIRCUIT_BREAKER_TRIP, CONFIG, CONFIG_TIMELOCK,
+        EXCHANGE_RATE_SNAPSHOTS, INFLOW_TRACKER, OPERATION_CONTROLS, PROTOCOL_REVENUE, STATE,
+        USERS, USER_DEPOSITS, USER_HOLDING_PERIODS, VALIDATOR_META,
     };
     use crate::testing::mock_querier::{mock_dependencies, WasmMockQuerier};
     use crate::testing::test_helpers::check_equal_vec;
     use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
     use cosmwasm_std::{
-        from_binary, to_binary, Addr, Attribute, BankMsg, Coin, Decimal, Delegation,
-        DistributionMsg, Env, FullDelegation, MessageInfo, OwnedDeps, StakingMsg, StdResult,
-        SubMsg, Uint128, Validator, WasmMsg,
+        from_binary, to_binary, Addr, Attribute, BankMsg, Coin, CosmosMsg, Decimal, Delegation,
+        DistributionMsg, Env, Event, FullDelegation, MessageInfo, OwnedDeps, Reply, StakingMsg,
+        StdResult, SubMsg, SubMsgResponse, SubMsgResult, Uint128, Validator, WasmMsg,
     };
+    use cw2::set_contract_version;
     use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
     use reward::msg::ExecuteMsg as RewardExecuteMsg;
     use reward::state::{TmpManagerStore, TMP_MANAGER_STORE};
+    use stader_utils::fee_split::FeeSplitEntry;
+    use std::collections::HashMap;
 
     fn get_validators() -> Vec<Validator> {
         vec![
@@ -243,7 +258,10 @@ mod tests {
                     .time
                     .minus_seconds(config.config.reinvest_cooldown),
                 validators: vec![],
-                reconciled_funds_to_withdraw: Uint128::zero()
+                reconciled_funds_to_withdraw: Uint128::zero(),
+                unvested_rewards: Uint128::zero(),
+                rewards_vesting_start: env.block.time,
+                rewards_vesting_end: env.block.time,
             }
         );
     }
@@ -254,7 +272,7 @@ mod tests {
         let env = mock_env();
         let info = mock_info("creator", &[]);
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
         let valid1 = Addr::unchecked("valid0001");
         let valid2 = Addr::unchecked("valid0002");
@@ -333,11 +351,8 @@ mod tests {
                 },
             ]
         }
-        deps.querier.update_staking(
-            "uluna",
-            &*get_validators_test_3(),
-            &*get_delegations_test_3(),
-        );
+        deps.querier
+            .update_staking("uluna", &get_validators_test_3(), &get_delegations_test_3());
         let res = get_active_validators_sorted_by_stake(
             deps.as_mut().querier,
             vec![valid1.clone(), valid2.clone(), valid3.clone()],
@@ -360,7 +375,7 @@ mod tests {
         let env = mock_env();
         let info = mock_info("creator", &[]);
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
         let valid1 = Addr::unchecked("valid0001");
         let valid2 = Addr::unchecked("valid0002");
@@ -420,11 +435,8 @@ mod tests {
                 },
             ]
         }
-        deps.querier.update_staking(
-            "uluna",
-            &*get_validators_test_1(),
-            &*get_delegations_test_1(),
-        );
+        deps.querier
+            .update_staking("uluna", &get_validators_test_1(), &get_delegations_test_1());
         let res = get_validator_for_deposit(
             deps.as_mut().querier,
             vec![valid1.clone(), valid2.clone(), valid3.clone()],
@@ -483,11 +495,8 @@ mod tests {
                 },
             ]
         }
-        deps.querier.update_staking(
-            "uluna",
-            &*get_validators_test_2(),
-            &*get_delegations_test_2(),
-        );
+        deps.querier
+            .update_staking("uluna", &get_validators_test_2(), &get_delegations_test_2());
         let res = get_validator_for_deposit(
             deps.as_mut().querier,
             vec![valid1.clone(), valid2.clone(), valid3.clone()],
@@ -502,7 +511,7 @@ mod tests {
         let mut deps = mock_dependencies(&[]);
         let env = mock_env();
         let info = mock_info("creator", &[]);
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
         /*
            Test - 1. Check send manager
@@ -556,7 +565,7 @@ mod tests {
         let info = mock_info("creator", &[]);
         let env = mock_env();
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
         /*
            Unauthorized
@@ -639,7 +648,7 @@ mod tests {
         let env = mock_env();
         let info = mock_info("creator", &[]);
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
         let tmp_manager_store = TMP_MANAGER_STORE.may_load(deps.as_mut().storage).unwrap();
         assert_eq!(tmp_manager_store, None);
@@ -649,6 +658,7 @@ mod tests {
             mock_info("creator", &[]),
             ExecuteMsg::SetManager {
                 manager: "new_manager1".to_string(),
+                expires_in: None,
             },
         )
         .unwrap();
@@ -673,6 +683,7 @@ mod tests {
             mock_info("new_manager1", &[]),
             ExecuteMsg::SetManager {
                 manager: "new_manager2".to_string(),
+                expires_in: None,
             },
         )
         .unwrap();
@@ -697,7 +708,7 @@ mod tests {
         let env = mock_env();
         let info = mock_info("creator", &[]);
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
         /*
            Unauthorized
@@ -708,6 +719,7 @@ mod tests {
             mock_info("not-creator", &[]),
             ExecuteMsg::SetManager {
                 manager: "test_manager".to_string(),
+                expires_in: None,
             },
         )
         .unwrap_err();
@@ -722,6 +734,7 @@ mod tests {
             mock_info("creator", &[]),
             ExecuteMsg::SetManager {
                 manager: "test_manager".to_string(),
+                expires_in: None,
             },
         )
         .unwrap();
@@ -735,7 +748,7 @@ mod tests {
         let env = mock_env();
         let info = mock_info("creator", &[]);
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
         /*
            Empty tmp store
@@ -757,6 +770,7 @@ mod tests {
                 deps.as_mut().storage,
                 &TmpManagerStore {
                     manager: "new_manager".to_string(),
+                    expires_at: None,
                 },
             )
             .unwrap();
@@ -794,7 +808,7 @@ mod tests {
         let info = mock_info("creator", &[]);
         let env = mock_env();
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
         let initial_state = STATE.load(deps.as_mut().storage).unwrap();
 
         /*
@@ -816,6 +830,11 @@ mod tests {
                     unbonding_period: None,
                     undelegation_cooldown: None,
                     reinvest_cooldown: None,
+                    withdraw_fee_schedule: None,
+                    config_timelock: None,
+                    reinvest_split_limit: None,
+                    reward_vesting_period: None,
+                    min_holding_period: None,
                 },
             },
         )
@@ -839,6 +858,11 @@ mod tests {
                     unbonding_period: None,
                     undelegation_cooldown: None,
                     reinvest_cooldown: None,
+                    withdraw_fee_schedule: None,
+                    config_timelock: None,
+                    reinvest_split_limit: None,
+                    reward_vesting_period: None,
+                    min_holding_period: None,
                 },
             },
         )
@@ -865,6 +889,11 @@ mod tests {
                     unbonding_period: Some(1814400),
                     undelegation_cooldown: Some(258900u64),
                     reinvest_cooldown: Some(234u64),
+                    withdraw_fee_schedule: None,
+                    config_timelock: None,
+                    reinvest_split_limit: None,
+                    reward_vesting_period: None,
+                    min_holding_period: None,
                 },
             },
         )
@@ -890,6 +919,11 @@ mod tests {
                     unbonding_period: Some(1814400),
                     undelegation_cooldown: Some(258900u64),
                     reinvest_cooldown: Some(234u64),
+                    withdraw_fee_schedule: None,
+                    config_timelock: None,
+                    reinvest_split_limit: None,
+                    reward_vesting_period: None,
+                    min_holding_period: None,
                 },
             },
         )
@@ -915,6 +949,11 @@ mod tests {
                     unbonding_period: Some(1814400),
                     undelegation_cooldown: Some(258900u64),
                     reinvest_cooldown: Some(234u64),
+                    withdraw_fee_schedule: None,
+                    config_timelock: None,
+                    reinvest_split_limit: None,
+                    reward_vesting_period: None,
+                    min_holding_period: None,
                 },
             },
         )
@@ -940,6 +979,11 @@ mod tests {
                     unbonding_period: Some(100),
                     undelegation_cooldown: Some(258900u64),
                     reinvest_cooldown: Some(234u64),
+                    withdraw_fee_schedule: None,
+                    config_timelock: None,
+                    reinvest_split_limit: None,
+                    reward_vesting_period: None,
+                    min_holding_period: None,
                 },
             },
         )
@@ -965,6 +1009,11 @@ mod tests {
                     unbonding_period: Some(1814400),
                     undelegation_cooldown: Some(100),
                     reinvest_cooldown: Some(234u64),
+                    withdraw_fee_schedule: None,
+                    config_timelock: None,
+                    reinvest_split_limit: None,
+                    reward_vesting_period: None,
+                    min_holding_period: None,
                 },
             },
         )
@@ -990,6 +1039,11 @@ mod tests {
                     unbonding_period: Some(1814400),
                     undelegation_cooldown: Some(258900u64),
                     reinvest_cooldown: Some(234u64),
+                    withdraw_fee_schedule: None,
+                    config_timelock: None,
+                    reinvest_split_limit: None,
+                    reward_vesting_period: None,
+                    min_holding_period: None,
                 },
             },
         )
@@ -1012,6 +1066,11 @@ mod tests {
                     unbonding_period: Some(1814400),
                     undelegation_cooldown: Some(258900u64),
                     reinvest_cooldown: Some(234u64),
+                    withdraw_fee_schedule: None,
+                    config_timelock: None,
+                    reinvest_split_limit: None,
+                    reward_vesting_period: None,
+                    min_holding_period: None,
                 },
             },
         )
@@ -1034,6 +1093,11 @@ mod tests {
                     unbonding_period: Some(1814400),
                     undelegation_cooldown: Some(258900u64),
                     reinvest_cooldown: Some(234u64),
+                    withdraw_fee_schedule: None,
+                    config_timelock: None,
+                    reinvest_split_limit: None,
+                    reward_vesting_period: None,
+                    min_holding_period: None,
                 },
             },
         )
@@ -1074,6 +1138,183 @@ mod tests {
         assert_eq!(config.reinvest_cooldown, 234u64);
     }
 
+    #[test]
+    fn test_pending_config_changes() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let mut env = mock_env();
+
+        instantiate_contract(&mut deps, &info, &env);
+
+        /*
+           Test - 1. Timelock above the max is rejected. Increases apply immediately.
+        */
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateConfig {
+                config_request: ConfigUpdateRequest {
+                    config_timelock: Some(31 * 24 * 3600),
+                    ..Default::default()
+                },
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidConfigTimelock {}));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateConfig {
+                config_request: ConfigUpdateRequest {
+                    config_timelock: Some(1000),
+                    ..Default::default()
+                },
+            },
+        )
+        .unwrap();
+
+        /*
+           Test - 2. Fee changes are queued while address changes apply immediately
+        */
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateConfig {
+                config_request: ConfigUpdateRequest {
+                    protocol_withdraw_fee: Some(Decimal::from_ratio(3_u128, 100_u128)),
+                    airdrop_registry_contract: Some("new_airdrop_registry".to_string()),
+                    config_timelock: Some(500),
+                    ..Default::default()
+                },
+            },
+        )
+        .unwrap();
+        assert_eq!(res.events[0].ty, "lunax-update-config");
+        assert_eq!(
+            res.events[0].attributes,
+            vec![
+                Attribute::new("protocol_withdraw_fee", "0.03"),
+                Attribute::new("pending_config_change", "1")
+            ]
+        );
+        let config = CONFIG.load(deps.as_mut().storage).unwrap();
+        assert_eq!(
+            config.protocol_withdraw_fee,
+            Decimal::from_ratio(1_u128, 100_u128)
+        );
+        assert_eq!(
+            config.airdrop_registry_contract,
+            Addr::unchecked("new_airdrop_registry")
+        );
+        let res: PendingConfigChangesResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::PendingConfigChanges {},
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            res,
+            PendingConfigChangesResponse {
+                config_timelock: 1000,
+                pending_config_changes: vec![PendingConfigChange {
+                    id: 1,
+                    config_request: ConfigUpdateRequest {
+                        protocol_withdraw_fee: Some(Decimal::from_ratio(3_u128, 100_u128)),
+                        config_timelock: Some(500),
+                        ..Default::default()
+                    },
+                    submit_time: env.block.time,
+                    executable_time: env.block.time.plus_seconds(1000),
+                }]
+            }
+        );
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateConfig {
+                config_request: ConfigUpdateRequest {
+                    reinvest_cooldown: Some(100),
+                    ..Default::default()
+                },
+            },
+        )
+        .unwrap();
+
+        /*
+           Test - 3. Changes can only be executed after the timelock
+        */
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::ExecutePendingConfig {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PendingConfigChangeNotReady {}));
+
+        /*
+           Test - 4. Only the manager can cancel a pending change
+        */
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::CancelPendingConfig { id: 2 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::CancelPendingConfig { id: 2 },
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::CancelPendingConfig { id: 2 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PendingConfigChangeNotFound {}));
+
+        env.block.time = env.block.time.plus_seconds(1000);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::ExecutePendingConfig {},
+        )
+        .unwrap();
+        let config = CONFIG.load(deps.as_mut().storage).unwrap();
+        assert_eq!(
+            config.protocol_withdraw_fee,
+            Decimal::from_ratio(3_u128, 100_u128)
+        );
+        assert_eq!(config.reinvest_cooldown, 10);
+        let res: PendingConfigChangesResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::PendingConfigChanges {},
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.config_timelock, 500);
+        assert!(res.pending_config_changes.is_empty());
+    }
+
     fn convert_to_delegation(full_delegations: Vec<FullDelegation>) -> Vec<Delegation> {
         full_delegations
             .iter()
@@ -1091,7 +1332,7 @@ mod tests {
         let info = mock_info("creator", &[]);
         let env = mock_env();
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
         let valid1 = Addr::unchecked("valid0001");
         let valid2 = Addr::unchecked("valid0002");
@@ -1101,7 +1342,7 @@ mod tests {
            Test - 1. There is no slashing
         */
         deps.querier
-            .update_staking("uluna", &*get_validators(), &*get_delegations());
+            .update_staking("uluna", &get_validators(), &get_delegations());
         deps.querier
             .update_stader_balances(Some(Uint128::new(3000_u128)), None);
         VALIDATOR_META
@@ -1237,11 +1478,8 @@ mod tests {
                 },
             ]
         }
-        deps.querier.update_staking(
-            "uluna",
-            &*get_validators_test_2(),
-            &*get_delegations_test_2(),
-        );
+        deps.querier
+            .update_staking("uluna", &get_validators_test_2(), &get_delegations_test_2());
         deps.querier
             .update_stader_balances(Some(Uint128::new(3000_u128)), None);
         VALIDATOR_META
@@ -1370,11 +1608,8 @@ mod tests {
                 },
             ]
         }
-        deps.querier.update_staking(
-            "uluna",
-            &*get_validators_test_3(),
-            &*get_delegations_test_3(),
-        );
+        deps.querier
+            .update_staking("uluna", &get_validators_test_3(), &get_delegations_test_3());
         deps.querier
             .update_stader_balances(Some(Uint128::new(3000_u128)), None);
         VALIDATOR_META
@@ -1459,7 +1694,7 @@ mod tests {
         let info = mock_info("creator", &[]);
         let env = mock_env();
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
         /*
            Test - 1. Unauthorized
@@ -1503,7 +1738,7 @@ mod tests {
             Test - 3. Validator not discoverable
         */
         deps.querier
-            .update_staking("uluna", &*get_validators(), &*get_delegations());
+            .update_staking("uluna", &get_validators(), &get_delegations());
         let err = execute(
             deps.as_mut(),
             env.clone(),
@@ -1522,14 +1757,14 @@ mod tests {
         let info = mock_info("creator", &[]);
         let env = mock_env();
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
         /*
            Test - 1. Successful add
         */
         let val_addr = Addr::unchecked("valid0001");
         deps.querier
-            .update_staking("uluna", &*get_validators(), &*get_delegations());
+            .update_staking("uluna", &get_validators(), &get_delegations());
         let res = execute(
             deps.as_mut(),
             env.clone(),
@@ -1561,7 +1796,7 @@ mod tests {
         let info = mock_info("creator", &[]);
         let env = mock_env();
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
         let valid1 = Addr::unchecked("valid0001");
         let valid2 = Addr::unchecked("valid0002");
@@ -1623,7 +1858,7 @@ mod tests {
             )
             .unwrap();
         deps.querier
-            .update_staking("uluna", &*get_validators(), &*get_delegations());
+            .update_staking("uluna", &get_validators(), &get_delegations());
         deps.querier
             .update_stader_balances(Some(Uint128::new(3000_u128)), None);
         VALIDATOR_META
@@ -1666,20 +1901,27 @@ mod tests {
             ExecuteMsg::RedeemRewards { validators: None },
         )
         .unwrap();
-        assert_eq!(res.messages.len(), 3);
+        assert_eq!(res.messages.len(), 5);
         assert!(check_equal_vec(
             res.messages,
-            vec![
-                SubMsg::new(DistributionMsg::WithdrawDelegatorReward {
-                    validator: "valid0001".to_string()
-                }),
-                SubMsg::new(DistributionMsg::WithdrawDelegatorReward {
-                    validator: "valid0002".to_string()
-                }),
-                SubMsg::new(DistributionMsg::WithdrawDelegatorReward {
-                    validator: "valid0003".to_string()
-                })
-            ]
+            wrap_rewards_withdrawal(
+                &Addr::unchecked("reward_contract"),
+                vec![
+                    DistributionMsg::WithdrawDelegatorReward {
+                        validator: "valid0001".to_string()
+                    }
+                    .into(),
+                    DistributionMsg::WithdrawDelegatorReward {
+                        validator: "valid0002".to_string()
+                    }
+                    .into(),
+                    DistributionMsg::WithdrawDelegatorReward {
+                        validator: "valid0003".to_string()
+                    }
+                    .into()
+                ]
+            )
+            .unwrap()
         ));
 
         /*
@@ -1701,7 +1943,7 @@ mod tests {
             )
             .unwrap();
         deps.querier
-            .update_staking("uluna", &*get_validators(), &*get_delegations());
+            .update_staking("uluna", &get_validators(), &get_delegations());
         deps.querier
             .update_stader_balances(Some(Uint128::new(3000_u128)), None);
         VALIDATOR_META
@@ -1755,20 +1997,27 @@ mod tests {
                 value: "valid0004".to_string()
             }]
         );
-        assert_eq!(res.messages.len(), 3);
+        assert_eq!(res.messages.len(), 5);
         assert!(check_equal_vec(
             res.messages,
-            vec![
-                SubMsg::new(DistributionMsg::WithdrawDelegatorReward {
-                    validator: "valid0001".to_string()
-                }),
-                SubMsg::new(DistributionMsg::WithdrawDelegatorReward {
-                    validator: "valid0002".to_string()
-                }),
-                SubMsg::new(DistributionMsg::WithdrawDelegatorReward {
-                    validator: "valid0003".to_string()
-                })
-            ]
+            wrap_rewards_withdrawal(
+                &Addr::unchecked("reward_contract"),
+                vec![
+                    DistributionMsg::WithdrawDelegatorReward {
+                        validator: "valid0001".to_string()
+                    }
+                    .into(),
+                    DistributionMsg::WithdrawDelegatorReward {
+                        validator: "valid0002".to_string()
+                    }
+                    .into(),
+                    DistributionMsg::WithdrawDelegatorReward {
+                        validator: "valid0003".to_string()
+                    }
+                    .into()
+                ]
+            )
+            .unwrap()
         ));
 
         /*
@@ -1790,7 +2039,7 @@ mod tests {
             )
             .unwrap();
         deps.querier
-            .update_staking("uluna", &*get_validators(), &*get_delegations());
+            .update_staking("uluna", &get_validators(), &get_delegations());
         deps.querier
             .update_stader_balances(Some(Uint128::new(3000_u128)), None);
         VALIDATOR_META
@@ -1838,17 +2087,23 @@ mod tests {
             },
         )
         .unwrap();
-        assert_eq!(res.messages.len(), 2);
+        assert_eq!(res.messages.len(), 4);
         assert!(check_equal_vec(
             res.messages,
-            vec![
-                SubMsg::new(DistributionMsg::WithdrawDelegatorReward {
-                    validator: "valid0001".to_string()
-                }),
-                SubMsg::new(DistributionMsg::WithdrawDelegatorReward {
-                    validator: "valid0002".to_string()
-                }),
-            ]
+            wrap_rewards_withdrawal(
+                &Addr::unchecked("reward_contract"),
+                vec![
+                    DistributionMsg::WithdrawDelegatorReward {
+                        validator: "valid0001".to_string()
+                    }
+                    .into(),
+                    DistributionMsg::WithdrawDelegatorReward {
+                        validator: "valid0002".to_string()
+                    }
+                    .into(),
+                ]
+            )
+            .unwrap()
         ));
     }
 
@@ -1858,7 +2113,7 @@ mod tests {
         let info = mock_info("creator", &[]);
         let env = mock_env();
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
         /*
            Test - 1. Unauthorized
@@ -1938,7 +2193,7 @@ mod tests {
             )
             .unwrap();
         deps.querier
-            .update_staking("uluna", &*get_validators(), &*get_delegations());
+            .update_staking("uluna", &get_validators(), &get_delegations());
         deps.querier
             .update_stader_balances(Some(Uint128::new(3000_u128)), None);
         /*
@@ -1970,7 +2225,7 @@ mod tests {
             )
             .unwrap();
         deps.querier
-            .update_staking("uluna", &*get_validators(), &*get_delegations());
+            .update_staking("uluna", &get_validators(), &get_delegations());
         let err = execute(
             deps.as_mut(),
             env.clone(),
@@ -1997,7 +2252,7 @@ mod tests {
             )
             .unwrap();
         deps.querier
-            .update_staking("uluna", &*get_validators(), &*get_delegations());
+            .update_staking("uluna", &get_validators(), &get_delegations());
         let err = execute(
             deps.as_mut(),
             env.clone(),
@@ -2063,7 +2318,7 @@ mod tests {
             ]
         }
         deps.querier
-            .update_staking("uluna", &*get_validators_test(), &*get_delegations_test());
+            .update_staking("uluna", &get_validators_test(), &get_delegations_test());
         STATE
             .update(
                 deps.as_mut().storage,
@@ -2093,7 +2348,7 @@ mod tests {
         let info = mock_info("creator", &[]);
         let env = mock_env();
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
         /*
            Test - 1. Success
@@ -2144,7 +2399,7 @@ mod tests {
             )
             .unwrap();
         deps.querier
-            .update_staking("uluna", &*get_validators(), &*get_delegations());
+            .update_staking("uluna", &get_validators(), &get_delegations());
         deps.querier
             .update_stader_balances(Some(Uint128::new(3000_u128)), None);
         let res = execute(
@@ -2158,14 +2413,19 @@ mod tests {
             },
         )
         .unwrap();
-        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages.len(), 3);
         assert!(check_equal_vec(
             res.messages,
-            vec![SubMsg::new(StakingMsg::Redelegate {
-                src_validator: valid1.to_string(),
-                dst_validator: valid2.to_string(),
-                amount: Coin::new(100_u128, "uluna".to_string())
-            })]
+            wrap_rewards_withdrawal(
+                &Addr::unchecked("reward_contract"),
+                vec![StakingMsg::Redelegate {
+                    src_validator: valid1.to_string(),
+                    dst_validator: valid2.to_string(),
+                    amount: Coin::new(100_u128, "uluna".to_string())
+                }
+                .into()]
+            )
+            .unwrap()
         ));
         let val1_meta = VALIDATOR_META.load(deps.as_mut().storage, &valid1).unwrap();
         let val2_meta = VALIDATOR_META.load(deps.as_mut().storage, &valid2).unwrap();
@@ -2193,7 +2453,7 @@ mod tests {
         let info = mock_info("creator", &[]);
         let env = mock_env();
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
         let valid1 = Addr::unchecked("valid0001");
         let valid2 = Addr::unchecked("valid0002");
@@ -2208,7 +2468,7 @@ mod tests {
             )
             .unwrap();
         deps.querier
-            .update_staking("uluna", &*get_validators(), &*get_delegations());
+            .update_staking("uluna", &get_validators(), &get_delegations());
         deps.querier
             .update_stader_balances(Some(Uint128::new(3000_u128)), None);
         VALIDATOR_META
@@ -2311,7 +2571,7 @@ mod tests {
         let info = mock_info("creator", &[]);
         let env = mock_env();
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
         /*
            Test - 1. Validator with delegation
@@ -2329,7 +2589,7 @@ mod tests {
             )
             .unwrap();
         deps.querier
-            .update_staking("uluna", &*get_validators(), &*get_delegations());
+            .update_staking("uluna", &get_validators(), &get_delegations());
         deps.querier
             .update_stader_balances(Some(Uint128::new(3000_u128)), None);
         VALIDATOR_META
@@ -2375,14 +2635,19 @@ mod tests {
             },
         )
         .unwrap();
-        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages.len(), 3);
         assert!(check_equal_vec(
             res.messages,
-            vec![SubMsg::new(StakingMsg::Redelegate {
-                src_validator: valid1.to_string(),
-                dst_validator: valid2.to_string(),
-                amount: Coin::new(1000_u128, "uluna".to_string())
-            })]
+            wrap_rewards_withdrawal(
+                &Addr::unchecked("reward_contract"),
+                vec![StakingMsg::Redelegate {
+                    src_validator: valid1.to_string(),
+                    dst_validator: valid2.to_string(),
+                    amount: Coin::new(1000_u128, "uluna".to_string())
+                }
+                .into()]
+            )
+            .unwrap()
         ));
         let val1_meta = VALIDATOR_META
             .may_load(deps.as_mut().storage, &valid1)
@@ -2410,7 +2675,7 @@ mod tests {
         let info = mock_info("creator", &[]);
         let env = mock_env();
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
         let valid1 = Addr::unchecked("valid0001");
         let valid2 = Addr::unchecked("valid0002");
@@ -2502,7 +2767,7 @@ mod tests {
             )
             .unwrap();
         deps.querier
-            .update_staking("uluna", &*get_validators(), &*get_delegations());
+            .update_staking("uluna", &get_validators(), &get_delegations());
         deps.querier
             .update_stader_balances(Some(Uint128::new(3000_u128)), None);
 
@@ -2555,7 +2820,7 @@ mod tests {
         let info = mock_info("creator", &[]);
         let env = mock_env();
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
         /*
            Test - 1. Successful deposit
@@ -2574,7 +2839,7 @@ mod tests {
             )
             .unwrap();
         deps.querier
-            .update_staking("uluna", &*get_validators(), &*get_delegations());
+            .update_staking("uluna", &get_validators(), &get_delegations());
         deps.querier
             .update_stader_balances(Some(Uint128::new(3000_u128)), None);
         VALIDATOR_META
@@ -2618,31 +2883,48 @@ mod tests {
         )
         .unwrap();
         let config = CONFIG.load(deps.as_mut().storage).unwrap();
-        assert_eq!(res.messages.len(), 3);
-        assert_eq!(
-            res.messages,
-            vec![
-                SubMsg::new(BankMsg::Send {
-                    to_address: "protocol_fee_contract".to_string(),
-                    amount: vec![Coin::new(10_u128, "uluna")]
-                }),
-                SubMsg::new(StakingMsg::Delegate {
+        assert_eq!(res.messages.len(), 5);
+        let mut expected_msgs = vec![SubMsg::new(BankMsg::Send {
+            to_address: "protocol_fee_contract".to_string(),
+            amount: vec![Coin::new(10_u128, "uluna")],
+        })];
+        expected_msgs.extend(
+            wrap_rewards_withdrawal(
+                &config.reward_contract,
+                vec![StakingMsg::Delegate {
                     validator: valid1.to_string(),
-                    amount: Coin::new(990_u128, "uluna".to_string())
-                }),
-                SubMsg::new(WasmMsg::Execute {
-                    contract_addr: config.cw20_token_contract.to_string(),
-                    msg: to_binary(&Cw20ExecuteMsg::Mint {
-                        recipient: "other".to_string(),
-                        amount: Uint128::new(990_u128)
-                    })
-                    .unwrap(),
-                    funds: vec![]
-                })
-            ]
+                    amount: Coin::new(990_u128, "uluna".to_string()),
+                }
+                .into()],
+            )
+            .unwrap(),
+        );
+        expected_msgs.push(SubMsg::new(WasmMsg::Execute {
+            contract_addr: config.cw20_token_contract.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: "other".to_string(),
+                amount: Uint128::new(990_u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        }));
+        assert_eq!(res.messages, expected_msgs);
+        assert_eq!(
+            res.events,
+            vec![Event::new("lunax-deposit").add_attributes(vec![
+                ("user", "other"),
+                ("amount", "1000"),
+                ("staked_amount", "990"),
+                ("protocol_fee", "10"),
+                ("tokens_minted", "990"),
+                ("exchange_rate", "1"),
+                ("validator", "valid0001"),
+            ])]
         );
         let state = STATE.load(deps.as_mut().storage).unwrap();
         assert_eq!(state.total_staked, Uint128::new(3990_u128));
+        let protocol_revenue = PROTOCOL_REVENUE.load(deps.as_mut().storage).unwrap();
+        assert_eq!(protocol_revenue.deposit_fees, Uint128::new(10_u128));
         let val1_meta = VALIDATOR_META.load(deps.as_mut().storage, &valid1).unwrap();
         assert_eq!(
             val1_meta,
@@ -2660,7 +2942,7 @@ mod tests {
         let info = mock_info("creator", &[]);
         let env = mock_env();
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
         CONFIG
             .update(
@@ -2690,7 +2972,7 @@ mod tests {
             )
             .unwrap();
         deps.querier
-            .update_staking("uluna", &*get_validators(), &*get_delegations());
+            .update_staking("uluna", &get_validators(), &get_delegations());
         deps.querier
             .update_stader_balances(Some(Uint128::new(3000_u128)), None);
         VALIDATOR_META
@@ -2777,7 +3059,7 @@ mod tests {
         let info = mock_info("creator", &[]);
         let env = mock_env();
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
         /*
            reinvest paused
@@ -2856,7 +3138,7 @@ mod tests {
         deps.querier
             .update_stader_balances(Some(Uint128::new(3000_u128)), None);
         deps.querier
-            .update_staking("uluna", &*get_validators(), &*get_delegations());
+            .update_staking("uluna", &get_validators(), &get_delegations());
         STATE
             .update(
                 deps.as_mut().storage,
@@ -2900,6 +3182,17 @@ mod tests {
                 },
             )
             .unwrap();
+        INFLOW_TRACKER
+            .save(
+                deps.as_mut().storage,
+                &InflowTracker {
+                    expected_rewards: Uint128::new(1000_u128),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        deps.querier
+            .update_reward_vault_balance(Uint128::new(1000_u128));
         let res = execute(
             deps.as_mut(),
             env.clone(),
@@ -2907,30 +3200,40 @@ mod tests {
             ExecuteMsg::Reinvest {},
         )
         .unwrap();
-        assert_eq!(res.messages.len(), 2);
-        assert!(check_equal_vec(
-            res.messages,
-            vec![
-                SubMsg::new(WasmMsg::Execute {
-                    contract_addr: config.reward_contract.to_string(),
-                    msg: to_binary(&RewardExecuteMsg::Transfer {
-                        reward_amount: Uint128::new(990_u128),
-                        reward_withdraw_contract: env.contract.address,
-                        protocol_fee_amount: Uint128::new(10_u128),
-                        protocol_fee_contract: config.protocol_fee_contract
-                    })
-                    .unwrap(),
-                    funds: vec![]
-                }),
-                SubMsg::new(StakingMsg::Delegate {
+        assert_eq!(res.messages.len(), 4);
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: config.reward_contract.to_string(),
+                msg: to_binary(&RewardExecuteMsg::Transfer {
+                    reward_amount: Uint128::new(990_u128),
+                    reward_withdraw_contract: env.contract.address.clone(),
+                    protocol_fee_amount: Uint128::new(10_u128),
+                    protocol_fee_contract: config.protocol_fee_contract.clone(),
+                    protocol_fee_split: vec![]
+                })
+                .unwrap(),
+                funds: vec![]
+            })
+        );
+        assert_eq!(
+            res.messages[1..].to_vec(),
+            wrap_rewards_withdrawal(
+                &config.reward_contract,
+                vec![StakingMsg::Delegate {
                     validator: valid1.to_string(),
                     amount: Coin::new(990_u128, "uluna".to_string())
-                })
-            ]
-        ));
+                }
+                .into()]
+            )
+            .unwrap()
+        );
         let state = STATE.load(deps.as_mut().storage).unwrap();
         assert_eq!(state.last_reinvest_time, env.block.time);
         assert_eq!(state.total_staked, Uint128::new(3990_u128));
+        let protocol_revenue = PROTOCOL_REVENUE.load(deps.as_mut().storage).unwrap();
+        assert_eq!(protocol_revenue.reward_fees, Uint128::new(10_u128));
+        assert_eq!(protocol_revenue.rewards_reinvested, Uint128::new(990_u128));
         assert_eq!(
             state.exchange_rate,
             Decimal::from_ratio(3990_u128, 3000_u128)
@@ -2938,786 +3241,3911 @@ mod tests {
         assert_eq!(state.last_reinvest_time, env.block.time);
         let val1_meta = VALIDATOR_META.load(deps.as_mut().storage, &valid1).unwrap();
         assert_eq!(val1_meta.staked, Uint128::new(1990_u128));
-    }
-
-    #[test]
-    fn test_claim_airdrops_fail() {
-        let mut deps = mock_dependencies(&[]);
-        let info = mock_info("creator", &[]);
-        let env = mock_env();
-
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        let snapshot = EXCHANGE_RATE_SNAPSHOTS
+            .load(deps.as_mut().storage, env.block.time.seconds())
+            .unwrap();
+        assert_eq!(
+            snapshot,
+            ExchangeRateSnapshot {
+                exchange_rate: Decimal::from_ratio(3990_u128, 3000_u128),
+                total_staked: Uint128::new(3990_u128),
+                total_token_supply: Uint128::new(3000_u128),
+                gross_exchange_rate: Decimal::from_ratio(4000_u128, 3000_u128),
+                time: env.block.time,
+            }
+        );
 
         /*
-           Test - 1. Airdrop not registered
+           Swap proceeds recorded for the vault are reinvested along with the withdrawn rewards
         */
-        CONFIG
-            .update(
+        let mut reinvest_env = env.clone();
+        reinvest_env.block.time = reinvest_env.block.time.plus_seconds(1000);
+        INFLOW_TRACKER
+            .save(
                 deps.as_mut().storage,
-                |mut config| -> Result<_, ContractError> {
-                    config.airdrop_withdrawal_contract =
-                        Addr::unchecked("airdrop_withdrawal_contract");
-                    config.airdrop_registry_contract = Addr::unchecked("airdrop_registry_contract");
-                    Ok(config)
+                &InflowTracker {
+                    expected_rewards: Uint128::new(100_u128),
+                    ..Default::default()
                 },
             )
             .unwrap();
-        let err = execute(
+        deps.querier.update_balance(
+            config.reward_contract.clone(),
+            vec![Coin::new(600_u128, "uluna".to_string())],
+        );
+        deps.querier
+            .update_reward_vault_balance(Uint128::new(600_u128));
+        let res = execute(
             deps.as_mut(),
-            env.clone(),
+            reinvest_env.clone(),
             mock_info("creator", &[]),
-            ExecuteMsg::ClaimAirdrops {
-                rates: vec![
-                    AirdropRate {
-                        denom: "unreg_token".to_string(), // special name to test for an unregistered token
-                        amount: Uint128::new(1000_u128),
-                        stage: 0,
-                        proof: vec![
-                            "unreg_token_proof1".to_string(),
-                            "unreg_token_proof2".to_string(),
-                        ],
-                    },
-                    AirdropRate {
-                        denom: "anc".to_string(),
-                        amount: Uint128::new(1000_u128),
-                        stage: 0,
-                        proof: vec!["anc_proof1".to_string(), "anc_proof2".to_string()],
-                    },
-                ],
-            },
+            ExecuteMsg::Reinvest {},
         )
-        .unwrap_err();
-        assert!(matches!(
-            err,
-            ContractError::AirdropNotRegistered(String { .. })
-        ));
+        .unwrap();
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: config.reward_contract.to_string(),
+                msg: to_binary(&RewardExecuteMsg::Transfer {
+                    reward_amount: Uint128::new(594_u128),
+                    reward_withdraw_contract: reinvest_env.contract.address.clone(),
+                    protocol_fee_amount: Uint128::new(6_u128),
+                    protocol_fee_contract: config.protocol_fee_contract.clone(),
+                    protocol_fee_split: vec![]
+                })
+                .unwrap(),
+                funds: vec![]
+            })
+        );
+        let delegated: u128 = res
+            .messages
+            .iter()
+            .filter_map(|x| match &x.msg {
+                CosmosMsg::Staking(StakingMsg::Delegate { amount, .. }) => {
+                    Some(amount.amount.u128())
+                }
+                _ => None,
+            })
+            .sum();
+        assert_eq!(delegated, 594_u128);
+        let state = STATE.load(deps.as_mut().storage).unwrap();
+        assert_eq!(state.total_staked, Uint128::new(3594_u128));
+        // Only the rewards pending on the delegated validator are expected now.
+        let inflow_tracker = INFLOW_TRACKER.load(deps.as_mut().storage).unwrap();
+        assert_eq!(inflow_tracker.expected_rewards, Uint128::new(20_u128));
     }
 
     #[test]
-    fn test_claim_airdrops_success() {
+    fn test_exchange_rate_history() {
         let mut deps = mock_dependencies(&[]);
         let info = mock_info("creator", &[]);
         let env = mock_env();
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
-        /*
-           Test - 1. Success
-        */
-        CONFIG
+        let res: ExchangeRateHistoryResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::ExchangeRateHistory {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(res.snapshots.is_empty());
+
+        let valid1 = Addr::unchecked("valid0001");
+        let valid2 = Addr::unchecked("valid0002");
+        let valid3 = Addr::unchecked("valid0003");
+        let config = CONFIG.load(deps.as_mut().storage).unwrap();
+        deps.querier.update_balance(
+            config.reward_contract,
+            vec![Coin::new(1000_u128, "uluna".to_string())],
+        );
+        deps.querier
+            .update_stader_balances(Some(Uint128::new(3000_u128)), None);
+        deps.querier
+            .update_staking("uluna", &get_validators(), &get_delegations());
+        STATE
             .update(
                 deps.as_mut().storage,
-                |mut config| -> Result<_, ContractError> {
-                    config.airdrop_withdrawal_contract =
-                        Addr::unchecked("airdrop_withdrawal_contract");
-                    config.airdrop_registry_contract = Addr::unchecked("airdrop_registry_contract");
-                    Ok(config)
+                |mut state| -> Result<_, ContractError> {
+                    state.validators = vec![valid1.clone(), valid2.clone(), valid3.clone()];
+                    Ok(state)
                 },
             )
             .unwrap();
-        let res = execute(
-            deps.as_mut(),
-            env.clone(),
-            mock_info("creator", &[]),
-            ExecuteMsg::ClaimAirdrops {
-                rates: vec![
-                    AirdropRate {
-                        denom: "anc".to_string(),
-                        amount: Uint128::new(1000_u128),
-                        stage: 0,
-                        proof: vec!["anc_proof1".to_string(), "anc_proof2".to_string()],
-                    },
-                    AirdropRate {
-                        denom: "mir".to_string(),
-                        amount: Uint128::new(2000_u128),
-                        stage: 0,
-                        proof: vec!["mir_proof1".to_string(), "mir_proof2".to_string()],
+
+        /*
+           Test - 1. One snapshot per reinvest
+        */
+        let mut reinvest_env = env.clone();
+        deps.querier
+            .update_reward_vault_balance(Uint128::new(1000_u128));
+        for _ in 0..3 {
+            reinvest_env.block.time = reinvest_env.block.time.plus_seconds(100);
+            INFLOW_TRACKER
+                .save(
+                    deps.as_mut().storage,
+                    &InflowTracker {
+                        expected_rewards: Uint128::new(1000_u128),
+                        ..Default::default()
                     },
-                ],
-            },
+                )
+                .unwrap();
+            execute(
+                deps.as_mut(),
+                reinvest_env.clone(),
+                mock_info("other", &[]),
+                ExecuteMsg::Reinvest {},
+            )
+            .unwrap();
+        }
+
+        let res: ExchangeRateHistoryResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::ExchangeRateHistory {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
         )
         .unwrap();
-        let config = CONFIG.load(deps.as_mut().storage).unwrap();
-        assert_eq!(res.messages.len(), 4);
-        assert!(check_equal_vec(
-            res.messages,
-            vec![
-                SubMsg::new(WasmMsg::Execute {
-                    contract_addr: "anc_airdrop_contract".to_string(),
-                    msg: to_binary(&MerkleAirdropMsg::Claim {
-                        stage: 0,
-                        amount: Uint128::new(1000_u128),
-                        proof: vec!["anc_proof1".to_string(), "anc_proof2".to_string()],
-                    })
-                    .unwrap(),
-                    funds: vec![]
-                }),
-                SubMsg::new(WasmMsg::Execute {
-                    contract_addr: "mir_airdrop_contract".to_string(),
-                    msg: to_binary(&MerkleAirdropMsg::Claim {
-                        stage: 0,
-                        amount: Uint128::new(2000_u128),
-                        proof: vec!["mir_proof1".to_string(), "mir_proof2".to_string()],
-                    })
-                    .unwrap(),
-                    funds: vec![]
-                }),
-                SubMsg::new(WasmMsg::Execute {
-                    contract_addr: "anc_cw20_contract".to_string(),
-                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                        recipient: config.airdrop_withdrawal_contract.to_string(),
-                        amount: Uint128::new(1000_u128)
-                    })
-                    .unwrap(),
-                    funds: vec![]
-                }),
-                SubMsg::new(WasmMsg::Execute {
-                    contract_addr: "mir_cw20_contract".to_string(),
-                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                        recipient: config.airdrop_withdrawal_contract.to_string(),
-                        amount: Uint128::new(2000_u128)
-                    })
-                    .unwrap(),
-                    funds: vec![]
-                })
-            ]
-        ));
+        assert_eq!(res.snapshots.len(), 3);
+        assert_eq!(res.snapshots[0].time, env.block.time.plus_seconds(100));
+        assert_eq!(res.snapshots[2].time, env.block.time.plus_seconds(300));
+        assert_eq!(
+            res.snapshots[0].exchange_rate,
+            Decimal::from_ratio(3990_u128, 3000_u128)
+        );
+
+        /*
+           Test - 2. Pagination
+        */
+        let res: ExchangeRateHistoryResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::ExchangeRateHistory {
+                    start_after: Some(env.block.time.plus_seconds(100).seconds()),
+                    limit: Some(1),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.snapshots.len(), 1);
+        assert_eq!(res.snapshots[0].time, env.block.time.plus_seconds(200));
     }
 
     #[test]
-    fn test_compute_withdrawable_funds_fail() {
+    fn test_query_apr() {
         let mut deps = mock_dependencies(&[]);
         let info = mock_info("creator", &[]);
         let env = mock_env();
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
-
-        let user1 = Addr::unchecked("user1");
+        instantiate_contract(&mut deps, &info, &env);
 
         /*
-           Test - 1. Undelegation batch not found
+           Test - 1. No checkpoints yet
         */
-        let err = compute_withdrawable_funds(deps.as_mut().storage, 0, &user1).unwrap_err();
-        assert!(matches!(err, ContractError::UndelegationBatchNotFound {}));
+        let res: AprResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Apr {
+                    window_seconds: 3600,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            res,
+            AprResponse {
+                apr: Decimal::zero(),
+                gross_apr: Decimal::zero(),
+                start_time: None,
+                end_time: None
+            }
+        );
+
+        let year = 365 * 24 * 3600;
+        // Gross exchange rates grow independently of the protocol fee in the config
+        for (time, er, gross_er) in [
+            (
+                env.block.time.minus_seconds(year),
+                Decimal::one(),
+                Decimal::one(),
+            ),
+            (
+                env.block.time.minus_seconds(year / 2),
+                Decimal::from_ratio(105_u128, 100_u128),
+                Decimal::from_ratio(106_u128, 100_u128),
+            ),
+            (
+                env.block.time,
+                Decimal::from_ratio(1155_u128, 1000_u128),
+                Decimal::from_ratio(11766_u128, 10000_u128),
+            ),
+        ] {
+            EXCHANGE_RATE_SNAPSHOTS
+                .save(
+                    deps.as_mut().storage,
+                    time.seconds(),
+                    &ExchangeRateSnapshot {
+                        exchange_rate: er,
+                        total_staked: Default::default(),
+                        total_token_supply: Default::default(),
+                        gross_exchange_rate: gross_er,
+                        time,
+                    },
+                )
+                .unwrap();
+        }
 
         /*
-           Test - 2. Batch not reconciled
+           Test - 2. Half year window. 10% growth in half a year
         */
-        BATCH_UNDELEGATION_REGISTRY
-            .save(
-                deps.as_mut().storage,
-                1,
-                &BatchUndelegationRecord {
-                    undelegated_tokens: Uint128::new(10000_u128),
-                    create_time: Default::default(),
-                    est_release_time: None,
-                    reconciled: false,
-                    undelegation_er: Default::default(),
-                    undelegated_stake: Default::default(),
-                    unbonding_slashing_ratio: Default::default(),
+        let res: AprResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Apr {
+                    window_seconds: year / 2,
                 },
             )
-            .unwrap();
-        let err = compute_withdrawable_funds(deps.as_mut().storage, 1, &user1).unwrap_err();
-        assert!(matches!(
-            err,
-            ContractError::UndelegationBatchNotReconciled {}
-        ));
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.apr, Decimal::from_ratio(20_u128, 100_u128));
+        assert_eq!(res.gross_apr, Decimal::from_ratio(22_u128, 100_u128));
+        assert_eq!(res.start_time, Some(env.block.time.minus_seconds(year / 2)));
+        assert_eq!(res.end_time, Some(env.block.time));
 
         /*
-            Test - 3. User undelegation record not found
+           Test - 3. Window longer than the history falls back to the oldest checkpoint
         */
-        BATCH_UNDELEGATION_REGISTRY
-            .save(
-                deps.as_mut().storage,
-                1,
-                &BatchUndelegationRecord {
-                    undelegated_tokens: Uint128::new(10000_u128),
-                    create_time: Default::default(),
-                    est_release_time: None,
-                    reconciled: true,
-                    undelegation_er: Default::default(),
-                    undelegated_stake: Default::default(),
-                    unbonding_slashing_ratio: Default::default(),
+        let res: AprResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Apr {
+                    window_seconds: 10 * year,
                 },
             )
-            .unwrap();
-        let err = compute_withdrawable_funds(deps.as_mut().storage, 1, &user1).unwrap_err();
-        assert!(matches!(err, ContractError::UndelegationEntryNotFound {}));
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.apr, Decimal::from_ratio(155_u128, 1000_u128));
+        assert_eq!(res.start_time, Some(env.block.time.minus_seconds(year)));
     }
 
     #[test]
-    fn test_compute_withdrawable_funds_success() {
+    fn test_check_invariants() {
         let mut deps = mock_dependencies(&[]);
         let info = mock_info("creator", &[]);
         let env = mock_env();
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
-        let user1 = Addr::unchecked("user1");
-
-        /*
-           Test - 1. Success
-        */
-        BATCH_UNDELEGATION_REGISTRY
-            .save(
+        let valid1 = Addr::unchecked("valid0001");
+        let valid2 = Addr::unchecked("valid0002");
+        let valid3 = Addr::unchecked("valid0003");
+        deps.querier
+            .update_staking("uluna", &get_validators(), &get_delegations());
+        STATE
+            .update(
                 deps.as_mut().storage,
-                1,
-                &BatchUndelegationRecord {
-                    undelegated_tokens: Uint128::new(10000_u128),
-                    create_time: Default::default(),
-                    est_release_time: None,
-                    reconciled: true,
-                    undelegation_er: Decimal::one(),
-                    undelegated_stake: Uint128::new(10000_u128),
-                    unbonding_slashing_ratio: Decimal::from_ratio(3_u128, 4_u128),
+                |mut state| -> Result<_, ContractError> {
+                    state.validators = vec![valid1.clone(), valid2.clone(), valid3.clone()];
+                    state.total_staked = Uint128::new(3000_u128);
+                    Ok(state)
                 },
             )
             .unwrap();
+        for val_addr in [&valid1, &valid2, &valid3] {
+            VALIDATOR_META
+                .save(
+                    deps.as_mut().storage,
+                    val_addr,
+                    &VMeta {
+                        staked: Uint128::new(1000_u128),
+                        slashed: Default::default(),
+                        filled: Default::default(),
+                    },
+                )
+                .unwrap();
+        }
+        BATCH_UNDELEGATION_REGISTRY
+            .update(deps.as_mut().storage, 1, |x| -> StdResult<_> {
+                let mut batch = x.unwrap();
+                batch.undelegated_tokens = Uint128::new(100_u128);
+                Ok(batch)
+            })
+            .unwrap();
         USERS
             .save(
                 deps.as_mut().storage,
-                (&user1, 1),
+                (&Addr::unchecked("user1"), 1),
                 &UndelegationInfo {
                     batch_id: 1,
-                    token_amount: Uint128::new(1000_u128),
+                    token_amount: Uint128::new(100_u128),
                 },
             )
             .unwrap();
-        let res = compute_withdrawable_funds(deps.as_mut().storage, 1, &user1).unwrap();
-        assert_eq!(
-            res,
-            GetFundsClaimRecord {
-                user_withdrawal_amount: Uint128::new(743_u128),
-                protocol_fee: Uint128::new(7_u128),
-                undelegated_tokens: Uint128::new(1000_u128)
-            }
-        );
-    }
-
-    #[test]
-    fn test_withdraw_funds_to_wallet() {
-        let mut deps = mock_dependencies(&[]);
-        let info = mock_info("creator", &[]);
-        let env = mock_env();
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
-
-        let user1 = Addr::unchecked("user1");
+        /*
+           Test - 1. Consistent accounting
+        */
+        let res: CheckInvariantsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::CheckInvariants {
+                    start_after: None,
+                    limit: None,
+                    batch_user_tokens: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(res.violations.is_empty());
 
         /*
-           withdraw paused
+           Test - 2. Every discrepancy is reported with its magnitude
         */
-        OPERATION_CONTROLS
+        VALIDATOR_META
             .save(
                 deps.as_mut().storage,
-                &OperationControls {
-                    deposit_paused: false,
-                    queue_undelegate_paused: false,
-                    undelegate_paused: false,
-                    withdraw_paused: true,
-                    reinvest_paused: false,
-                    reconcile_paused: false,
-                    claim_airdrops_paused: false,
-                    redeem_rewards_paused: false,
+                &valid1,
+                &VMeta {
+                    staked: Uint128::new(1200_u128),
+                    slashed: Default::default(),
+                    filled: Default::default(),
                 },
             )
             .unwrap();
-        let err = execute(
-            deps.as_mut(),
-            env.clone(),
-            mock_info("other", &[]),
-            ExecuteMsg::WithdrawFundsToWallet { batch_id: 0 },
-        )
-        .unwrap_err();
-        assert!(matches!(err, ContractError::OperationPaused(String { .. })));
-
-        OPERATION_CONTROLS
-            .save(
-                deps.as_mut().storage,
-                &OperationControls {
-                    deposit_paused: false,
-                    queue_undelegate_paused: false,
-                    undelegate_paused: false,
-                    withdraw_paused: false,
-                    reinvest_paused: false,
-                    reconcile_paused: false,
-                    claim_airdrops_paused: false,
-                    redeem_rewards_paused: false,
-                },
-            )
-            .unwrap();
-
         STATE
-            .update(deps.as_mut().storage, |mut state| -> StdResult<_> {
-                state.reconciled_funds_to_withdraw = Uint128::new(1200);
-                Ok(state)
-            })
-            .unwrap();
-        BATCH_UNDELEGATION_REGISTRY
-            .save(
+            .update(
                 deps.as_mut().storage,
-                1,
-                &BatchUndelegationRecord {
-                    undelegated_tokens: Uint128::new(10000_u128),
-                    create_time: Default::default(),
-                    est_release_time: None,
-                    reconciled: true,
-                    undelegation_er: Decimal::one(),
-                    undelegated_stake: Uint128::new(10000_u128),
-                    unbonding_slashing_ratio: Decimal::from_ratio(3_u128, 4_u128),
+                |mut state| -> Result<_, ContractError> {
+                    state.reconciled_funds_to_withdraw = Uint128::new(50_u128);
+                    Ok(state)
                 },
             )
             .unwrap();
         USERS
             .save(
                 deps.as_mut().storage,
-                (&user1, 1),
+                (&Addr::unchecked("user2"), 1),
                 &UndelegationInfo {
                     batch_id: 1,
-                    token_amount: Uint128::new(1000_u128),
+                    token_amount: Uint128::new(20_u128),
                 },
             )
             .unwrap();
-        let res = execute(
-            deps.as_mut(),
-            env.clone(),
-            mock_info("user1", &[]),
-            ExecuteMsg::WithdrawFundsToWallet { batch_id: 1 },
+        let res: CheckInvariantsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::CheckInvariants {
+                    start_after: None,
+                    limit: None,
+                    batch_user_tokens: None,
+                },
+            )
+            .unwrap(),
         )
         .unwrap();
-        assert_eq!(res.messages.len(), 2);
-        assert_eq!(
-            res.messages[0],
-            SubMsg::new(BankMsg::Send {
-                to_address: user1.to_string(),
-                amount: vec![Coin::new(743_u128, "uluna".to_string())]
-            })
-        );
         assert_eq!(
-            res.messages[1],
-            SubMsg::new(BankMsg::Send {
-                to_address: "protocol_fee_contract".to_string(),
-                amount: vec![Coin::new(7_u128, "uluna".to_string())]
-            })
+            res.violations,
+            vec![
+                InvariantViolation {
+                    invariant: "validator_meta_staked:valid0001".to_string(),
+                    expected: Uint128::new(1000_u128),
+                    actual: Uint128::new(1200_u128),
+                    difference: Uint128::new(200_u128)
+                },
+                InvariantViolation {
+                    invariant: "total_staked_vs_validator_meta".to_string(),
+                    expected: Uint128::new(3200_u128),
+                    actual: Uint128::new(3000_u128),
+                    difference: Uint128::new(200_u128)
+                },
+                InvariantViolation {
+                    invariant: "reconciled_funds_vs_balance".to_string(),
+                    expected: Uint128::new(50_u128),
+                    actual: Uint128::zero(),
+                    difference: Uint128::new(50_u128)
+                },
+                InvariantViolation {
+                    invariant: "batch_undelegated_tokens:1".to_string(),
+                    expected: Uint128::new(100_u128),
+                    actual: Uint128::new(120_u128),
+                    difference: Uint128::new(20_u128)
+                },
+            ]
         );
-        let state = STATE.load(deps.as_mut().storage).unwrap();
-        assert_eq!(state.reconciled_funds_to_withdraw, Uint128::new(450));
-        let user_undel_info = USERS.may_load(deps.as_mut().storage, (&user1, 1)).unwrap();
-        assert_eq!(user_undel_info, None);
-    }
-
-    #[test]
-    fn test_undelegate_stake_fail() {
-        let mut deps = mock_dependencies(&[]);
-        let info = mock_info("creator", &[]);
-        let env = mock_env();
+        assert!(res.next_start_after.is_none());
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
-
-        let valid1 = Addr::unchecked("valid0001");
-        let valid2 = Addr::unchecked("valid0002");
-        let valid3 = Addr::unchecked("valid0003");
+        /*
+           Test - 3. Paginated scan carries the batch sums and still counts delegations to
+           validators removed from the pool
+        */
         STATE
             .update(
                 deps.as_mut().storage,
                 |mut state| -> Result<_, ContractError> {
-                    state.validators = vec![valid1.clone(), valid2.clone(), valid3.clone()];
+                    state.validators = vec![valid1.clone(), valid2.clone()];
                     Ok(state)
                 },
             )
             .unwrap();
-        VALIDATOR_META
-            .save(
-                deps.as_mut().storage,
-                &valid1,
-                &VMeta {
-                    staked: Uint128::new(1000_u128),
-                    slashed: Default::default(),
-                    filled: Default::default(),
+        let res: CheckInvariantsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::CheckInvariants {
+                    start_after: None,
+                    limit: Some(1),
+                    batch_user_tokens: None,
                 },
             )
-            .unwrap();
-        VALIDATOR_META
-            .save(
-                deps.as_mut().storage,
-                &valid2,
-                &VMeta {
-                    staked: Uint128::new(1000_u128),
-                    slashed: Default::default(),
-                    filled: Default::default(),
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.violations
+                .iter()
+                .map(|x| x.invariant.as_str())
+                .collect::<Vec<&str>>(),
+            vec![
+                "validator_meta_staked:valid0001",
+                "total_staked_vs_validator_meta",
+                "reconciled_funds_vs_balance"
+            ]
+        );
+        assert_eq!(
+            res.batch_user_tokens,
+            vec![BatchUserTokens {
+                batch_id: 1,
+                token_amount: Uint128::new(100_u128)
+            }]
+        );
+        assert_eq!(res.next_start_after, Some(("user1".to_string(), 1)));
+
+        let res: CheckInvariantsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::CheckInvariants {
+                    start_after: res.next_start_after,
+                    limit: Some(1),
+                    batch_user_tokens: Some(res.batch_user_tokens),
                 },
             )
-            .unwrap();
-        VALIDATOR_META
-            .save(
-                deps.as_mut().storage,
-                &valid3,
-                &VMeta {
-                    staked: Uint128::new(1000_u128),
-                    slashed: Default::default(),
-                    filled: Default::default(),
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(res.violations.is_empty());
+        assert_eq!(res.next_start_after, Some(("user2".to_string(), 1)));
+
+        let res: CheckInvariantsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::CheckInvariants {
+                    start_after: res.next_start_after,
+                    limit: Some(1),
+                    batch_user_tokens: Some(res.batch_user_tokens),
                 },
             )
-            .unwrap();
-        deps.querier
-            .update_staking("uluna", &*get_validators(), &*get_delegations());
-        deps.querier
-            .update_stader_balances(Some(Uint128::new(3000_u128)), None);
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.violations,
+            vec![InvariantViolation {
+                invariant: "batch_undelegated_tokens:1".to_string(),
+                expected: Uint128::new(100_u128),
+                actual: Uint128::new(120_u128),
+                difference: Uint128::new(20_u128)
+            }]
+        );
+        assert!(res.next_start_after.is_none());
+    }
+
+    #[test]
+    fn test_circuit_breaker() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+
+        instantiate_contract(&mut deps, &info, &env);
 
         /*
-           Paused
+           Test - 1. Update circuit breaker config
         */
-        OPERATION_CONTROLS
-            .save(
-                deps.as_mut().storage,
-                &OperationControls {
-                    deposit_paused: false,
-                    queue_undelegate_paused: false,
-                    undelegate_paused: true,
-                    withdraw_paused: false,
-                    reinvest_paused: false,
-                    reconcile_paused: false,
-                    claim_airdrops_paused: false,
-                    redeem_rewards_paused: false,
-                },
-            )
-            .unwrap();
+        let circuit_breaker_config = CircuitBreakerConfig {
+            max_exchange_rate_drop: Some(Decimal::from_ratio(10_u128, 100_u128)),
+            max_exchange_rate_rise: Some(Decimal::from_ratio(10_u128, 100_u128)),
+        };
         let err = execute(
             deps.as_mut(),
             env.clone(),
-            mock_info("other", &[]),
-            ExecuteMsg::Undelegate {},
+            mock_info("not-creator", &[]),
+            ExecuteMsg::UpdateCircuitBreaker {
+                circuit_breaker_config: circuit_breaker_config.clone(),
+            },
         )
         .unwrap_err();
-        assert!(matches!(err, ContractError::OperationPaused(String { .. })));
+        assert!(matches!(err, ContractError::Unauthorized {}));
 
-        OPERATION_CONTROLS
-            .save(
-                deps.as_mut().storage,
-                &OperationControls {
-                    deposit_paused: false,
-                    queue_undelegate_paused: false,
-                    undelegate_paused: false,
-                    withdraw_paused: false,
-                    reinvest_paused: false,
-                    reconcile_paused: false,
-                    claim_airdrops_paused: false,
-                    redeem_rewards_paused: false,
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateCircuitBreaker {
+                circuit_breaker_config: CircuitBreakerConfig {
+                    max_exchange_rate_drop: Some(Decimal::one()),
+                    max_exchange_rate_rise: None,
                 },
-            )
-            .unwrap();
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InvalidCircuitBreakerThreshold {}
+        ));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateCircuitBreaker {
+                circuit_breaker_config: circuit_breaker_config.clone(),
+            },
+        )
+        .unwrap();
 
         /*
-           Test - 1. Undelegation in cooldown
+           Test - 2. Exchange rate drop on deposit trips the breaker and refunds the deposit
         */
-        CONFIG
-            .update(
-                deps.as_mut().storage,
-                |mut config| -> Result<_, ContractError> {
-                    config.undelegation_cooldown = 1000;
-                    Ok(config)
-                },
-            )
-            .unwrap();
+        let valid1 = Addr::unchecked("valid0001");
+        let valid2 = Addr::unchecked("valid0002");
+        let valid3 = Addr::unchecked("valid0003");
+        let mut delegations = get_delegations();
+        delegations[0].amount = Coin::new(500, "uluna");
+        deps.querier
+            .update_staking("uluna", &get_validators(), &delegations);
+        deps.querier
+            .update_stader_balances(Some(Uint128::new(3000_u128)), None);
         STATE
             .update(
                 deps.as_mut().storage,
                 |mut state| -> Result<_, ContractError> {
-                    state.last_undelegation_time = env.block.time.minus_seconds(100);
+                    state.validators = vec![valid1.clone(), valid2.clone(), valid3.clone()];
+                    state.total_staked = Uint128::new(3000_u128);
                     Ok(state)
                 },
             )
             .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("user1", &[Coin::new(1000_u128, "uluna")]),
+            ExecuteMsg::Deposit {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "user1".to_string(),
+                amount: vec![Coin::new(1000_u128, "uluna")]
+            })]
+        );
+        assert_eq!(res.events.len(), 1);
+        assert_eq!(res.events[0].ty, "lunax-circuit-breaker-tripped");
+        let operation_controls = OPERATION_CONTROLS.load(deps.as_mut().storage).unwrap();
+        assert!(operation_controls.deposit_paused);
+        assert!(operation_controls.queue_undelegate_paused);
+        assert!(operation_controls.withdraw_paused);
+        assert!(!operation_controls.reinvest_paused);
+        let circuit_breaker: CircuitBreakerResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::CircuitBreaker {}).unwrap())
+                .unwrap();
+        assert_eq!(circuit_breaker.config, circuit_breaker_config);
+        assert_eq!(
+            circuit_breaker.trip,
+            Some(CircuitBreakerTrip {
+                operation: "check_slashing".to_string(),
+                previous_exchange_rate: Decimal::one(),
+                new_exchange_rate: Decimal::from_ratio(2500_u128, 3000_u128),
+                time: env.block.time,
+                paused_operations: vec![
+                    "deposit".to_string(),
+                    "queue_undelegate".to_string(),
+                    "withdraw".to_string()
+                ]
+            })
+        );
+
         let err = execute(
             deps.as_mut(),
             env.clone(),
-            mock_info("other", &[]),
-            ExecuteMsg::Undelegate {},
+            mock_info("user1", &[Coin::new(1000_u128, "uluna")]),
+            ExecuteMsg::Deposit {},
         )
         .unwrap_err();
-        assert!(matches!(err, ContractError::UndelegationInCooldown {}));
+        assert!(matches!(err, ContractError::OperationPaused(String { .. })));
 
-        let valid1 = Addr::unchecked("valid0001");
-        let valid2 = Addr::unchecked("valid0002");
-        let valid3 = Addr::unchecked("valid0003");
         /*
-            Test - 2. No-Op
+           Test - 3. Only the manager can reset the breaker
         */
-        CONFIG
-            .update(
-                deps.as_mut().storage,
-                |mut config| -> Result<_, ContractError> {
-                    config.undelegation_cooldown = 1000;
-                    Ok(config)
-                },
-            )
-            .unwrap();
-        STATE
-            .update(
-                deps.as_mut().storage,
-                |mut state| -> Result<_, ContractError> {
-                    state.last_undelegation_time = env.block.time.minus_seconds(2000);
-                    Ok(state)
-                },
-            )
-            .unwrap();
-        STATE
-            .update(
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("not-creator", &[]),
+            ExecuteMsg::ResetCircuitBreaker {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::ResetCircuitBreaker {},
+        )
+        .unwrap();
+        let operation_controls = OPERATION_CONTROLS.load(deps.as_mut().storage).unwrap();
+        assert!(!operation_controls.deposit_paused);
+        assert!(!operation_controls.queue_undelegate_paused);
+        assert!(!operation_controls.withdraw_paused);
+        assert!(CIRCUIT_BREAKER_TRIP
+            .may_load(deps.as_mut().storage)
+            .unwrap()
+            .is_none());
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::ResetCircuitBreaker {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::CircuitBreakerNotTripped {}));
+
+        /*
+           Test - 4. Exchange rate jump on reinvest trips the breaker and leaves rewards untouched
+        */
+        let config = CONFIG.load(deps.as_mut().storage).unwrap();
+        deps.querier.update_balance(
+            config.reward_contract,
+            vec![Coin::new(1000_u128, "uluna".to_string())],
+        );
+        INFLOW_TRACKER
+            .save(
                 deps.as_mut().storage,
-                |mut state| -> Result<_, ContractError> {
-                    state.validators = vec![valid1.clone(), valid2.clone(), valid3.clone()];
-                    state.current_undelegation_batch_id = 1;
-                    Ok(state)
+                &InflowTracker {
+                    expected_rewards: Uint128::new(1000_u128),
+                    ..Default::default()
                 },
             )
             .unwrap();
         deps.querier
-            .update_staking("uluna", &*get_validators(), &*get_delegations());
+            .update_reward_vault_balance(Uint128::new(1000_u128));
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::Reinvest {},
+        )
+        .unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(res.events[0].ty, "lunax-circuit-breaker-tripped");
+        let state = STATE.load(deps.as_mut().storage).unwrap();
+        assert_eq!(state.total_staked, Uint128::new(2500_u128));
+        assert_eq!(
+            state.exchange_rate,
+            Decimal::from_ratio(2500_u128, 3000_u128)
+        );
+        let trip = CIRCUIT_BREAKER_TRIP.load(deps.as_mut().storage).unwrap();
+        assert_eq!(trip.operation, "reinvest".to_string());
+        assert!(
+            OPERATION_CONTROLS
+                .load(deps.as_mut().storage)
+                .unwrap()
+                .deposit_paused
+        );
+
+        /*
+           Test - 5. Pausers cannot reset the breaker
+        */
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::GrantRole {
+                role: Role::Pauser,
+                address: "pauser".to_string(),
+            },
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("pauser", &[]),
+            ExecuteMsg::ResetCircuitBreaker {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::ResetCircuitBreaker {},
+        )
+        .unwrap();
+
+        /*
+           Test - 6. A trip is kept when the call cannot go ahead after the slashing check
+        */
+        delegations[0].amount = Coin::new(100, "uluna");
         deps.querier
-            .update_stader_balances(Some(Uint128::new(3000_u128)), None);
-        VALIDATOR_META
-            .save(
-                deps.as_mut().storage,
-                &valid2,
-                &VMeta {
-                    staked: Uint128::new(1000_u128),
-                    slashed: Default::default(),
-                    filled: Default::default(),
-                },
-            )
-            .unwrap();
-        VALIDATOR_META
-            .save(
-                deps.as_mut().storage,
-                &valid3,
-                &VMeta {
-                    staked: Uint128::new(1000_u128),
-                    slashed: Default::default(),
-                    filled: Default::default(),
-                },
-            )
-            .unwrap();
-        VALIDATOR_META
-            .save(
-                deps.as_mut().storage,
-                &valid1,
-                &VMeta {
-                    staked: Uint128::new(1000_u128),
-                    slashed: Default::default(),
-                    filled: Default::default(),
-                },
-            )
-            .unwrap();
+            .update_staking("uluna", &get_validators()[1..], &delegations);
         BATCH_UNDELEGATION_REGISTRY
-            .save(
-                deps.as_mut().storage,
-                1,
-                &BatchUndelegationRecord {
-                    undelegated_tokens: Uint128::zero(),
-                    create_time: Default::default(),
-                    est_release_time: None,
-                    reconciled: false,
-                    undelegation_er: Default::default(),
-                    undelegated_stake: Default::default(),
-                    unbonding_slashing_ratio: Default::default(),
-                },
-            )
+            .update(deps.as_mut().storage, 1, |x| -> StdResult<_> {
+                let mut batch = x.unwrap();
+                batch.undelegated_tokens = Uint128::new(3000_u128);
+                Ok(batch)
+            })
             .unwrap();
-        let err = execute(
+        let res = execute(
             deps.as_mut(),
             env.clone(),
-            mock_info("other", &[]),
+            mock_info("creator", &[]),
             ExecuteMsg::Undelegate {},
         )
-        .unwrap_err();
-        assert!(matches!(err, ContractError::NoOp {}));
+        .unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(res.events[0].ty, "lunax-circuit-breaker-tripped");
+        let trip = CIRCUIT_BREAKER_TRIP.load(deps.as_mut().storage).unwrap();
+        assert_eq!(trip.operation, "check_slashing".to_string());
+        assert_eq!(
+            trip.new_exchange_rate,
+            Decimal::from_ratio(2100_u128, 3000_u128)
+        );
+        let batch = BATCH_UNDELEGATION_REGISTRY
+            .load(deps.as_mut().storage, 1)
+            .unwrap();
+        assert!(batch.est_release_time.is_none());
+        assert_eq!(
+            STATE
+                .load(deps.as_mut().storage)
+                .unwrap()
+                .current_undelegation_batch_id,
+            1
+        );
 
         /*
-            Test - 3. Validators do not have sufficient funds
+           Test - 7. A trip takes over guardian pauses which would expire before the reset
         */
-        CONFIG
-            .update(
-                deps.as_mut().storage,
-                |mut config| -> Result<_, ContractError> {
-                    config.undelegation_cooldown = 1000;
-                    Ok(config)
-                },
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::ResetCircuitBreaker {},
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::GrantRole {
+                role: Role::Guardian,
+                address: "guardian".to_string(),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("guardian", &[]),
+            ExecuteMsg::PauseOperations {
+                operations: vec!["deposit".to_string()],
+                reason: None,
+                expires_at: Some(env.block.time.seconds() + 100),
+            },
+        )
+        .unwrap();
+        delegations[1].amount = Coin::new(500, "uluna");
+        deps.querier
+            .update_staking("uluna", &get_validators(), &delegations);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::RedeemRewards { validators: None },
+        )
+        .unwrap();
+        assert_eq!(res.events[0].ty, "lunax-circuit-breaker-tripped");
+        let trip = CIRCUIT_BREAKER_TRIP.load(deps.as_mut().storage).unwrap();
+        assert_eq!(
+            trip.paused_operations,
+            vec![
+                "deposit".to_string(),
+                "queue_undelegate".to_string(),
+                "withdraw".to_string()
+            ]
+        );
+        assert!(ACTIVE_PAUSES
+            .may_load(deps.as_mut().storage, "deposit")
+            .unwrap()
+            .is_none());
+
+        let mut later_env = env.clone();
+        later_env.block.time = later_env.block.time.plus_seconds(200);
+        let operation_controls: OperationControls = from_binary(
+            &query(
+                deps.as_ref(),
+                later_env.clone(),
+                QueryMsg::OperationControls {},
             )
-            .unwrap();
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(operation_controls.deposit_paused);
+        execute(
+            deps.as_mut(),
+            later_env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::ResetCircuitBreaker {},
+        )
+        .unwrap();
+        assert!(
+            !OPERATION_CONTROLS
+                .load(deps.as_mut().storage)
+                .unwrap()
+                .deposit_paused
+        );
+    }
+
+    #[test]
+    fn test_donate_and_quarantine() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+
+        instantiate_contract(&mut deps, &info, &env);
+
+        let valid1 = Addr::unchecked("valid0001");
+        let valid2 = Addr::unchecked("valid0002");
+        let valid3 = Addr::unchecked("valid0003");
+        let config = CONFIG.load(deps.as_mut().storage).unwrap();
+        deps.querier
+            .update_stader_balances(Some(Uint128::new(3000_u128)), None);
+        deps.querier
+            .update_staking("uluna", &get_validators(), &get_delegations());
         STATE
             .update(
                 deps.as_mut().storage,
                 |mut state| -> Result<_, ContractError> {
-                    state.last_undelegation_time = env.block.time.minus_seconds(2000);
+                    state.validators = vec![valid1.clone(), valid2.clone(), valid3.clone()];
+                    state.total_staked = Uint128::new(3000_u128);
                     Ok(state)
                 },
             )
             .unwrap();
+        for val_addr in [&valid1, &valid2, &valid3] {
+            VALIDATOR_META
+                .save(
+                    deps.as_mut().storage,
+                    val_addr,
+                    &VMeta {
+                        staked: Uint128::new(1000_u128),
+                        slashed: Default::default(),
+                        filled: Default::default(),
+                    },
+                )
+                .unwrap();
+        }
+
+        /*
+           Test - 1. Donation raises the exchange rate without minting
+        */
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("donor", &[]),
+            ExecuteMsg::Donate {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoFunds {}));
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("donor", &[Coin::new(300_u128, "uluna".to_string())]),
+            ExecuteMsg::Donate {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            wrap_rewards_withdrawal(
+                &config.reward_contract,
+                vec![StakingMsg::Delegate {
+                    validator: valid1.to_string(),
+                    amount: Coin::new(300_u128, "uluna".to_string())
+                }
+                .into()]
+            )
+            .unwrap()
+        );
+        let state = STATE.load(deps.as_mut().storage).unwrap();
+        assert_eq!(state.total_staked, Uint128::new(3300_u128));
+        assert_eq!(
+            state.exchange_rate,
+            Decimal::from_ratio(3300_u128, 3000_u128)
+        );
+        let inflow_tracker = INFLOW_TRACKER.load(deps.as_mut().storage).unwrap();
+        assert_eq!(inflow_tracker.total_donations, Uint128::new(300_u128));
+        // Delegating withdraws the rewards pending on valid0001.
+        assert_eq!(inflow_tracker.expected_rewards, Uint128::new(20_u128));
+
+        /*
+           Test - 2. Stray funds in the reward contract are not reinvested
+        */
+        deps.querier.update_balance(
+            config.reward_contract.clone(),
+            vec![Coin::new(1000_u128, "uluna".to_string())],
+        );
+        // The reward contract recorded the 20 withdrawn by the donation.
+        deps.querier
+            .update_reward_vault_balance(Uint128::new(20_u128));
+        let res: InflowTrackerResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::InflowTracker {}).unwrap())
+                .unwrap();
+        assert_eq!(res.unaccounted_reward_funds, Uint128::new(980_u128));
+
+        /*
+           Test - 3. Stray funds in the staking contract are quarantined, not credited to batches
+        */
+        deps.querier.update_balance(
+            env.contract.address.clone(),
+            vec![Coin::new(500_u128, "uluna".to_string())],
+        );
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::ReconcileFunds {},
+        )
+        .unwrap();
+        let inflow_tracker = INFLOW_TRACKER.load(deps.as_mut().storage).unwrap();
+        assert_eq!(inflow_tracker.quarantined_funds, Uint128::new(500_u128));
+        let state = STATE.load(deps.as_mut().storage).unwrap();
+        assert_eq!(state.reconciled_funds_to_withdraw, Uint128::zero());
+
+        /*
+           Test - 4. Manager sweeps the quarantined funds, the reward contract surplus is left to the
+           reward contract manager
+        */
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::SweepQuarantinedFunds {
+                recipient: "treasury".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::SweepQuarantinedFunds {
+                recipient: "treasury".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "treasury".to_string(),
+                amount: vec![Coin::new(500_u128, "uluna".to_string())]
+            })]
+        );
+        let inflow_tracker = INFLOW_TRACKER.load(deps.as_mut().storage).unwrap();
+        assert_eq!(inflow_tracker.quarantined_funds, Uint128::zero());
+    }
+
+    #[test]
+    fn test_fee_split() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+
+        instantiate_contract(&mut deps, &info, &env);
+
+        let valid1 = Addr::unchecked("valid0001");
+        let fee_split = vec![
+            FeeSplitEntry {
+                recipient: Addr::unchecked("treasury"),
+                weight: Decimal::from_ratio(7_u128, 10_u128),
+            },
+            FeeSplitEntry {
+                recipient: Addr::unchecked("insurance_fund"),
+                weight: Decimal::from_ratio(3_u128, 10_u128),
+            },
+        ];
+
+        /*
+           Test - 1. Only the manager can update the fee split and weights should sum to 1
+        */
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::UpdateFeeSplit {
+                fee_split: fee_split.clone(),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateFeeSplit {
+                fee_split: vec![fee_split[0].clone()],
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidFeeSplit {}));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateFeeSplit {
+                fee_split: fee_split.clone(),
+            },
+        )
+        .unwrap();
+        let res: FeeSplitResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::FeeSplit {}).unwrap())
+                .unwrap();
+        assert_eq!(res.fee_split, fee_split);
+
+        /*
+           Test - 2. Deposit fee is sent to every recipient in the table
+        */
+        STATE
+            .update(
+                deps.as_mut().storage,
+                |mut state| -> Result<_, ContractError> {
+                    state.validators = vec![valid1.clone()];
+                    state.total_staked = Uint128::new(1000_u128);
+                    Ok(state)
+                },
+            )
+            .unwrap();
+        VALIDATOR_META
+            .save(
+                deps.as_mut().storage,
+                &valid1,
+                &VMeta {
+                    staked: Uint128::new(1000_u128),
+                    slashed: Default::default(),
+                    filled: Default::default(),
+                },
+            )
+            .unwrap();
+        deps.querier
+            .update_staking("uluna", &get_validators(), &get_delegations());
+        deps.querier
+            .update_stader_balances(Some(Uint128::new(1000_u128)), None);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[Coin::new(1500_u128, "uluna".to_string())]),
+            ExecuteMsg::Deposit {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[..2],
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: "treasury".to_string(),
+                    amount: vec![Coin::new(11_u128, "uluna")]
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "insurance_fund".to_string(),
+                    amount: vec![Coin::new(4_u128, "uluna")]
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_claim_airdrops_fail() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+
+        instantiate_contract(&mut deps, &info, &env);
+
+        /*
+           Test - 1. Airdrop not registered
+        */
+        CONFIG
+            .update(
+                deps.as_mut().storage,
+                |mut config| -> Result<_, ContractError> {
+                    config.airdrop_withdrawal_contract =
+                        Addr::unchecked("airdrop_withdrawal_contract");
+                    config.airdrop_registry_contract = Addr::unchecked("airdrop_registry_contract");
+                    Ok(config)
+                },
+            )
+            .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::ClaimAirdrops {
+                rates: vec![
+                    AirdropRate {
+                        denom: "unreg_token".to_string(), // special name to test for an unregistered token
+                        amount: Uint128::new(1000_u128),
+                        stage: 0,
+                        proof: vec![
+                            "unreg_token_proof1".to_string(),
+                            "unreg_token_proof2".to_string(),
+                        ],
+                    },
+                    AirdropRate {
+                        denom: "anc".to_string(),
+                        amount: Uint128::new(1000_u128),
+                        stage: 0,
+                        proof: vec!["anc_proof1".to_string(), "anc_proof2".to_string()],
+                    },
+                ],
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::AirdropNotRegistered(String { .. })
+        ));
+    }
+
+    #[test]
+    fn test_claim_airdrops_success() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+
+        instantiate_contract(&mut deps, &info, &env);
+
+        /*
+           Test - 1. Success
+        */
+        CONFIG
+            .update(
+                deps.as_mut().storage,
+                |mut config| -> Result<_, ContractError> {
+                    config.airdrop_withdrawal_contract =
+                        Addr::unchecked("airdrop_withdrawal_contract");
+                    config.airdrop_registry_contract = Addr::unchecked("airdrop_registry_contract");
+                    Ok(config)
+                },
+            )
+            .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::ClaimAirdrops {
+                rates: vec![
+                    AirdropRate {
+                        denom: "anc".to_string(),
+                        amount: Uint128::new(1000_u128),
+                        stage: 0,
+                        proof: vec!["anc_proof1".to_string(), "anc_proof2".to_string()],
+                    },
+                    AirdropRate {
+                        denom: "mir".to_string(),
+                        amount: Uint128::new(2000_u128),
+                        stage: 0,
+                        proof: vec!["mir_proof1".to_string(), "mir_proof2".to_string()],
+                    },
+                ],
+            },
+        )
+        .unwrap();
+        let config = CONFIG.load(deps.as_mut().storage).unwrap();
+        assert_eq!(res.messages.len(), 4);
+        assert!(check_equal_vec(
+            res.messages,
+            vec![
+                SubMsg::new(WasmMsg::Execute {
+                    contract_addr: "anc_airdrop_contract".to_string(),
+                    msg: to_binary(&MerkleAirdropMsg::Claim {
+                        stage: 0,
+                        amount: Uint128::new(1000_u128),
+                        proof: vec!["anc_proof1".to_string(), "anc_proof2".to_string()],
+                    })
+                    .unwrap(),
+                    funds: vec![]
+                }),
+                SubMsg::new(WasmMsg::Execute {
+                    contract_addr: "mir_airdrop_contract".to_string(),
+                    msg: to_binary(&MerkleAirdropMsg::Claim {
+                        stage: 0,
+                        amount: Uint128::new(2000_u128),
+                        proof: vec!["mir_proof1".to_string(), "mir_proof2".to_string()],
+                    })
+                    .unwrap(),
+                    funds: vec![]
+                }),
+                SubMsg::new(WasmMsg::Execute {
+                    contract_addr: "anc_cw20_contract".to_string(),
+                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                        recipient: config.airdrop_withdrawal_contract.to_string(),
+                        amount: Uint128::new(1000_u128)
+                    })
+                    .unwrap(),
+                    funds: vec![]
+                }),
+                SubMsg::new(WasmMsg::Execute {
+                    contract_addr: "mir_cw20_contract".to_string(),
+                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                        recipient: config.airdrop_withdrawal_contract.to_string(),
+                        amount: Uint128::new(2000_u128)
+                    })
+                    .unwrap(),
+                    funds: vec![]
+                })
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_compute_withdrawable_funds_fail() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+
+        instantiate_contract(&mut deps, &info, &env);
+
+        let user1 = Addr::unchecked("user1");
+
+        /*
+           Test - 1. Undelegation batch not found
+        */
+        let err = compute_withdrawable_funds(deps.as_mut().storage, 0, &user1).unwrap_err();
+        assert!(matches!(err, ContractError::UndelegationBatchNotFound {}));
+
+        /*
+           Test - 2. Batch not reconciled
+        */
+        BATCH_UNDELEGATION_REGISTRY
+            .save(
+                deps.as_mut().storage,
+                1,
+                &BatchUndelegationRecord {
+                    undelegated_tokens: Uint128::new(10000_u128),
+                    create_time: Default::default(),
+                    est_release_time: None,
+                    reconciled: false,
+                    undelegation_er: Default::default(),
+                    undelegated_stake: Default::default(),
+                    unbonding_slashing_ratio: Default::default(),
+                },
+            )
+            .unwrap();
+        let err = compute_withdrawable_funds(deps.as_mut().storage, 1, &user1).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::UndelegationBatchNotReconciled {}
+        ));
+
+        /*
+            Test - 3. User undelegation record not found
+        */
+        BATCH_UNDELEGATION_REGISTRY
+            .save(
+                deps.as_mut().storage,
+                1,
+                &BatchUndelegationRecord {
+                    undelegated_tokens: Uint128::new(10000_u128),
+                    create_time: Default::default(),
+                    est_release_time: None,
+                    reconciled: true,
+                    undelegation_er: Default::default(),
+                    undelegated_stake: Default::default(),
+                    unbonding_slashing_ratio: Default::default(),
+                },
+            )
+            .unwrap();
+        let err = compute_withdrawable_funds(deps.as_mut().storage, 1, &user1).unwrap_err();
+        assert!(matches!(err, ContractError::UndelegationEntryNotFound {}));
+    }
+
+    #[test]
+    fn test_compute_withdrawable_funds_success() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+
+        instantiate_contract(&mut deps, &info, &env);
+
+        let user1 = Addr::unchecked("user1");
+
+        /*
+           Test - 1. Success
+        */
+        BATCH_UNDELEGATION_REGISTRY
+            .save(
+                deps.as_mut().storage,
+                1,
+                &BatchUndelegationRecord {
+                    undelegated_tokens: Uint128::new(10000_u128),
+                    create_time: Default::default(),
+                    est_release_time: None,
+                    reconciled: true,
+                    undelegation_er: Decimal::one(),
+                    undelegated_stake: Uint128::new(10000_u128),
+                    unbonding_slashing_ratio: Decimal::from_ratio(3_u128, 4_u128),
+                },
+            )
+            .unwrap();
+        USERS
+            .save(
+                deps.as_mut().storage,
+                (&user1, 1),
+                &UndelegationInfo {
+                    batch_id: 1,
+                    token_amount: Uint128::new(1000_u128),
+                },
+            )
+            .unwrap();
+        let res = compute_withdrawable_funds(deps.as_mut().storage, 1, &user1).unwrap();
+        assert_eq!(
+            res,
+            GetFundsClaimRecord {
+                user_withdrawal_amount: Uint128::new(743_u128),
+                protocol_fee: Uint128::new(7_u128),
+                undelegated_tokens: Uint128::new(1000_u128),
+                protocol_withdraw_fee: Decimal::from_ratio(1_u128, 100_u128),
+                holding_period: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_withdraw_fee_schedule() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+
+        instantiate_contract(&mut deps, &info, &env);
+
+        let valid1 = Addr::unchecked("valid0001");
+        let valid2 = Addr::unchecked("valid0002");
+        let valid3 = Addr::unchecked("valid0003");
+        let user1 = Addr::unchecked("user1");
+        let user2 = Addr::unchecked("user2");
+        CONFIG
+            .update(
+                deps.as_mut().storage,
+                |mut config| -> Result<_, ContractError> {
+                    config.cw20_token_contract = Addr::unchecked("cw20_contract");
+                    Ok(config)
+                },
+            )
+            .unwrap();
+        deps.querier
+            .update_staking("uluna", &get_validators(), &get_delegations());
+        deps.querier
+            .update_stader_balances(Some(Uint128::new(3000_u128)), None);
+        STATE
+            .update(
+                deps.as_mut().storage,
+                |mut state| -> Result<_, ContractError> {
+                    state.validators = vec![valid1.clone(), valid2.clone(), valid3.clone()];
+                    state.total_staked = Uint128::new(3000_u128);
+                    Ok(state)
+                },
+            )
+            .unwrap();
+        for val_addr in [&valid1, &valid2, &valid3] {
+            VALIDATOR_META
+                .save(
+                    deps.as_mut().storage,
+                    val_addr,
+                    &VMeta {
+                        staked: Uint128::new(1000_u128),
+                        slashed: Default::default(),
+                        filled: Default::default(),
+                    },
+                )
+                .unwrap();
+        }
+        let withdraw_fee_schedule = WithdrawFeeSchedule {
+            tiers: vec![WithdrawFeeTier {
+                min_amount: Uint128::new(2000_u128),
+                fee: Decimal::from_ratio(5_u128, 1000_u128),
+            }],
+            holding_period_discounts: vec![HoldingPeriodDiscount {
+                min_holding_period: 1000,
+                discount: Decimal::from_ratio(1_u128, 2_u128),
+            }],
+        };
+        let get_config_update_request = |withdraw_fee_schedule| ConfigUpdateRequest {
+            min_deposit: None,
+            max_deposit: None,
+            cw20_token_contract: None,
+            protocol_reward_fee: None,
+            protocol_withdraw_fee: None,
+            protocol_deposit_fee: None,
+            airdrop_registry_contract: None,
+            unbonding_period: None,
+            undelegation_cooldown: None,
+            reinvest_cooldown: None,
+            withdraw_fee_schedule: Some(withdraw_fee_schedule),
+            config_timelock: None,
+            reinvest_split_limit: None,
+            reward_vesting_period: None,
+            min_holding_period: None,
+        };
+
+        /*
+           Test - 1. Tier fees above the withdraw fee cap are rejected
+        */
+        let mut invalid_schedule = withdraw_fee_schedule.clone();
+        invalid_schedule.tiers[0].fee = Decimal::from_ratio(6_u128, 100_u128);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateConfig {
+                config_request: get_config_update_request(invalid_schedule),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidWithdrawFeeSchedule {}));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateConfig {
+                config_request: get_config_update_request(withdraw_fee_schedule.clone()),
+            },
+        )
+        .unwrap();
+        let res: WithdrawFeeSchedule = from_binary(
+            &query(deps.as_ref(), env.clone(), QueryMsg::WithdrawFeeSchedule {}).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res, withdraw_fee_schedule);
+
+        /*
+           Test - 2. Holding period is measured from the token weighted deposit time
+        */
+        record_user_deposit(
+            deps.as_mut().storage,
+            &user1,
+            Uint128::new(250_u128),
+            env.block.time.minus_seconds(2000),
+        )
+        .unwrap();
+        record_user_deposit(
+            deps.as_mut().storage,
+            &user1,
+            Uint128::new(750_u128),
+            env.block.time.minus_seconds(1000),
+        )
+        .unwrap();
+        let user_deposit = USER_DEPOSITS.load(deps.as_mut().storage, &user1).unwrap();
+        assert_eq!(user_deposit.tokens_minted, Uint128::new(1000_u128));
+        assert_eq!(
+            user_deposit.deposit_time.seconds(),
+            env.block.time.seconds() - 1250
+        );
+        for user in ["user1", "user2"] {
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("cw20_contract", &[]),
+                ExecuteMsg::Receive(Cw20ReceiveMsg {
+                    sender: user.to_string(),
+                    amount: Uint128::new(1000_u128),
+                    msg: to_binary(&Cw20HookMsg::QueueUndelegate {}).unwrap(),
+                }),
+            )
+            .unwrap();
+        }
+        assert_eq!(
+            USER_HOLDING_PERIODS
+                .load(deps.as_mut().storage, (&user1, 1))
+                .unwrap(),
+            1250
+        );
+        assert_eq!(
+            USER_HOLDING_PERIODS
+                .load(deps.as_mut().storage, (&user2, 1))
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            USER_DEPOSITS
+                .load(deps.as_mut().storage, &user1)
+                .unwrap()
+                .tokens_minted,
+            Uint128::zero()
+        );
+
+        /*
+           Test - 3. Fee depends on the claim size and the holding period
+        */
+        BATCH_UNDELEGATION_REGISTRY
+            .save(
+                deps.as_mut().storage,
+                1,
+                &BatchUndelegationRecord {
+                    undelegated_tokens: Uint128::new(2000_u128),
+                    create_time: env.block.time,
+                    est_release_time: None,
+                    reconciled: true,
+                    undelegation_er: Decimal::one(),
+                    undelegated_stake: Uint128::new(2000_u128),
+                    unbonding_slashing_ratio: Decimal::one(),
+                },
+            )
+            .unwrap();
+        let res = compute_withdrawable_funds(deps.as_mut().storage, 1, &user1).unwrap();
+        assert_eq!(
+            res.protocol_withdraw_fee,
+            Decimal::from_ratio(5_u128, 1000_u128)
+        );
+        assert_eq!(res.protocol_fee, Uint128::new(5_u128));
+        assert_eq!(res.holding_period, 1250);
+        let res = compute_withdrawable_funds(deps.as_mut().storage, 1, &user2).unwrap();
+        assert_eq!(
+            res.protocol_withdraw_fee,
+            Decimal::from_ratio(1_u128, 100_u128)
+        );
+        assert_eq!(res.protocol_fee, Uint128::new(10_u128));
+
+        USERS
+            .save(
+                deps.as_mut().storage,
+                (&user1, 1),
+                &UndelegationInfo {
+                    batch_id: 1,
+                    token_amount: Uint128::new(3000_u128),
+                },
+            )
+            .unwrap();
+        let res: GetFundsClaimRecord = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::GetUserUndelegationInfo {
+                    user_addr: "user1".to_string(),
+                    batch_id: 1,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            res,
+            GetFundsClaimRecord {
+                user_withdrawal_amount: Uint128::new(2993_u128),
+                protocol_fee: Uint128::new(7_u128),
+                undelegated_tokens: Uint128::new(3000_u128),
+                protocol_withdraw_fee: Decimal::from_ratio(25_u128, 10000_u128),
+                holding_period: 1250
+            }
+        );
+
+        /*
+           Test - 4. Queueing more than was minted to the user counts as just acquired
+        */
+        let user3 = Addr::unchecked("user3");
+        record_user_deposit(
+            deps.as_mut().storage,
+            &user3,
+            Uint128::new(100_u128),
+            env.block.time.minus_seconds(5000),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("cw20_contract", &[]),
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: user3.to_string(),
+                amount: Uint128::new(150_u128),
+                msg: to_binary(&Cw20HookMsg::QueueUndelegate {}).unwrap(),
+            }),
+        )
+        .unwrap();
+        assert_eq!(
+            USER_HOLDING_PERIODS
+                .load(deps.as_mut().storage, (&user3, 1))
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            USER_DEPOSITS
+                .load(deps.as_mut().storage, &user3)
+                .unwrap()
+                .tokens_minted,
+            Uint128::zero()
+        );
+    }
+
+    #[test]
+    fn test_withdraw_funds_to_wallet() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+
+        instantiate_contract(&mut deps, &info, &env);
+
+        let user1 = Addr::unchecked("user1");
+
+        /*
+           withdraw paused
+        */
+        OPERATION_CONTROLS
+            .save(
+                deps.as_mut().storage,
+                &OperationControls {
+                    deposit_paused: false,
+                    queue_undelegate_paused: false,
+                    undelegate_paused: false,
+                    withdraw_paused: true,
+                    reinvest_paused: false,
+                    reconcile_paused: false,
+                    claim_airdrops_paused: false,
+                    redeem_rewards_paused: false,
+                },
+            )
+            .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::WithdrawFundsToWallet { batch_id: 0 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::OperationPaused(String { .. })));
+
+        OPERATION_CONTROLS
+            .save(
+                deps.as_mut().storage,
+                &OperationControls {
+                    deposit_paused: false,
+                    queue_undelegate_paused: false,
+                    undelegate_paused: false,
+                    withdraw_paused: false,
+                    reinvest_paused: false,
+                    reconcile_paused: false,
+                    claim_airdrops_paused: false,
+                    redeem_rewards_paused: false,
+                },
+            )
+            .unwrap();
+
+        STATE
+            .update(deps.as_mut().storage, |mut state| -> StdResult<_> {
+                state.reconciled_funds_to_withdraw = Uint128::new(1200);
+                Ok(state)
+            })
+            .unwrap();
+        BATCH_UNDELEGATION_REGISTRY
+            .save(
+                deps.as_mut().storage,
+                1,
+                &BatchUndelegationRecord {
+                    undelegated_tokens: Uint128::new(10000_u128),
+                    create_time: Default::default(),
+                    est_release_time: None,
+                    reconciled: true,
+                    undelegation_er: Decimal::one(),
+                    undelegated_stake: Uint128::new(10000_u128),
+                    unbonding_slashing_ratio: Decimal::from_ratio(3_u128, 4_u128),
+                },
+            )
+            .unwrap();
+        USERS
+            .save(
+                deps.as_mut().storage,
+                (&user1, 1),
+                &UndelegationInfo {
+                    batch_id: 1,
+                    token_amount: Uint128::new(1000_u128),
+                },
+            )
+            .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("user1", &[]),
+            ExecuteMsg::WithdrawFundsToWallet { batch_id: 1 },
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(BankMsg::Send {
+                to_address: user1.to_string(),
+                amount: vec![Coin::new(743_u128, "uluna".to_string())]
+            })
+        );
+        assert_eq!(
+            res.messages[1],
+            SubMsg::new(BankMsg::Send {
+                to_address: "protocol_fee_contract".to_string(),
+                amount: vec![Coin::new(7_u128, "uluna".to_string())]
+            })
+        );
+        let state = STATE.load(deps.as_mut().storage).unwrap();
+        assert_eq!(state.reconciled_funds_to_withdraw, Uint128::new(450));
+        let user_undel_info = USERS.may_load(deps.as_mut().storage, (&user1, 1)).unwrap();
+        assert_eq!(user_undel_info, None);
+    }
+
+    #[test]
+    fn test_protocol_revenue() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let mut env = mock_env();
+
+        instantiate_contract(&mut deps, &info, &env);
+
+        let user1 = Addr::unchecked("user1");
+
+        /*
+           Test - 1. Withdraw fee is recorded in the lifetime totals and the current epoch
+        */
+        STATE
+            .update(deps.as_mut().storage, |mut state| -> StdResult<_> {
+                state.reconciled_funds_to_withdraw = Uint128::new(1200);
+                Ok(state)
+            })
+            .unwrap();
+        BATCH_UNDELEGATION_REGISTRY
+            .save(
+                deps.as_mut().storage,
+                1,
+                &BatchUndelegationRecord {
+                    undelegated_tokens: Uint128::new(10000_u128),
+                    create_time: Default::default(),
+                    est_release_time: None,
+                    reconciled: true,
+                    undelegation_er: Decimal::one(),
+                    undelegated_stake: Uint128::new(10000_u128),
+                    unbonding_slashing_ratio: Decimal::one(),
+                },
+            )
+            .unwrap();
+        USERS
+            .save(
+                deps.as_mut().storage,
+                (&user1, 1),
+                &UndelegationInfo {
+                    batch_id: 1,
+                    token_amount: Uint128::new(1000_u128),
+                },
+            )
+            .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("user1", &[]),
+            ExecuteMsg::WithdrawFundsToWallet { batch_id: 1 },
+        )
+        .unwrap();
+        let epoch = env.block.time.seconds() / (24 * 3600);
+        let res: ProtocolRevenueResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::ProtocolRevenue {}).unwrap())
+                .unwrap();
+        assert_eq!(
+            res,
+            ProtocolRevenueResponse {
+                lifetime: ProtocolRevenue {
+                    withdraw_fees: Uint128::new(10_u128),
+                    ..Default::default()
+                },
+                current_epoch: epoch,
+                epoch_length: 24 * 3600,
+            }
+        );
+
+        /*
+           Test - 2. Revenue of a later epoch goes into a new bucket
+        */
+        env.block.time = env.block.time.plus_seconds(24 * 3600);
+        record_protocol_revenue(
+            deps.as_mut().storage,
+            env.block.time,
+            &ProtocolRevenue {
+                deposit_fees: Uint128::new(5_u128),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let res: ProtocolRevenueHistoryResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::ProtocolRevenueHistory {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.epochs,
+            vec![
+                EpochRevenue {
+                    epoch,
+                    revenue: ProtocolRevenue {
+                        withdraw_fees: Uint128::new(10_u128),
+                        ..Default::default()
+                    }
+                },
+                EpochRevenue {
+                    epoch: epoch + 1,
+                    revenue: ProtocolRevenue {
+                        deposit_fees: Uint128::new(5_u128),
+                        ..Default::default()
+                    }
+                }
+            ]
+        );
+        let res: ProtocolRevenueHistoryResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::ProtocolRevenueHistory {
+                    start_after: Some(epoch),
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.epochs.len(), 1);
+        let protocol_revenue = PROTOCOL_REVENUE.load(deps.as_mut().storage).unwrap();
+        assert_eq!(protocol_revenue.deposit_fees, Uint128::new(5_u128));
+        assert_eq!(protocol_revenue.withdraw_fees, Uint128::new(10_u128));
+    }
+
+    #[test]
+    fn test_undelegate_stake_fail() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+
+        instantiate_contract(&mut deps, &info, &env);
+
+        let valid1 = Addr::unchecked("valid0001");
+        let valid2 = Addr::unchecked("valid0002");
+        let valid3 = Addr::unchecked("valid0003");
+        STATE
+            .update(
+                deps.as_mut().storage,
+                |mut state| -> Result<_, ContractError> {
+                    state.validators = vec![valid1.clone(), valid2.clone(), valid3.clone()];
+                    Ok(state)
+                },
+            )
+            .unwrap();
+        VALIDATOR_META
+            .save(
+                deps.as_mut().storage,
+                &valid1,
+                &VMeta {
+                    staked: Uint128::new(1000_u128),
+                    slashed: Default::default(),
+                    filled: Default::default(),
+                },
+            )
+            .unwrap();
+        VALIDATOR_META
+            .save(
+                deps.as_mut().storage,
+                &valid2,
+                &VMeta {
+                    staked: Uint128::new(1000_u128),
+                    slashed: Default::default(),
+                    filled: Default::default(),
+                },
+            )
+            .unwrap();
+        VALIDATOR_META
+            .save(
+                deps.as_mut().storage,
+                &valid3,
+                &VMeta {
+                    staked: Uint128::new(1000_u128),
+                    slashed: Default::default(),
+                    filled: Default::default(),
+                },
+            )
+            .unwrap();
+        deps.querier
+            .update_staking("uluna", &get_validators(), &get_delegations());
+        deps.querier
+            .update_stader_balances(Some(Uint128::new(3000_u128)), None);
+
+        /*
+           Paused
+        */
+        OPERATION_CONTROLS
+            .save(
+                deps.as_mut().storage,
+                &OperationControls {
+                    deposit_paused: false,
+                    queue_undelegate_paused: false,
+                    undelegate_paused: true,
+                    withdraw_paused: false,
+                    reinvest_paused: false,
+                    reconcile_paused: false,
+                    claim_airdrops_paused: false,
+                    redeem_rewards_paused: false,
+                },
+            )
+            .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::Undelegate {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::OperationPaused(String { .. })));
+
+        OPERATION_CONTROLS
+            .save(
+                deps.as_mut().storage,
+                &OperationControls {
+                    deposit_paused: false,
+                    queue_undelegate_paused: false,
+                    undelegate_paused: false,
+                    withdraw_paused: false,
+                    reinvest_paused: false,
+                    reconcile_paused: false,
+                    claim_airdrops_paused: false,
+                    redeem_rewards_paused: false,
+                },
+            )
+            .unwrap();
+
+        /*
+           Test - 1. Undelegation in cooldown
+        */
+        CONFIG
+            .update(
+                deps.as_mut().storage,
+                |mut config| -> Result<_, ContractError> {
+                    config.undelegation_cooldown = 1000;
+                    Ok(config)
+                },
+            )
+            .unwrap();
+        STATE
+            .update(
+                deps.as_mut().storage,
+                |mut state| -> Result<_, ContractError> {
+                    state.last_undelegation_time = env.block.time.minus_seconds(100);
+                    Ok(state)
+                },
+            )
+            .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::Undelegate {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::UndelegationInCooldown {}));
+
+        let valid1 = Addr::unchecked("valid0001");
+        let valid2 = Addr::unchecked("valid0002");
+        let valid3 = Addr::unchecked("valid0003");
+        /*
+            Test - 2. No-Op
+        */
+        CONFIG
+            .update(
+                deps.as_mut().storage,
+                |mut config| -> Result<_, ContractError> {
+                    config.undelegation_cooldown = 1000;
+                    Ok(config)
+                },
+            )
+            .unwrap();
+        STATE
+            .update(
+                deps.as_mut().storage,
+                |mut state| -> Result<_, ContractError> {
+                    state.last_undelegation_time = env.block.time.minus_seconds(2000);
+                    Ok(state)
+                },
+            )
+            .unwrap();
+        STATE
+            .update(
+                deps.as_mut().storage,
+                |mut state| -> Result<_, ContractError> {
+                    state.validators = vec![valid1.clone(), valid2.clone(), valid3.clone()];
+                    state.current_undelegation_batch_id = 1;
+                    Ok(state)
+                },
+            )
+            .unwrap();
+        deps.querier
+            .update_staking("uluna", &get_validators(), &get_delegations());
+        deps.querier
+            .update_stader_balances(Some(Uint128::new(3000_u128)), None);
+        VALIDATOR_META
+            .save(
+                deps.as_mut().storage,
+                &valid2,
+                &VMeta {
+                    staked: Uint128::new(1000_u128),
+                    slashed: Default::default(),
+                    filled: Default::default(),
+                },
+            )
+            .unwrap();
+        VALIDATOR_META
+            .save(
+                deps.as_mut().storage,
+                &valid3,
+                &VMeta {
+                    staked: Uint128::new(1000_u128),
+                    slashed: Default::default(),
+                    filled: Default::default(),
+                },
+            )
+            .unwrap();
+        VALIDATOR_META
+            .save(
+                deps.as_mut().storage,
+                &valid1,
+                &VMeta {
+                    staked: Uint128::new(1000_u128),
+                    slashed: Default::default(),
+                    filled: Default::default(),
+                },
+            )
+            .unwrap();
+        BATCH_UNDELEGATION_REGISTRY
+            .save(
+                deps.as_mut().storage,
+                1,
+                &BatchUndelegationRecord {
+                    undelegated_tokens: Uint128::zero(),
+                    create_time: Default::default(),
+                    est_release_time: None,
+                    reconciled: false,
+                    undelegation_er: Default::default(),
+                    undelegated_stake: Default::default(),
+                    unbonding_slashing_ratio: Default::default(),
+                },
+            )
+            .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::Undelegate {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoOp {}));
+
+        /*
+            Test - 3. Validators do not have sufficient funds
+        */
+        CONFIG
+            .update(
+                deps.as_mut().storage,
+                |mut config| -> Result<_, ContractError> {
+                    config.undelegation_cooldown = 1000;
+                    Ok(config)
+                },
+            )
+            .unwrap();
+        STATE
+            .update(
+                deps.as_mut().storage,
+                |mut state| -> Result<_, ContractError> {
+                    state.last_undelegation_time = env.block.time.minus_seconds(2000);
+                    Ok(state)
+                },
+            )
+            .unwrap();
+        STATE
+            .update(
+                deps.as_mut().storage,
+                |mut state| -> Result<_, ContractError> {
+                    state.validators = vec![valid1.clone(), valid2.clone(), valid3.clone()];
+                    state.current_undelegation_batch_id = 1;
+                    Ok(state)
+                },
+            )
+            .unwrap();
+        deps.querier
+            .update_staking("uluna", &get_validators(), &get_delegations());
+        deps.querier
+            .update_stader_balances(Some(Uint128::new(3000_u128)), None);
+        VALIDATOR_META
+            .save(
+                deps.as_mut().storage,
+                &valid2,
+                &VMeta {
+                    staked: Uint128::new(1000_u128),
+                    slashed: Default::default(),
+                    filled: Default::default(),
+                },
+            )
+            .unwrap();
+        VALIDATOR_META
+            .save(
+                deps.as_mut().storage,
+                &valid3,
+                &VMeta {
+                    staked: Uint128::new(1000_u128),
+                    slashed: Default::default(),
+                    filled: Default::default(),
+                },
+            )
+            .unwrap();
+        VALIDATOR_META
+            .save(
+                deps.as_mut().storage,
+                &valid1,
+                &VMeta {
+                    staked: Uint128::new(1000_u128),
+                    slashed: Default::default(),
+                    filled: Default::default(),
+                },
+            )
+            .unwrap();
+        BATCH_UNDELEGATION_REGISTRY
+            .save(
+                deps.as_mut().storage,
+                1,
+                &BatchUndelegationRecord {
+                    undelegated_tokens: Uint128::new(4000_u128),
+                    create_time: Default::default(),
+                    est_release_time: None,
+                    reconciled: false,
+                    undelegation_er: Default::default(),
+                    undelegated_stake: Default::default(),
+                    unbonding_slashing_ratio: Default::default(),
+                },
+            )
+            .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::Undelegate {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InSufficientFunds {}));
+    }
+
+    #[test]
+    fn test_undelegate_stake_success() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+
+        instantiate_contract(&mut deps, &info, &env);
+
+        /*
+           Test - 1. Successful run
+        */
+        CONFIG
+            .update(
+                deps.as_mut().storage,
+                |mut config| -> Result<_, ContractError> {
+                    config.undelegation_cooldown = 1000;
+                    Ok(config)
+                },
+            )
+            .unwrap();
+        STATE
+            .update(
+                deps.as_mut().storage,
+                |mut state| -> Result<_, ContractError> {
+                    state.last_undelegation_time = env.block.time.minus_seconds(2000);
+                    Ok(state)
+                },
+            )
+            .unwrap();
+        let valid1 = Addr::unchecked("valid0001");
+        let valid2 = Addr::unchecked("valid0002");
+        let valid3 = Addr::unchecked("valid0003");
+        STATE
+            .update(
+                deps.as_mut().storage,
+                |mut state| -> Result<_, ContractError> {
+                    state.validators = vec![valid1.clone(), valid2.clone(), valid3.clone()];
+                    state.current_undelegation_batch_id = 1;
+                    state.total_staked = Uint128::new(3000_u128);
+                    Ok(state)
+                },
+            )
+            .unwrap();
+        deps.querier
+            .update_staking("uluna", &get_validators(), &get_delegations());
+        deps.querier
+            .update_stader_balances(Some(Uint128::new(3000_u128)), None);
+        VALIDATOR_META
+            .save(
+                deps.as_mut().storage,
+                &valid2,
+                &VMeta {
+                    staked: Uint128::new(1000_u128),
+                    slashed: Default::default(),
+                    filled: Default::default(),
+                },
+            )
+            .unwrap();
+        VALIDATOR_META
+            .save(
+                deps.as_mut().storage,
+                &valid3,
+                &VMeta {
+                    staked: Uint128::new(1000_u128),
+                    slashed: Default::default(),
+                    filled: Default::default(),
+                },
+            )
+            .unwrap();
+        VALIDATOR_META
+            .save(
+                deps.as_mut().storage,
+                &valid1,
+                &VMeta {
+                    staked: Uint128::new(1000_u128),
+                    slashed: Default::default(),
+                    filled: Default::default(),
+                },
+            )
+            .unwrap();
+        BATCH_UNDELEGATION_REGISTRY
+            .save(
+                deps.as_mut().storage,
+                1,
+                &BatchUndelegationRecord {
+                    undelegated_tokens: Uint128::new(2000_u128),
+                    create_time: env.block.time.minus_seconds(10000),
+                    est_release_time: None,
+                    reconciled: false,
+                    undelegation_er: Default::default(),
+                    undelegated_stake: Default::default(),
+                    unbonding_slashing_ratio: Default::default(),
+                },
+            )
+            .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::Undelegate {},
+        )
+        .unwrap();
+        let config = CONFIG.load(deps.as_mut().storage).unwrap();
+        assert_eq!(res.messages.len(), 5);
+        let mut expected_msgs = wrap_rewards_withdrawal(
+            &config.reward_contract,
+            vec![
+                StakingMsg::Undelegate {
+                    validator: valid3.to_string(),
+                    amount: Coin::new(1000_u128, "uluna".to_string()),
+                }
+                .into(),
+                StakingMsg::Undelegate {
+                    validator: valid2.to_string(),
+                    amount: Coin::new(1000_u128, "uluna".to_string()),
+                }
+                .into(),
+            ],
+        )
+        .unwrap();
+        expected_msgs.push(SubMsg::new(WasmMsg::Execute {
+            contract_addr: config.cw20_token_contract.to_string(),
+            msg: to_binary(&cw20::Cw20ExecuteMsg::Burn {
+                amount: Uint128::new(2000_u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        }));
+        assert!(check_equal_vec(res.messages, expected_msgs));
+        let val3_meta = VALIDATOR_META.load(deps.as_mut().storage, &valid3).unwrap();
+        let val2_meta = VALIDATOR_META.load(deps.as_mut().storage, &valid2).unwrap();
+        assert_eq!(
+            val3_meta,
+            VMeta {
+                staked: Uint128::zero(),
+                slashed: Default::default(),
+                filled: Default::default()
+            }
+        );
+        assert_eq!(
+            val2_meta,
+            VMeta {
+                staked: Uint128::zero(),
+                slashed: Default::default(),
+                filled: Default::default()
+            }
+        );
+        let config = CONFIG.load(deps.as_mut().storage).unwrap();
+        let undel_batch = BATCH_UNDELEGATION_REGISTRY
+            .load(deps.as_mut().storage, 1)
+            .unwrap();
+        assert_eq!(
+            undel_batch,
+            BatchUndelegationRecord {
+                undelegated_tokens: Uint128::new(2000_u128),
+                create_time: env.block.time.minus_seconds(10000),
+                est_release_time: Some(env.block.time.plus_seconds(config.unbonding_period)),
+                reconciled: false,
+                undelegation_er: Decimal::one(),
+                undelegated_stake: Uint128::new(2000_u128),
+                unbonding_slashing_ratio: Default::default()
+            }
+        );
+        let state = STATE.load(deps.as_mut().storage).unwrap();
+        assert_eq!(state.total_staked, Uint128::new(1000_u128));
+        assert_eq!(state.last_undelegation_time, env.block.time);
+        let new_undel_batch = BATCH_UNDELEGATION_REGISTRY
+            .may_load(deps.as_mut().storage, 2)
+            .unwrap();
+        assert_ne!(new_undel_batch, None);
+    }
+
+    #[test]
+    fn test_reconcile_funds() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+
+        instantiate_contract(&mut deps, &info, &env);
+
+        OPERATION_CONTROLS
+            .save(
+                deps.as_mut().storage,
+                &OperationControls {
+                    deposit_paused: false,
+                    queue_undelegate_paused: false,
+                    undelegate_paused: false,
+                    withdraw_paused: false,
+                    reinvest_paused: false,
+                    reconcile_paused: true,
+                    claim_airdrops_paused: false,
+                    redeem_rewards_paused: false,
+                },
+            )
+            .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::ReconcileFunds {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::OperationPaused(String { .. })));
+
+        OPERATION_CONTROLS
+            .save(
+                deps.as_mut().storage,
+                &OperationControls {
+                    deposit_paused: false,
+                    queue_undelegate_paused: false,
+                    undelegate_paused: false,
+                    withdraw_paused: false,
+                    reinvest_paused: false,
+                    reconcile_paused: false,
+                    claim_airdrops_paused: false,
+                    redeem_rewards_paused: false,
+                },
+            )
+            .unwrap();
+
+        /*
+           Test - 1. No undelegation slashing
+        */
+        STATE
+            .update(
+                deps.as_mut().storage,
+                |mut state| -> Result<_, ContractError> {
+                    state.current_undelegation_batch_id = 3;
+                    state.last_reconciled_batch_id = 1;
+                    state.reconciled_funds_to_withdraw = Uint128::new(1800);
+                    Ok(state)
+                },
+            )
+            .unwrap();
+        BATCH_UNDELEGATION_REGISTRY
+            .save(
+                deps.as_mut().storage,
+                2,
+                &BatchUndelegationRecord {
+                    undelegated_tokens: Uint128::new(3000_u128),
+                    create_time: env.block.time.minus_seconds(20000),
+                    est_release_time: Some(env.block.time.minus_seconds(300)),
+                    reconciled: false,
+                    undelegation_er: Decimal::one(),
+                    undelegated_stake: Uint128::new(3000_u128),
+                    unbonding_slashing_ratio: Decimal::one(),
+                },
+            )
+            .unwrap();
+        BATCH_UNDELEGATION_REGISTRY
+            .save(
+                deps.as_mut().storage,
+                3,
+                &BatchUndelegationRecord {
+                    undelegated_tokens: Uint128::new(2000_u128),
+                    create_time: env.block.time.minus_seconds(10000),
+                    est_release_time: Some(env.block.time.minus_seconds(100)),
+                    reconciled: false,
+                    undelegation_er: Decimal::one(),
+                    undelegated_stake: Uint128::new(2000_u128),
+                    unbonding_slashing_ratio: Decimal::one(),
+                },
+            )
+            .unwrap();
+        deps.querier.update_balance(
+            env.contract.address.clone(),
+            vec![Coin::new(7000_u128, "uluna".to_string())],
+        );
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::ReconcileFunds {},
+        )
+        .unwrap();
+        let state = STATE.load(deps.as_mut().storage).unwrap();
+        assert_eq!(state.reconciled_funds_to_withdraw, Uint128::new(6800));
+        let batch_2 = BATCH_UNDELEGATION_REGISTRY
+            .load(deps.as_mut().storage, 2)
+            .unwrap();
+        let batch_3 = BATCH_UNDELEGATION_REGISTRY
+            .load(deps.as_mut().storage, 3)
+            .unwrap();
+        assert_eq!(
+            batch_2,
+            BatchUndelegationRecord {
+                undelegated_tokens: Uint128::new(3000_u128),
+                create_time: env.block.time.minus_seconds(20000),
+                est_release_time: Some(env.block.time.minus_seconds(300)),
+                reconciled: true,
+                undelegation_er: Decimal::one(),
+                undelegated_stake: Uint128::new(3000_u128),
+                unbonding_slashing_ratio: Decimal::one()
+            }
+        );
+        assert_eq!(
+            batch_3,
+            BatchUndelegationRecord {
+                undelegated_tokens: Uint128::new(2000_u128),
+                create_time: env.block.time.minus_seconds(10000),
+                est_release_time: Some(env.block.time.minus_seconds(100)),
+                reconciled: true,
+                undelegation_er: Decimal::one(),
+                undelegated_stake: Uint128::new(2000_u128),
+                unbonding_slashing_ratio: Decimal::one()
+            }
+        );
+        let inflow_tracker = INFLOW_TRACKER.load(deps.as_mut().storage).unwrap();
+        assert_eq!(inflow_tracker.quarantined_funds, Uint128::new(200));
+        INFLOW_TRACKER.remove(deps.as_mut().storage);
+
+        /*
+           Test - 2. Some undelegation slashing
+        */
+        STATE
+            .update(
+                deps.as_mut().storage,
+                |mut state| -> Result<_, ContractError> {
+                    state.current_undelegation_batch_id = 3;
+                    state.last_reconciled_batch_id = 1;
+                    state.reconciled_funds_to_withdraw = Uint128::new(1800);
+                    Ok(state)
+                },
+            )
+            .unwrap();
+        BATCH_UNDELEGATION_REGISTRY
+            .save(
+                deps.as_mut().storage,
+                2,
+                &BatchUndelegationRecord {
+                    undelegated_tokens: Uint128::new(3000_u128),
+                    create_time: env.block.time.minus_seconds(20000),
+                    est_release_time: Some(env.block.time.minus_seconds(300)),
+                    reconciled: false,
+                    undelegation_er: Decimal::one(),
+                    undelegated_stake: Uint128::new(3000_u128),
+                    unbonding_slashing_ratio: Default::default(),
+                },
+            )
+            .unwrap();
+        BATCH_UNDELEGATION_REGISTRY
+            .save(
+                deps.as_mut().storage,
+                3,
+                &BatchUndelegationRecord {
+                    undelegated_tokens: Uint128::new(2000_u128),
+                    create_time: env.block.time.minus_seconds(10000),
+                    est_release_time: Some(env.block.time.minus_seconds(100)),
+                    reconciled: false,
+                    undelegation_er: Decimal::one(),
+                    undelegated_stake: Uint128::new(2000_u128),
+                    unbonding_slashing_ratio: Default::default(),
+                },
+            )
+            .unwrap();
+        deps.querier.update_balance(
+            env.contract.address.clone(),
+            vec![Coin::new(6000_u128, "uluna".to_string())],
+        );
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::ReconcileFunds {},
+        )
+        .unwrap();
+        let state = STATE.load(deps.as_mut().storage).unwrap();
+        assert_eq!(state.reconciled_funds_to_withdraw, Uint128::new(6000));
+        let batch_2 = BATCH_UNDELEGATION_REGISTRY
+            .load(deps.as_mut().storage, 2)
+            .unwrap();
+        let batch_3 = BATCH_UNDELEGATION_REGISTRY
+            .load(deps.as_mut().storage, 3)
+            .unwrap();
+        assert_eq!(
+            batch_2,
+            BatchUndelegationRecord {
+                undelegated_tokens: Uint128::new(3000_u128),
+                create_time: env.block.time.minus_seconds(20000),
+                est_release_time: Some(env.block.time.minus_seconds(300)),
+                reconciled: true,
+                undelegation_er: Decimal::one(),
+                undelegated_stake: Uint128::new(3000_u128),
+                unbonding_slashing_ratio: Decimal::from_ratio(42_u128, 50_u128)
+            }
+        );
+        assert_eq!(
+            batch_3,
+            BatchUndelegationRecord {
+                undelegated_tokens: Uint128::new(2000_u128),
+                create_time: env.block.time.minus_seconds(10000),
+                est_release_time: Some(env.block.time.minus_seconds(100)),
+                reconciled: true,
+                undelegation_er: Decimal::one(),
+                undelegated_stake: Uint128::new(2000_u128),
+                unbonding_slashing_ratio: Decimal::from_ratio(42_u128, 50_u128)
+            }
+        );
+
+        /*
+           Test - 3. Only the surplus above every outstanding batch is quarantined. Batch 3 has
+           already paid out ahead of its estimated release time.
+        */
+        STATE
+            .update(
+                deps.as_mut().storage,
+                |mut state| -> Result<_, ContractError> {
+                    state.current_undelegation_batch_id = 3;
+                    state.last_reconciled_batch_id = 1;
+                    state.reconciled_funds_to_withdraw = Uint128::new(1800);
+                    Ok(state)
+                },
+            )
+            .unwrap();
+        BATCH_UNDELEGATION_REGISTRY
+            .save(
+                deps.as_mut().storage,
+                2,
+                &BatchUndelegationRecord {
+                    undelegated_tokens: Uint128::new(3000_u128),
+                    create_time: env.block.time.minus_seconds(20000),
+                    est_release_time: Some(env.block.time.minus_seconds(300)),
+                    reconciled: false,
+                    undelegation_er: Decimal::one(),
+                    undelegated_stake: Uint128::new(3000_u128),
+                    unbonding_slashing_ratio: Default::default(),
+                },
+            )
+            .unwrap();
+        BATCH_UNDELEGATION_REGISTRY
+            .save(
+                deps.as_mut().storage,
+                3,
+                &BatchUndelegationRecord {
+                    undelegated_tokens: Uint128::new(2000_u128),
+                    create_time: env.block.time.minus_seconds(10000),
+                    est_release_time: Some(env.block.time.plus_seconds(100)),
+                    reconciled: false,
+                    undelegation_er: Decimal::one(),
+                    undelegated_stake: Uint128::new(2000_u128),
+                    unbonding_slashing_ratio: Default::default(),
+                },
+            )
+            .unwrap();
+        deps.querier.update_balance(
+            env.contract.address.clone(),
+            vec![Coin::new(7300_u128, "uluna".to_string())],
+        );
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::ReconcileFunds {},
+        )
+        .unwrap();
+        let state = STATE.load(deps.as_mut().storage).unwrap();
+        assert_eq!(state.reconciled_funds_to_withdraw, Uint128::new(4800));
+        assert_eq!(state.last_reconciled_batch_id, 2);
+        assert!(
+            !BATCH_UNDELEGATION_REGISTRY
+                .load(deps.as_mut().storage, 3)
+                .unwrap()
+                .reconciled
+        );
+        let inflow_tracker = INFLOW_TRACKER.load(deps.as_mut().storage).unwrap();
+        assert_eq!(inflow_tracker.quarantined_funds, Uint128::new(500));
+
+        /*
+           Test - 4. Unbonding landed before est_release_time is kept for its batch
+        */
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::ReconcileFunds {},
+        )
+        .unwrap();
+        let inflow_tracker = INFLOW_TRACKER.load(deps.as_mut().storage).unwrap();
+        assert_eq!(inflow_tracker.quarantined_funds, Uint128::new(500));
+
+        let mut release_env = env.clone();
+        release_env.block.time = env.block.time.plus_seconds(100);
+        execute(
+            deps.as_mut(),
+            release_env,
+            mock_info("other", &[]),
+            ExecuteMsg::ReconcileFunds {},
+        )
+        .unwrap();
+        let state = STATE.load(deps.as_mut().storage).unwrap();
+        assert_eq!(state.reconciled_funds_to_withdraw, Uint128::new(6800));
+        assert_eq!(state.last_reconciled_batch_id, 3);
+        let batch = BATCH_UNDELEGATION_REGISTRY
+            .load(deps.as_mut().storage, 3)
+            .unwrap();
+        assert!(batch.reconciled);
+        assert_eq!(batch.unbonding_slashing_ratio, Decimal::one());
+        let inflow_tracker = INFLOW_TRACKER.load(deps.as_mut().storage).unwrap();
+        assert_eq!(inflow_tracker.quarantined_funds, Uint128::new(500));
+    }
+
+    #[test]
+    fn test_roles() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+
+        instantiate_contract(&mut deps, &info, &env);
+
+        let pause_deposits = ExecuteMsg::UpdateOperationFlags {
+            operation_controls_update_request: OperationControlsUpdateRequest {
+                deposit_paused: Some(true),
+                queue_undelegate_paused: None,
+                undelegate_paused: None,
+                withdraw_paused: None,
+                reinvest_paused: None,
+                reconcile_paused: None,
+                claim_airdrops_paused: None,
+                redeem_rewards_paused: None,
+            },
+        };
+        let update_fee_split = ExecuteMsg::UpdateFeeSplit {
+            fee_split: vec![FeeSplitEntry {
+                recipient: Addr::unchecked("treasury"),
+                weight: Decimal::one(),
+            }],
+        };
+
+        /*
+           Test - 1. Only the manager or an admin can grant roles
+        */
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::GrantRole {
+                role: Role::Pauser,
+                address: "other".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::GrantRole {
+                role: Role::Admin,
+                address: "admin".to_string(),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            ExecuteMsg::GrantRole {
+                role: Role::Pauser,
+                address: "pauser".to_string(),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            ExecuteMsg::GrantRole {
+                role: Role::FeeManager,
+                address: "fee_manager".to_string(),
+            },
+        )
+        .unwrap();
+
+        let res: RoleMembersResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::RoleMembers { role: Role::Pauser },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.members, vec![Addr::unchecked("pauser")]);
+
+        let res: HasRoleResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::HasRole {
+                    role: Role::Keeper,
+                    address: "creator".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(res.has_role);
+        let res: HasRoleResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::HasRole {
+                    role: Role::FeeManager,
+                    address: "pauser".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(!res.has_role);
+
+        /*
+           Test - 2. Roles only unlock their own operations
+        */
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("pauser", &[]),
+            update_fee_split.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("fee_manager", &[]),
+            pause_deposits.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("pauser", &[]),
+            ExecuteMsg::AddValidator {
+                val_addr: Addr::unchecked("valid0001"),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("pauser", &[]),
+            pause_deposits.clone(),
+        )
+        .unwrap();
+        let operation_controls = OPERATION_CONTROLS.load(deps.as_mut().storage).unwrap();
+        assert!(operation_controls.deposit_paused);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("fee_manager", &[]),
+            update_fee_split,
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("fee_manager", &[]),
+            ExecuteMsg::UpdateConfig {
+                config_request: ConfigUpdateRequest {
+                    protocol_reward_fee: Some(Decimal::from_ratio(2_u128, 100_u128)),
+                    ..Default::default()
+                },
+            },
+        )
+        .unwrap();
+        let config = CONFIG.load(deps.as_mut().storage).unwrap();
+        assert_eq!(
+            config.protocol_reward_fee,
+            Decimal::from_ratio(2_u128, 100_u128)
+        );
+
+        // Config beyond the fees needs an admin
+        let non_fee_request = ExecuteMsg::UpdateConfig {
+            config_request: ConfigUpdateRequest {
+                protocol_reward_fee: Some(Decimal::from_ratio(3_u128, 100_u128)),
+                unbonding_period: Some(3600 * 24 * 21 + 600),
+                ..Default::default()
+            },
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("fee_manager", &[]),
+            non_fee_request.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            non_fee_request,
+        )
+        .unwrap();
+        let config = CONFIG.load(deps.as_mut().storage).unwrap();
+        assert_eq!(config.unbonding_period, 3600 * 24 * 21 + 600);
+
+        /*
+           Test - 3. Revoked roles lose access
+        */
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("admin", &[]),
+            ExecuteMsg::RevokeRole {
+                role: Role::Pauser,
+                address: "pauser".to_string(),
+            },
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("pauser", &[]),
+            pause_deposits,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let res: RoleMembersResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::RoleMembers { role: Role::Pauser },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(res.members.is_empty());
+
+        /*
+           Test - 4. Grants and revokes are in the audit log
+        */
+        let res: AuditLogResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::AuditLog {
+                    start_after: None,
+                    limit: Some(30),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let role_entries = res
+            .entries
+            .into_iter()
+            .filter(|x| x.operation.eq("grant_role") || x.operation.eq("revoke_role"))
+            .map(|x| (x.sender, x.operation, x.changes))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            role_entries,
+            vec![
+                (
+                    Addr::unchecked("creator"),
+                    "grant_role".to_string(),
+                    vec![AuditChange::new("admin", "", "admin")]
+                ),
+                (
+                    Addr::unchecked("admin"),
+                    "grant_role".to_string(),
+                    vec![AuditChange::new("pauser", "", "pauser")]
+                ),
+                (
+                    Addr::unchecked("admin"),
+                    "grant_role".to_string(),
+                    vec![AuditChange::new("fee_manager", "", "fee_manager")]
+                ),
+                (
+                    Addr::unchecked("admin"),
+                    "revoke_role".to_string(),
+                    vec![AuditChange::new("pauser", "pauser", "")]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pause_operations() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+
+        instantiate_contract(&mut deps, &info, &env);
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::GrantRole {
+                role: Role::Guardian,
+                address: "guardian".to_string(),
+            },
+        )
+        .unwrap();
+
+        /*
+           Test - 1. Guardians can only pause known operations with a future expiry
+        */
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::PauseOperations {
+                operations: vec!["deposit".to_string()],
+                reason: None,
+                expires_at: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("guardian", &[]),
+            ExecuteMsg::PauseOperations {
+                operations: vec![],
+                reason: None,
+                expires_at: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoOperationsToPause {}));
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("guardian", &[]),
+            ExecuteMsg::PauseOperations {
+                operations: vec!["stake".to_string()],
+                reason: None,
+                expires_at: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidOperation(_)));
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("guardian", &[]),
+            ExecuteMsg::PauseOperations {
+                operations: vec!["deposit".to_string()],
+                reason: None,
+                expires_at: Some(env.block.time.seconds()),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPauseExpiry {}));
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("guardian", &[]),
+            ExecuteMsg::UpdateOperationFlags {
+                operation_controls_update_request: OperationControlsUpdateRequest {
+                    deposit_paused: Some(false),
+                    queue_undelegate_paused: None,
+                    undelegate_paused: None,
+                    withdraw_paused: None,
+                    reinvest_paused: None,
+                    reconcile_paused: None,
+                    claim_airdrops_paused: None,
+                    redeem_rewards_paused: None,
+                },
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("guardian", &[]),
+            ExecuteMsg::PauseOperations {
+                operations: vec!["deposit".to_string(), "withdraw".to_string()],
+                reason: Some("Exchange rate anomaly".to_string()),
+                expires_at: Some(env.block.time.seconds() + 100),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::PauseOperations {
+                operations: vec!["reinvest".to_string()],
+                reason: Some("Reward contract upgrade".to_string()),
+                expires_at: None,
+            },
+        )
+        .unwrap();
+
+        let operation_controls = OPERATION_CONTROLS.load(deps.as_mut().storage).unwrap();
+        assert!(operation_controls.deposit_paused);
+        assert!(operation_controls.withdraw_paused);
+        assert!(operation_controls.reinvest_paused);
+
+        let res: ActivePausesResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::ActivePauses {}).unwrap())
+                .unwrap();
+        assert_eq!(res.active_pauses.len(), 3);
+        assert_eq!(res.active_pauses[0].operation, "deposit");
+        assert_eq!(
+            res.active_pauses[0].pause,
+            PauseRecord {
+                id: 1,
+                operations: vec!["deposit".to_string(), "withdraw".to_string()],
+                reason: Some("Exchange rate anomaly".to_string()),
+                paused_by: Addr::unchecked("guardian"),
+                pause_time: env.block.time.seconds(),
+                expires_at: Some(env.block.time.seconds() + 100),
+            }
+        );
+        assert_eq!(res.active_pauses[1].operation, "reinvest");
+        assert_eq!(res.active_pauses[1].pause.id, 2);
+
+        /*
+           Test - 2. A shorter pause does not cut an existing one short
+        */
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("guardian", &[]),
+            ExecuteMsg::PauseOperations {
+                operations: vec!["reinvest".to_string(), "withdraw".to_string()],
+                reason: None,
+                expires_at: Some(env.block.time.seconds() + 50),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            ACTIVE_PAUSES
+                .load(deps.as_mut().storage, "reinvest")
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            ACTIVE_PAUSES
+                .load(deps.as_mut().storage, "withdraw")
+                .unwrap(),
+            1
+        );
+
+        /*
+           Test - 3. Expired pauses are lifted
+        */
+        let mut later_env = env.clone();
+        later_env.block.time = later_env.block.time.plus_seconds(100);
+        let res: OperationControls = from_binary(
+            &query(
+                deps.as_ref(),
+                later_env.clone(),
+                QueryMsg::OperationControls {},
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(!res.deposit_paused);
+        assert!(!res.withdraw_paused);
+        assert!(res.reinvest_paused);
+
+        let res: ActivePausesResponse = from_binary(
+            &query(deps.as_ref(), later_env.clone(), QueryMsg::ActivePauses {}).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.active_pauses.len(), 1);
+        assert_eq!(res.active_pauses[0].operation, "reinvest");
+
+        execute(
+            deps.as_mut(),
+            later_env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateOperationFlags {
+                operation_controls_update_request: OperationControlsUpdateRequest {
+                    deposit_paused: None,
+                    queue_undelegate_paused: None,
+                    undelegate_paused: None,
+                    withdraw_paused: None,
+                    reinvest_paused: Some(false),
+                    reconcile_paused: None,
+                    claim_airdrops_paused: None,
+                    redeem_rewards_paused: None,
+                },
+            },
+        )
+        .unwrap();
+        let operation_controls = OPERATION_CONTROLS.load(deps.as_mut().storage).unwrap();
+        assert!(!operation_controls.deposit_paused);
+        assert!(!operation_controls.withdraw_paused);
+        assert!(!operation_controls.reinvest_paused);
+
+        let res: ActivePausesResponse = from_binary(
+            &query(deps.as_ref(), later_env.clone(), QueryMsg::ActivePauses {}).unwrap(),
+        )
+        .unwrap();
+        assert!(res.active_pauses.is_empty());
+
+        let res: PauseHistoryResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                later_env,
+                QueryMsg::PauseHistory {
+                    start_after: Some(1),
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.pauses.len(), 2);
+        assert_eq!(res.pauses[0].id, 2);
+        assert_eq!(res.pauses[1].id, 3);
+    }
+
+    #[test]
+    fn test_audit_log() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+
+        instantiate_contract(&mut deps, &info, &env);
+        deps.querier
+            .update_staking("uluna", &get_validators(), &get_delegations());
+
+        /*
+           Test - 1. Privileged changes are recorded with the sender and old and new values
+        */
+        let val_addr = Addr::unchecked("valid0001");
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::AddValidator {
+                val_addr: val_addr.clone(),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateOperationFlags {
+                operation_controls_update_request: OperationControlsUpdateRequest {
+                    deposit_paused: Some(true),
+                    queue_undelegate_paused: None,
+                    undelegate_paused: None,
+                    withdraw_paused: None,
+                    reinvest_paused: None,
+                    reconcile_paused: None,
+                    claim_airdrops_paused: None,
+                    redeem_rewards_paused: None,
+                },
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::AddValidator {
+                val_addr: Addr::unchecked("valid0002"),
+            },
+        )
+        .unwrap_err();
+
+        let res: AuditLogResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::AuditLog {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.entries,
+            vec![
+                AuditLogEntry {
+                    id: 1,
+                    sender: Addr::unchecked("creator"),
+                    block_height: env.block.height,
+                    time: env.block.time,
+                    operation: "add_validator".to_string(),
+                    changes: vec![AuditChange::new("validator", "", &val_addr)],
+                    reason: None,
+                },
+                AuditLogEntry {
+                    id: 2,
+                    sender: Addr::unchecked("creator"),
+                    block_height: env.block.height,
+                    time: env.block.time,
+                    operation: "update_operation_flags".to_string(),
+                    changes: vec![AuditChange::new("deposit_paused", false, true)],
+                    reason: None,
+                },
+            ]
+        );
+
+        /*
+           Test - 2. Pagination
+        */
+        let res: AuditLogResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::AuditLog {
+                    start_after: Some(1),
+                    limit: Some(1),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.entries.len(), 1);
+        assert_eq!(res.entries[0].id, 2);
+
+        /*
+           Test - 3. Pauses, fee splits and manager proposals are recorded too
+        */
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::PauseOperations {
+                operations: vec!["withdraw".to_string()],
+                reason: Some("incident".to_string()),
+                expires_at: None,
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateFeeSplit {
+                fee_split: vec![FeeSplitEntry {
+                    recipient: Addr::unchecked("treasury"),
+                    weight: Decimal::one(),
+                }],
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::SetManager {
+                manager: "new_manager".to_string(),
+                expires_in: None,
+            },
+        )
+        .unwrap();
+        let res: AuditLogResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env,
+                QueryMsg::AuditLog {
+                    start_after: Some(2),
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.entries
+                .iter()
+                .map(|x| (x.operation.as_str(), x.reason.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("pause_operations", Some("incident".to_string())),
+                ("update_fee_split", None),
+                ("set_manager", None),
+            ]
+        );
+        assert_eq!(
+            res.entries[0].changes,
+            vec![AuditChange::new("paused_operations", "", "withdraw")]
+        );
+        assert_eq!(
+            res.entries[1].changes,
+            vec![AuditChange::new(
+                "fee_split",
+                "[]",
+                r#"[{"recipient":"treasury","weight":"1"}]"#
+            )]
+        );
+        assert_eq!(
+            res.entries[2].changes,
+            vec![AuditChange::new("pending_manager", "", "new_manager")]
+        );
+    }
+
+    #[test]
+    fn test_recovery_operations() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+
+        instantiate_contract(&mut deps, &info, &env);
+
+        let val_addr = Addr::unchecked("valid0001");
+        VALIDATOR_META
+            .save(deps.as_mut().storage, &val_addr, &VMeta::new())
+            .unwrap();
         STATE
             .update(
                 deps.as_mut().storage,
                 |mut state| -> Result<_, ContractError> {
-                    state.validators = vec![valid1.clone(), valid2.clone(), valid3.clone()];
-                    state.current_undelegation_batch_id = 1;
+                    state.current_undelegation_batch_id = 3;
+                    state.last_reconciled_batch_id = 1;
+                    state.reconciled_funds_to_withdraw = Uint128::new(1800);
                     Ok(state)
                 },
             )
             .unwrap();
-        deps.querier
-            .update_staking("uluna", &*get_validators(), &*get_delegations());
-        deps.querier
-            .update_stader_balances(Some(Uint128::new(3000_u128)), None);
-        VALIDATOR_META
-            .save(
-                deps.as_mut().storage,
-                &valid2,
-                &VMeta {
-                    staked: Uint128::new(1000_u128),
-                    slashed: Default::default(),
-                    filled: Default::default(),
-                },
-            )
-            .unwrap();
-        VALIDATOR_META
-            .save(
-                deps.as_mut().storage,
-                &valid3,
-                &VMeta {
-                    staked: Uint128::new(1000_u128),
-                    slashed: Default::default(),
-                    filled: Default::default(),
-                },
-            )
-            .unwrap();
-        VALIDATOR_META
-            .save(
-                deps.as_mut().storage,
-                &valid1,
-                &VMeta {
-                    staked: Uint128::new(1000_u128),
-                    slashed: Default::default(),
-                    filled: Default::default(),
-                },
-            )
-            .unwrap();
         BATCH_UNDELEGATION_REGISTRY
             .save(
                 deps.as_mut().storage,
-                1,
+                2,
                 &BatchUndelegationRecord {
-                    undelegated_tokens: Uint128::new(4000_u128),
-                    create_time: Default::default(),
-                    est_release_time: None,
+                    undelegated_tokens: Uint128::new(3000_u128),
+                    create_time: env.block.time.minus_seconds(20000),
+                    est_release_time: Some(env.block.time.minus_seconds(300)),
                     reconciled: false,
-                    undelegation_er: Default::default(),
-                    undelegated_stake: Default::default(),
-                    unbonding_slashing_ratio: Default::default(),
+                    undelegation_er: Decimal::one(),
+                    undelegated_stake: Uint128::new(3000_u128),
+                    unbonding_slashing_ratio: Decimal::one(),
                 },
             )
             .unwrap();
+
+        let override_meta = ExecuteMsg::OverrideValidatorMeta {
+            val_addr: val_addr.clone(),
+            staked: Some(Uint128::new(500)),
+            slashed: None,
+            filled: None,
+            reason: "staked drifted after slashing".to_string(),
+        };
+
+        /*
+           Test - 1. Only the manager can recover, with a reason and while withdrawals are paused
+        */
         let err = execute(
             deps.as_mut(),
             env.clone(),
             mock_info("other", &[]),
-            ExecuteMsg::Undelegate {},
+            override_meta.clone(),
         )
         .unwrap_err();
-        assert!(matches!(err, ContractError::InSufficientFunds {}));
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            override_meta.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::WithdrawNotPaused {}));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateOperationFlags {
+                operation_controls_update_request: OperationControlsUpdateRequest {
+                    deposit_paused: None,
+                    queue_undelegate_paused: None,
+                    undelegate_paused: None,
+                    withdraw_paused: Some(true),
+                    reinvest_paused: None,
+                    reconcile_paused: None,
+                    claim_airdrops_paused: None,
+                    redeem_rewards_paused: None,
+                },
+            },
+        )
+        .unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::ResetReconciledFunds {
+                reconciled_funds_to_withdraw: Uint128::zero(),
+                reason: " ".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::RecoveryReasonRequired {}));
+
+        /*
+           Test - 2. Overwrite validator meta
+        */
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            override_meta,
+        )
+        .unwrap();
+        let val_meta = VALIDATOR_META
+            .load(deps.as_mut().storage, &val_addr)
+            .unwrap();
+        assert_eq!(
+            val_meta,
+            VMeta {
+                staked: Uint128::new(500),
+                slashed: Uint128::zero(),
+                filled: Uint128::zero(),
+            }
+        );
+
+        /*
+           Test - 3. Force reconcile the next batch with the funds that actually arrived
+        */
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::ForceReconcileBatch {
+                batch_id: 3,
+                received_amount: Uint128::new(1500),
+                reason: "unbonding never arrived".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidRecoveryBatch {}));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::ForceReconcileBatch {
+                batch_id: 2,
+                received_amount: Uint128::new(1500),
+                reason: "unbonding never arrived".to_string(),
+            },
+        )
+        .unwrap();
+        let batch = BATCH_UNDELEGATION_REGISTRY
+            .load(deps.as_mut().storage, 2)
+            .unwrap();
+        assert!(batch.reconciled);
+        assert_eq!(
+            batch.unbonding_slashing_ratio,
+            Decimal::from_ratio(1_u128, 2_u128)
+        );
+        let state = STATE.load(deps.as_mut().storage).unwrap();
+        assert_eq!(state.last_reconciled_batch_id, 2);
+        assert_eq!(state.reconciled_funds_to_withdraw, Uint128::new(3300));
+
+        /*
+           Test - 4. Reset reconciled funds, with every recovery logged with its reason
+        */
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::ResetReconciledFunds {
+                reconciled_funds_to_withdraw: Uint128::new(3000),
+                reason: "withdrawals over-reconciled".to_string(),
+            },
+        )
+        .unwrap();
+        let state = STATE.load(deps.as_mut().storage).unwrap();
+        assert_eq!(state.reconciled_funds_to_withdraw, Uint128::new(3000));
+
+        let res: AuditLogResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env,
+                QueryMsg::AuditLog {
+                    start_after: Some(1),
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.entries.len(), 3);
+        assert_eq!(res.entries[0].operation, "override_validator_meta");
+        assert_eq!(res.entries[1].operation, "force_reconcile_batch");
+        assert_eq!(res.entries[2].operation, "reset_reconciled_funds");
+        assert_eq!(
+            res.entries[2].reason,
+            Some("withdrawals over-reconciled".to_string())
+        );
+        assert_eq!(
+            res.entries[2].changes,
+            vec![AuditChange::new(
+                "reconciled_funds_to_withdraw",
+                3300_u128,
+                3000_u128
+            )]
+        );
     }
 
     #[test]
-    fn test_undelegate_stake_success() {
+    fn test_pending_rewards() {
         let mut deps = mock_dependencies(&[]);
         let info = mock_info("creator", &[]);
         let env = mock_env();
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        instantiate_contract(&mut deps, &info, &env);
 
-        /*
-           Test - 1. Successful run
-        */
-        CONFIG
+        deps.querier
+            .update_staking("uluna", &get_validators(), &get_delegations());
+        deps.querier
+            .update_stader_balances(Some(Uint128::new(3000_u128)), None);
+        deps.querier.update_balance(
+            Addr::unchecked("reward_contract"),
+            vec![Coin::new(100, "uluna")],
+        );
+        STATE
             .update(
                 deps.as_mut().storage,
-                |mut config| -> Result<_, ContractError> {
-                    config.undelegation_cooldown = 1000;
-                    Ok(config)
+                |mut state| -> Result<_, ContractError> {
+                    state.validators = vec![
+                        Addr::unchecked("valid0001"),
+                        Addr::unchecked("valid0002"),
+                        Addr::unchecked("valid0003"),
+                    ];
+                    state.total_staked = Uint128::new(3000_u128);
+                    Ok(state)
                 },
             )
             .unwrap();
+        // Half of the reward contract balance was not recorded for the staking contract.
+        deps.querier.update_reward_vault_balance(Uint128::new(50));
+
+        /*
+           Test - 1. Without vesting, the projection includes all reinvestable rewards
+        */
+        let res: PendingRewardsResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::PendingRewards {}).unwrap())
+                .unwrap();
+        assert_eq!(
+            res,
+            PendingRewardsResponse {
+                validators: vec![
+                    ValidatorPendingRewards {
+                        validator: Addr::unchecked("valid0001"),
+                        pending_rewards: Uint128::new(20),
+                    },
+                    ValidatorPendingRewards {
+                        validator: Addr::unchecked("valid0002"),
+                        pending_rewards: Uint128::new(40),
+                    },
+                    ValidatorPendingRewards {
+                        validator: Addr::unchecked("valid0003"),
+                        pending_rewards: Uint128::zero(),
+                    },
+                ],
+                total_pending_rewards: Uint128::new(60),
+                reward_contract_balance: Uint128::new(100),
+                reinvestable_rewards: Uint128::new(110),
+                protocol_fee: Uint128::new(1),
+                exchange_rate: Decimal::one(),
+                projected_exchange_rate: Decimal::from_ratio(3109_u128, 3000_u128),
+            }
+        );
+
+        /*
+           Test - 2. With vesting, only the vested part of earlier and projected rewards counts
+        */
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateConfig {
+                config_request: ConfigUpdateRequest {
+                    reward_vesting_period: Some(1000),
+                    ..Default::default()
+                },
+            },
+        )
+        .unwrap();
+        // 300 reinvested 500 seconds ago, half of which has vested since.
         STATE
             .update(
                 deps.as_mut().storage,
                 |mut state| -> Result<_, ContractError> {
-                    state.last_undelegation_time = env.block.time.minus_seconds(2000);
+                    state.total_staked = Uint128::new(3300_u128);
+                    state.unvested_rewards = Uint128::new(300_u128);
+                    state.rewards_vesting_start = env.block.time.minus_seconds(500);
+                    state.rewards_vesting_end = env.block.time.plus_seconds(500);
                     Ok(state)
                 },
             )
             .unwrap();
+        let res: PendingRewardsResponse =
+            from_binary(&query(deps.as_ref(), env, QueryMsg::PendingRewards {}).unwrap()).unwrap();
+        assert_eq!(res.reinvestable_rewards, Uint128::new(110));
+        assert_eq!(res.exchange_rate, Decimal::one());
+        // The 109 reinvested and the 150 not vested yet are left out.
+        assert_eq!(
+            res.projected_exchange_rate,
+            Decimal::from_ratio(3150_u128, 3000_u128)
+        );
+    }
+
+    #[test]
+    fn test_migrate() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+        instantiate_contract(&mut deps, &info, &env);
+
+        OPERATION_CONTROLS
+            .save(
+                deps.as_mut().storage,
+                &OperationControls {
+                    withdraw_paused: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        /*
+           Test - 1. Invalid migration parameters
+        */
+        let err = migrate(
+            deps.as_mut(),
+            env.clone(),
+            MigrateMsg {
+                config_timelock: Some(u64::MAX),
+                circuit_breaker_config: None,
+                reinvest_split_limit: None,
+                reward_vesting_period: None,
+                min_holding_period: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidConfigTimelock {}));
+
+        /*
+           Test - 2. Pause state is kept and new fields are set
+        */
+        let res = migrate(
+            deps.as_mut(),
+            env.clone(),
+            MigrateMsg {
+                config_timelock: Some(3600),
+                circuit_breaker_config: Some(CircuitBreakerConfig {
+                    max_exchange_rate_drop: Some(Decimal::from_ratio(1_u128, 10_u128)),
+                    max_exchange_rate_rise: None,
+                }),
+                reinvest_split_limit: None,
+                reward_vesting_period: None,
+                min_holding_period: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.events[0].attributes,
+            vec![
+                Attribute::new("from_version", "0.2.0"),
+                Attribute::new("to_version", "0.2.0"),
+            ]
+        );
+        let operation_controls = OPERATION_CONTROLS.load(deps.as_ref().storage).unwrap();
+        assert!(operation_controls.withdraw_paused);
+        assert!(!operation_controls.deposit_paused);
+        assert_eq!(CONFIG_TIMELOCK.load(deps.as_ref().storage).unwrap(), 3600);
+        assert_eq!(
+            CIRCUIT_BREAKER_CONFIG
+                .load(deps.as_ref().storage)
+                .unwrap()
+                .max_exchange_rate_drop,
+            Some(Decimal::from_ratio(1_u128, 10_u128))
+        );
+
+        /*
+           Test - 3. Contracts from before inflow tracking expect only their share of the rewards
+        */
+        let no_params = MigrateMsg {
+            config_timelock: None,
+            circuit_breaker_config: None,
+            reinvest_split_limit: None,
+            reward_vesting_period: None,
+            min_holding_period: None,
+        };
+        set_contract_version(deps.as_mut().storage, "staking", "0.1.0").unwrap();
+        INFLOW_TRACKER.remove(deps.as_mut().storage);
+        deps.querier.update_balance(
+            Addr::unchecked("reward_contract"),
+            vec![Coin::new(1000_u128, "uluna".to_string())],
+        );
+        deps.querier
+            .update_reward_vault_balance(Uint128::new(400_u128));
+        migrate(deps.as_mut(), env.clone(), no_params.clone()).unwrap();
+        let res: InflowTrackerResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), QueryMsg::InflowTracker {}).unwrap())
+                .unwrap();
+        assert_eq!(res.inflow_tracker.expected_rewards, Uint128::new(400_u128));
+        assert_eq!(res.unaccounted_reward_funds, Uint128::new(600_u128));
+        let operation_controls = OPERATION_CONTROLS.load(deps.as_ref().storage).unwrap();
+        assert!(operation_controls.withdraw_paused);
+
+        /*
+           Test - 4. Downgrades and other contracts are refused
+        */
+        set_contract_version(deps.as_mut().storage, "staking", "9.0.0").unwrap();
+        let err = migrate(deps.as_mut(), env.clone(), no_params.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::MigrationDowngrade(_, _)));
+
+        set_contract_version(deps.as_mut().storage, "reward", "0.1.0").unwrap();
+        let err = migrate(deps.as_mut(), env, no_params).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMigrationContract(name) if name == "reward"));
+    }
+
+    #[test]
+    fn test_auto_compound() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+        instantiate_contract(&mut deps, &info, &env);
+
         let valid1 = Addr::unchecked("valid0001");
         let valid2 = Addr::unchecked("valid0002");
         let valid3 = Addr::unchecked("valid0003");
+        let config = CONFIG.load(deps.as_ref().storage).unwrap();
+        deps.querier
+            .update_stader_balances(Some(Uint128::new(3000_u128)), None);
+        deps.querier
+            .update_staking("uluna", &get_validators(), &get_delegations());
         STATE
             .update(
                 deps.as_mut().storage,
                 |mut state| -> Result<_, ContractError> {
                     state.validators = vec![valid1.clone(), valid2.clone(), valid3.clone()];
-                    state.current_undelegation_batch_id = 1;
                     state.total_staked = Uint128::new(3000_u128);
+                    state.last_reinvest_time = env.block.time.minus_seconds(3);
                     Ok(state)
                 },
             )
             .unwrap();
-        deps.querier
-            .update_staking("uluna", &*get_validators(), &*get_delegations());
-        deps.querier
-            .update_stader_balances(Some(Uint128::new(3000_u128)), None);
-        VALIDATOR_META
-            .save(
-                deps.as_mut().storage,
-                &valid2,
-                &VMeta {
-                    staked: Uint128::new(1000_u128),
-                    slashed: Default::default(),
-                    filled: Default::default(),
-                },
-            )
-            .unwrap();
-        VALIDATOR_META
-            .save(
-                deps.as_mut().storage,
-                &valid3,
-                &VMeta {
-                    staked: Uint128::new(1000_u128),
-                    slashed: Default::default(),
-                    filled: Default::default(),
-                },
-            )
-            .unwrap();
-        VALIDATOR_META
-            .save(
-                deps.as_mut().storage,
-                &valid1,
-                &VMeta {
-                    staked: Uint128::new(1000_u128),
-                    slashed: Default::default(),
-                    filled: Default::default(),
-                },
-            )
-            .unwrap();
-        BATCH_UNDELEGATION_REGISTRY
-            .save(
+        for val_addr in [&valid1, &valid2, &valid3] {
+            VALIDATOR_META
+                .save(
+                    deps.as_mut().storage,
+                    val_addr,
+                    &VMeta {
+                        staked: Uint128::new(1000_u128),
+                        slashed: Default::default(),
+                        filled: Default::default(),
+                    },
+                )
+                .unwrap();
+        }
+
+        /*
+           Test - 1. Same cooldown as reinvest
+        */
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other", &[]),
+            ExecuteMsg::AutoCompound {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ReinvestInCooldown {}));
+
+        /*
+           Test - 2. Rewards are redeemed with a reply once they have been recorded
+        */
+        STATE
+            .update(
                 deps.as_mut().storage,
-                1,
-                &BatchUndelegationRecord {
-                    undelegated_tokens: Uint128::new(2000_u128),
-                    create_time: env.block.time.minus_seconds(10000),
-                    est_release_time: None,
-                    reconciled: false,
-                    undelegation_er: Default::default(),
-                    undelegated_stake: Default::default(),
-                    unbonding_slashing_ratio: Default::default(),
+                |mut state| -> Result<_, ContractError> {
+                    state.last_reinvest_time = env.block.time.minus_seconds(100);
+                    Ok(state)
                 },
             )
             .unwrap();
@@ -3725,297 +7153,505 @@ mod tests {
             deps.as_mut(),
             env.clone(),
             mock_info("other", &[]),
-            ExecuteMsg::Undelegate {},
+            ExecuteMsg::AutoCompound {},
         )
         .unwrap();
-        let config = CONFIG.load(deps.as_mut().storage).unwrap();
-        assert_eq!(res.messages.len(), 3);
-        assert!(check_equal_vec(
+        assert_eq!(
             res.messages,
             vec![
-                SubMsg::new(StakingMsg::Undelegate {
-                    validator: valid3.to_string(),
-                    amount: Coin::new(1000_u128, "uluna".to_string())
+                SubMsg::new(WasmMsg::Execute {
+                    contract_addr: config.reward_contract.to_string(),
+                    msg: to_binary(&RewardExecuteMsg::BeginRewardsWithdrawal {}).unwrap(),
+                    funds: vec![],
+                }),
+                SubMsg::new(DistributionMsg::WithdrawDelegatorReward {
+                    validator: valid1.to_string(),
                 }),
-                SubMsg::new(StakingMsg::Undelegate {
+                SubMsg::new(DistributionMsg::WithdrawDelegatorReward {
                     validator: valid2.to_string(),
-                    amount: Coin::new(1000_u128, "uluna".to_string())
                 }),
-                SubMsg::new(WasmMsg::Execute {
-                    contract_addr: config.cw20_token_contract.to_string(),
-                    msg: to_binary(&cw20::Cw20ExecuteMsg::Burn {
-                        amount: Uint128::new(2000_u128)
-                    })
-                    .unwrap(),
-                    funds: vec![]
-                })
+                SubMsg::new(DistributionMsg::WithdrawDelegatorReward {
+                    validator: valid3.to_string(),
+                }),
+                SubMsg::reply_on_success(
+                    WasmMsg::Execute {
+                        contract_addr: config.reward_contract.to_string(),
+                        msg: to_binary(&RewardExecuteMsg::RecordRewards {}).unwrap(),
+                        funds: vec![],
+                    },
+                    1,
+                ),
             ]
-        ));
-        let val3_meta = VALIDATOR_META.load(deps.as_mut().storage, &valid3).unwrap();
-        let val2_meta = VALIDATOR_META.load(deps.as_mut().storage, &valid2).unwrap();
-        assert_eq!(
-            val3_meta,
-            VMeta {
-                staked: Uint128::zero(),
-                slashed: Default::default(),
-                filled: Default::default()
-            }
         );
         assert_eq!(
-            val2_meta,
-            VMeta {
-                staked: Uint128::zero(),
-                slashed: Default::default(),
-                filled: Default::default()
-            }
+            INFLOW_TRACKER
+                .load(deps.as_ref().storage)
+                .unwrap()
+                .expected_rewards,
+            Uint128::new(60)
         );
-        let config = CONFIG.load(deps.as_mut().storage).unwrap();
-        let undel_batch = BATCH_UNDELEGATION_REGISTRY
-            .load(deps.as_mut().storage, 1)
-            .unwrap();
+        let state = STATE.load(deps.as_ref().storage).unwrap();
+        assert_eq!(state.last_reinvest_time, env.block.time.minus_seconds(100));
+
+        /*
+           Test - 3. Redeemed rewards are reinvested once they have landed
+        */
+        let err = reply(
+            deps.as_mut(),
+            env.clone(),
+            Reply {
+                id: 2,
+                result: SubMsgResult::Ok(SubMsgResponse {
+                    events: vec![],
+                    data: None,
+                }),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::UnknownReplyId(2)));
+
+        deps.querier.update_balance(
+            config.reward_contract.clone(),
+            vec![Coin::new(60_u128, "uluna".to_string())],
+        );
+        deps.querier
+            .update_reward_vault_balance(Uint128::new(60_u128));
+        let res = reply(
+            deps.as_mut(),
+            env.clone(),
+            Reply {
+                id: 1,
+                result: SubMsgResult::Ok(SubMsgResponse {
+                    events: vec![],
+                    data: None,
+                }),
+            },
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 4);
         assert_eq!(
-            undel_batch,
-            BatchUndelegationRecord {
-                undelegated_tokens: Uint128::new(2000_u128),
-                create_time: env.block.time.minus_seconds(10000),
-                est_release_time: Some(env.block.time.plus_seconds(config.unbonding_period)),
-                reconciled: false,
-                undelegation_er: Decimal::one(),
-                undelegated_stake: Uint128::new(2000_u128),
-                unbonding_slashing_ratio: Default::default()
-            }
+            res.messages[0],
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: config.reward_contract.to_string(),
+                msg: to_binary(&RewardExecuteMsg::Transfer {
+                    reward_amount: Uint128::new(60),
+                    reward_withdraw_contract: env.contract.address.clone(),
+                    protocol_fee_amount: Uint128::zero(),
+                    protocol_fee_contract: config.protocol_fee_contract.clone(),
+                    protocol_fee_split: vec![],
+                })
+                .unwrap(),
+                funds: vec![],
+            })
         );
-        let state = STATE.load(deps.as_mut().storage).unwrap();
-        assert_eq!(state.total_staked, Uint128::new(1000_u128));
-        assert_eq!(state.last_undelegation_time, env.block.time);
-        let new_undel_batch = BATCH_UNDELEGATION_REGISTRY
-            .may_load(deps.as_mut().storage, 2)
-            .unwrap();
-        assert_ne!(new_undel_batch, None);
+        assert!(res.events.iter().any(|x| x.ty.eq("lunax-reinvest")));
+        let state = STATE.load(deps.as_ref().storage).unwrap();
+        assert_eq!(state.last_reinvest_time, env.block.time);
     }
 
     #[test]
-    fn test_reconcile_funds() {
+    fn test_reinvest_split() {
         let mut deps = mock_dependencies(&[]);
         let info = mock_info("creator", &[]);
         let env = mock_env();
+        instantiate_contract(&mut deps, &info, &env);
 
-        let _res = instantiate_contract(&mut deps, &info, &env);
+        let valid1 = Addr::unchecked("valid0001");
+        let valid2 = Addr::unchecked("valid0002");
+        let valid3 = Addr::unchecked("valid0003");
+        let validators = vec![valid1.clone(), valid2.clone(), valid3.clone()];
+        let full_delegations: Vec<FullDelegation> = get_delegations()
+            .into_iter()
+            .zip([1000_u128, 2000, 3000])
+            .map(|(mut full_delegation, amount)| {
+                full_delegation.amount = Coin::new(amount, "uluna");
+                full_delegation
+            })
+            .collect();
+        deps.querier
+            .update_staking("uluna", &get_validators(), &full_delegations);
+        let delegations = convert_to_delegation(full_delegations);
 
-        OPERATION_CONTROLS
+        /*
+           Test - 1. The least staked validators are filled first, up to the split limit
+        */
+        let split = |amount: u128, split_limit: u64| {
+            get_reinvest_split(
+                deps.as_ref().querier,
+                validators.clone(),
+                delegations.as_slice(),
+                Uint128::new(amount),
+                split_limit,
+            )
+            .unwrap()
+        };
+        assert_eq!(split(1500, 1), vec![(valid1.clone(), Uint128::new(1500))]);
+        assert_eq!(
+            split(1500, 3),
+            vec![
+                (valid1.clone(), Uint128::new(1250)),
+                (valid2.clone(), Uint128::new(250))
+            ]
+        );
+        assert_eq!(
+            split(5000, 3),
+            vec![
+                (valid1.clone(), Uint128::new(2668)),
+                (valid2.clone(), Uint128::new(1666)),
+                (valid3.clone(), Uint128::new(666))
+            ]
+        );
+        assert_eq!(
+            split(5000, 2),
+            vec![
+                (valid1.clone(), Uint128::new(3000)),
+                (valid2.clone(), Uint128::new(2000))
+            ]
+        );
+        assert_eq!(split(0, 3), vec![]);
+
+        /*
+           Test - 2. Split limit is configurable
+        */
+        let config_request = |reinvest_split_limit| ConfigUpdateRequest {
+            reinvest_split_limit: Some(reinvest_split_limit),
+            ..Default::default()
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateConfig {
+                config_request: config_request(11),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidReinvestSplitLimit {}));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateConfig {
+                config_request: config_request(3),
+            },
+        )
+        .unwrap();
+        let res: ReinvestSplitLimitResponse =
+            from_binary(&query(deps.as_ref(), env, QueryMsg::ReinvestSplitLimit {}).unwrap())
+                .unwrap();
+        assert_eq!(res.reinvest_split_limit, 3);
+    }
+
+    #[test]
+    fn test_reward_vesting() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+        instantiate_contract(&mut deps, &info, &env);
+
+        let valid1 = Addr::unchecked("valid0001");
+        let valid2 = Addr::unchecked("valid0002");
+        let valid3 = Addr::unchecked("valid0003");
+        let config = CONFIG.load(deps.as_ref().storage).unwrap();
+        deps.querier.update_balance(
+            config.reward_contract.clone(),
+            vec![Coin::new(1000_u128, "uluna".to_string())],
+        );
+        deps.querier
+            .update_stader_balances(Some(Uint128::new(3000_u128)), None);
+        deps.querier
+            .update_staking("uluna", &get_validators(), &get_delegations());
+        STATE
+            .update(
+                deps.as_mut().storage,
+                |mut state| -> Result<_, ContractError> {
+                    state.validators = vec![valid1.clone(), valid2.clone(), valid3.clone()];
+                    state.total_staked = Uint128::new(3000_u128);
+                    Ok(state)
+                },
+            )
+            .unwrap();
+        for val_addr in [&valid1, &valid2, &valid3] {
+            VALIDATOR_META
+                .save(
+                    deps.as_mut().storage,
+                    val_addr,
+                    &VMeta {
+                        staked: Uint128::new(1000_u128),
+                        slashed: Default::default(),
+                        filled: Default::default(),
+                    },
+                )
+                .unwrap();
+        }
+        INFLOW_TRACKER
             .save(
                 deps.as_mut().storage,
-                &OperationControls {
-                    deposit_paused: false,
-                    queue_undelegate_paused: false,
-                    undelegate_paused: false,
-                    withdraw_paused: false,
-                    reinvest_paused: false,
-                    reconcile_paused: true,
-                    claim_airdrops_paused: false,
-                    redeem_rewards_paused: false,
+                &InflowTracker {
+                    expected_rewards: Uint128::new(1000_u128),
+                    ..Default::default()
                 },
             )
             .unwrap();
+        deps.querier
+            .update_reward_vault_balance(Uint128::new(1000_u128));
+
+        /*
+           Test - 1. Vesting period is capped
+        */
+        let config_request = |reward_vesting_period| ConfigUpdateRequest {
+            reward_vesting_period: Some(reward_vesting_period),
+            ..Default::default()
+        };
         let err = execute(
             deps.as_mut(),
             env.clone(),
-            mock_info("other", &[]),
-            ExecuteMsg::ReconcileFunds {},
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateConfig {
+                config_request: config_request(7 * 24 * 3600 + 1),
+            },
         )
         .unwrap_err();
-        assert!(matches!(err, ContractError::OperationPaused(String { .. })));
+        assert!(matches!(err, ContractError::InvalidRewardVestingPeriod {}));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateConfig {
+                config_request: config_request(1000),
+            },
+        )
+        .unwrap();
 
-        OPERATION_CONTROLS
-            .save(
+        /*
+           Test - 2. Reinvested rewards are staked at once but do not move the exchange rate yet
+        */
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::Reinvest {},
+        )
+        .unwrap();
+        let state = STATE.load(deps.as_ref().storage).unwrap();
+        assert_eq!(state.total_staked, Uint128::new(3990));
+        assert_eq!(state.unvested_rewards, Uint128::new(990));
+        assert_eq!(state.rewards_vesting_end, env.block.time.plus_seconds(1000));
+        assert_eq!(state.exchange_rate, Decimal::one());
+
+        /*
+           Test - 3. Rewards vest linearly over the vesting period
+        */
+        let query_vesting = |seconds: u64| -> RewardVestingResponse {
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(seconds);
+            from_binary(&query(deps.as_ref(), env, QueryMsg::RewardVesting {}).unwrap()).unwrap()
+        };
+        assert_eq!(
+            query_vesting(500),
+            RewardVestingResponse {
+                reward_vesting_period: 1000,
+                unvested_rewards: Uint128::new(495),
+                rewards_vesting_end: env.block.time.plus_seconds(1000),
+                exchange_rate: Decimal::from_ratio(3495_u128, 3000_u128),
+            }
+        );
+        let res = query_vesting(1500);
+        assert_eq!(res.unvested_rewards, Uint128::zero());
+        assert_eq!(res.exchange_rate, Decimal::from_ratio(3990_u128, 3000_u128));
+    }
+
+    #[test]
+    fn test_min_holding_period() {
+        let mut deps = mock_dependencies(&[]);
+        let info = mock_info("creator", &[]);
+        let env = mock_env();
+        instantiate_contract(&mut deps, &info, &env);
+
+        CONFIG
+            .update(
                 deps.as_mut().storage,
-                &OperationControls {
-                    deposit_paused: false,
-                    queue_undelegate_paused: false,
-                    undelegate_paused: false,
-                    withdraw_paused: false,
-                    reinvest_paused: false,
-                    reconcile_paused: false,
-                    claim_airdrops_paused: false,
-                    redeem_rewards_paused: false,
+                |mut config| -> Result<_, ContractError> {
+                    config.cw20_token_contract = Addr::unchecked("cw20_contract");
+                    Ok(config)
                 },
             )
             .unwrap();
-
-        /*
-           Test - 1. No undelegation slashing
-        */
         STATE
             .update(
                 deps.as_mut().storage,
                 |mut state| -> Result<_, ContractError> {
-                    state.current_undelegation_batch_id = 3;
-                    state.last_reconciled_batch_id = 1;
-                    state.reconciled_funds_to_withdraw = Uint128::new(1800);
+                    state.validators = vec![Addr::unchecked("valid0001")];
+                    state.total_staked = Uint128::new(3000_u128);
+                    state.current_undelegation_batch_id = 1;
                     Ok(state)
                 },
             )
             .unwrap();
-        BATCH_UNDELEGATION_REGISTRY
-            .save(
-                deps.as_mut().storage,
-                2,
-                &BatchUndelegationRecord {
-                    undelegated_tokens: Uint128::new(3000_u128),
-                    create_time: env.block.time.minus_seconds(20000),
-                    est_release_time: Some(env.block.time.minus_seconds(300)),
-                    reconciled: false,
-                    undelegation_er: Decimal::one(),
-                    undelegated_stake: Uint128::new(3000_u128),
-                    unbonding_slashing_ratio: Decimal::one(),
-                },
+        deps.querier
+            .update_staking("uluna", &get_validators(), &get_delegations());
+        deps.querier.update_stader_balances(
+            Some(Uint128::new(3000_u128)),
+            Some(HashMap::from([(
+                Addr::unchecked("user1"),
+                Uint128::new(100),
+            )])),
+        );
+        let user1 = Addr::unchecked("user1");
+        record_user_deposit(
+            deps.as_mut().storage,
+            &user1,
+            Uint128::new(100),
+            env.block.time.minus_seconds(1000),
+        )
+        .unwrap();
+        let queue_undelegate = |deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+                                sender: &str| {
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("cw20_contract", &[]),
+                ExecuteMsg::Receive(Cw20ReceiveMsg {
+                    sender: sender.to_string(),
+                    amount: Uint128::new(10_u128),
+                    msg: to_binary(&Cw20HookMsg::QueueUndelegate {}).unwrap(),
+                }),
             )
-            .unwrap();
-        BATCH_UNDELEGATION_REGISTRY
-            .save(
-                deps.as_mut().storage,
-                3,
-                &BatchUndelegationRecord {
-                    undelegated_tokens: Uint128::new(2000_u128),
-                    create_time: env.block.time.minus_seconds(10000),
-                    est_release_time: Some(env.block.time.minus_seconds(100)),
-                    reconciled: false,
-                    undelegation_er: Decimal::one(),
-                    undelegated_stake: Uint128::new(2000_u128),
-                    unbonding_slashing_ratio: Decimal::one(),
-                },
+        };
+        let query_user_info = |deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier>| {
+            let res: UserInfoResponse = from_binary(
+                &query(
+                    deps.as_ref(),
+                    env.clone(),
+                    QueryMsg::GetUserInfo {
+                        user_addr: "user1".to_string(),
+                    },
+                )
+                .unwrap(),
             )
             .unwrap();
-        deps.querier.update_balance(
-            env.contract.address.clone(),
-            vec![Coin::new(7000_u128, "uluna".to_string())],
+            res.user_info
+        };
+
+        /*
+           Test - 1. No holding period by default
+        */
+        let user_info = query_user_info(&deps);
+        assert_eq!(user_info.holding_period, 1000);
+        assert_eq!(
+            user_info.last_deposit_time,
+            Some(env.block.time.minus_seconds(1000))
         );
-        let _res = execute(
+        assert_eq!(user_info.holding_period_end, None);
+        queue_undelegate(&mut deps, "user1").unwrap();
+
+        /*
+           Test - 2. Holding period is capped
+        */
+        let config_request = |min_holding_period| ConfigUpdateRequest {
+            min_holding_period: Some(min_holding_period),
+            ..Default::default()
+        };
+        let err = execute(
             deps.as_mut(),
             env.clone(),
-            mock_info("other", &[]),
-            ExecuteMsg::ReconcileFunds {},
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateConfig {
+                config_request: config_request(7 * 24 * 3600 + 1),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMinHoldingPeriod {}));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::UpdateConfig {
+                config_request: config_request(3600),
+            },
         )
         .unwrap();
-        let state = STATE.load(deps.as_mut().storage).unwrap();
-        assert_eq!(state.reconciled_funds_to_withdraw, Uint128::new(6800));
-        let batch_2 = BATCH_UNDELEGATION_REGISTRY
-            .load(deps.as_mut().storage, 2)
-            .unwrap();
-        let batch_3 = BATCH_UNDELEGATION_REGISTRY
-            .load(deps.as_mut().storage, 3)
-            .unwrap();
+
+        /*
+           Test - 3. Undelegation is blocked until the holding period of the last deposit ends
+        */
+        let holding_period_end = env.block.time.plus_seconds(2600);
+        let err = queue_undelegate(&mut deps, "user1").unwrap_err();
+        assert!(matches!(err, ContractError::InHoldingPeriod(x) if x == holding_period_end));
         assert_eq!(
-            batch_2,
-            BatchUndelegationRecord {
-                undelegated_tokens: Uint128::new(3000_u128),
-                create_time: env.block.time.minus_seconds(20000),
-                est_release_time: Some(env.block.time.minus_seconds(300)),
-                reconciled: true,
-                undelegation_er: Decimal::one(),
-                undelegated_stake: Uint128::new(3000_u128),
-                unbonding_slashing_ratio: Decimal::one()
-            }
+            query_user_info(&deps).holding_period_end,
+            Some(holding_period_end)
         );
+
+        // Addresses without deposits are not restricted.
+        queue_undelegate(&mut deps, "user2").unwrap();
+
+        // The last deposit counts, however small, even though the average barely moves.
+        record_user_deposit(
+            deps.as_mut().storage,
+            &user1,
+            Uint128::new(100),
+            env.block.time.minus_seconds(4000),
+        )
+        .unwrap();
+        queue_undelegate(&mut deps, "user1").unwrap();
+        record_user_deposit(
+            deps.as_mut().storage,
+            &user1,
+            Uint128::new(1),
+            env.block.time,
+        )
+        .unwrap();
+        let user_info = query_user_info(&deps);
+        assert!(user_info.holding_period > 2000);
         assert_eq!(
-            batch_3,
-            BatchUndelegationRecord {
-                undelegated_tokens: Uint128::new(2000_u128),
-                create_time: env.block.time.minus_seconds(10000),
-                est_release_time: Some(env.block.time.minus_seconds(100)),
-                reconciled: true,
-                undelegation_er: Decimal::one(),
-                undelegated_stake: Uint128::new(2000_u128),
-                unbonding_slashing_ratio: Decimal::one()
-            }
+            user_info.holding_period_end,
+            Some(env.block.time.plus_seconds(3600))
         );
+        let err = queue_undelegate(&mut deps, "user1").unwrap_err();
+        assert!(matches!(err, ContractError::InHoldingPeriod(_)));
 
         /*
-           Test - 2. Some undelegation slashing
+           Test - 4. Tokens without a deposit record are past the holding window
         */
-        STATE
-            .update(
-                deps.as_mut().storage,
-                |mut state| -> Result<_, ContractError> {
-                    state.current_undelegation_batch_id = 3;
-                    state.last_reconciled_batch_id = 1;
-                    state.reconciled_funds_to_withdraw = Uint128::new(1800);
-                    Ok(state)
-                },
-            )
-            .unwrap();
-        BATCH_UNDELEGATION_REGISTRY
-            .save(
-                deps.as_mut().storage,
-                2,
-                &BatchUndelegationRecord {
-                    undelegated_tokens: Uint128::new(3000_u128),
-                    create_time: env.block.time.minus_seconds(20000),
-                    est_release_time: Some(env.block.time.minus_seconds(300)),
-                    reconciled: false,
-                    undelegation_er: Decimal::one(),
-                    undelegated_stake: Uint128::new(3000_u128),
-                    unbonding_slashing_ratio: Default::default(),
-                },
-            )
-            .unwrap();
+        let user3 = Addr::unchecked("user3");
+        record_user_deposit(
+            deps.as_mut().storage,
+            &user3,
+            Uint128::new(600_u128),
+            env.block.time.minus_seconds(4000),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("cw20_contract", &[]),
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: user3.to_string(),
+                amount: Uint128::new(1000_u128),
+                msg: to_binary(&Cw20HookMsg::QueueUndelegate {}).unwrap(),
+            }),
+        )
+        .unwrap();
         BATCH_UNDELEGATION_REGISTRY
             .save(
                 deps.as_mut().storage,
-                3,
+                1,
                 &BatchUndelegationRecord {
-                    undelegated_tokens: Uint128::new(2000_u128),
-                    create_time: env.block.time.minus_seconds(10000),
-                    est_release_time: Some(env.block.time.minus_seconds(100)),
-                    reconciled: false,
+                    undelegated_tokens: Uint128::new(1000_u128),
+                    create_time: env.block.time,
+                    est_release_time: None,
+                    reconciled: true,
                     undelegation_er: Decimal::one(),
-                    undelegated_stake: Uint128::new(2000_u128),
-                    unbonding_slashing_ratio: Default::default(),
+                    undelegated_stake: Uint128::new(1000_u128),
+                    unbonding_slashing_ratio: Decimal::one(),
                 },
             )
             .unwrap();
-        deps.querier.update_balance(
-            env.contract.address.clone(),
-            vec![Coin::new(6000_u128, "uluna".to_string())],
-        );
-        let _res = execute(
-            deps.as_mut(),
-            env.clone(),
-            mock_info("other", &[]),
-            ExecuteMsg::ReconcileFunds {},
-        )
-        .unwrap();
-        let state = STATE.load(deps.as_mut().storage).unwrap();
-        assert_eq!(state.reconciled_funds_to_withdraw, Uint128::new(6000));
-        let batch_2 = BATCH_UNDELEGATION_REGISTRY
-            .load(deps.as_mut().storage, 2)
-            .unwrap();
-        let batch_3 = BATCH_UNDELEGATION_REGISTRY
-            .load(deps.as_mut().storage, 3)
-            .unwrap();
-        assert_eq!(
-            batch_2,
-            BatchUndelegationRecord {
-                undelegated_tokens: Uint128::new(3000_u128),
-                create_time: env.block.time.minus_seconds(20000),
-                est_release_time: Some(env.block.time.minus_seconds(300)),
-                reconciled: true,
-                undelegation_er: Decimal::one(),
-                undelegated_stake: Uint128::new(3000_u128),
-                unbonding_slashing_ratio: Decimal::from_ratio(42_u128, 50_u128)
-            }
-        );
-        assert_eq!(
-            batch_3,
-            BatchUndelegationRecord {
-                undelegated_tokens: Uint128::new(2000_u128),
-                create_time: env.block.time.minus_seconds(10000),
-                est_release_time: Some(env.block.time.minus_seconds(100)),
-                reconciled: true,
-                undelegation_er: Decimal::one(),
-                undelegated_stake: Uint128::new(2000_u128),
-                unbonding_slashing_ratio: Decimal::from_ratio(42_u128, 50_u128)
-            }
-        );
+        let res = compute_withdrawable_funds(deps.as_ref().storage, 1, &user3).unwrap();
+        // The regular 1% on both the 600 deposited and the 400 moved in.
+        assert_eq!(res.protocol_fee, Uint128::new(10_u128));
+        assert_eq!(res.user_withdrawal_amount, Uint128::new(990_u128));
     }
 }
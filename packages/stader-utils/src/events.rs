@@ -0,0 +1,67 @@
+use cosmwasm_std::{Addr, Decimal, Event, Uint128};
+
+// wasmd prefixes custom events with `wasm-`, so these are indexed as `wasm-lunax-<action>`.
+pub const EVENT_PREFIX: &str = "lunax";
+
+pub fn lunax_event(action: &str) -> Event {
+    Event::new(format!("{}-{}", EVENT_PREFIX, action))
+}
+
+// Attribute helpers so every contract encodes the same fields the same way. Amounts are in the
+// smallest denomination and exchange rates are decimal strings.
+pub trait LunaxEventExt {
+    fn add_amount(self, key: &str, amount: Uint128) -> Self;
+    fn add_exchange_rate(self, exchange_rate: Decimal) -> Self;
+    fn add_batch_id(self, batch_id: u64) -> Self;
+    fn add_user(self, user: &Addr) -> Self;
+    fn add_validator(self, validator: &Addr) -> Self;
+}
+
+impl LunaxEventExt for Event {
+    fn add_amount(self, key: &str, amount: Uint128) -> Self {
+        self.add_attribute(key, amount.to_string())
+    }
+
+    fn add_exchange_rate(self, exchange_rate: Decimal) -> Self {
+        self.add_attribute("exchange_rate", exchange_rate.to_string())
+    }
+
+    fn add_batch_id(self, batch_id: u64) -> Self {
+        self.add_attribute("batch_id", batch_id.to_string())
+    }
+
+    fn add_user(self, user: &Addr) -> Self {
+        self.add_attribute("user", user.as_str())
+    }
+
+    fn add_validator(self, validator: &Addr) -> Self {
+        self.add_attribute("validator", validator.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::events::{lunax_event, LunaxEventExt};
+    use cosmwasm_std::{attr, Addr, Decimal, Uint128};
+
+    #[test]
+    fn test_lunax_event() {
+        let event = lunax_event("deposit")
+            .add_user(&Addr::unchecked("user1"))
+            .add_amount("amount", Uint128::new(1000))
+            .add_exchange_rate(Decimal::from_ratio(1_u128, 2_u128))
+            .add_batch_id(3)
+            .add_validator(&Addr::unchecked("valid0001"));
+        assert_eq!(event.ty, "lunax-deposit");
+        assert_eq!(
+            event.attributes,
+            vec![
+                attr("user", "user1"),
+                attr("amount", "1000"),
+                attr("exchange_rate", "0.5"),
+                attr("batch_id", "3"),
+                attr("validator", "valid0001"),
+            ]
+        );
+    }
+}
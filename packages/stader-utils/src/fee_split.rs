@@ -0,0 +1,143 @@
+use crate::helpers::send_funds_msg;
+use cosmwasm_std::{Addr, BankMsg, Coin, Decimal, Uint128};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeeSplitEntry {
+    pub recipient: Addr,
+    pub weight: Decimal,
+}
+
+// An empty table is valid and means the whole fee goes to the default recipient.
+pub fn is_valid_fee_split(fee_split: &[FeeSplitEntry]) -> bool {
+    if fee_split.is_empty() {
+        return true;
+    }
+
+    let mut total_weight = Decimal::zero();
+    for (i, entry) in fee_split.iter().enumerate() {
+        if entry.weight.is_zero()
+            || fee_split[..i]
+                .iter()
+                .any(|x| x.recipient.eq(&entry.recipient))
+        {
+            return false;
+        }
+        total_weight += entry.weight;
+    }
+    total_weight.eq(&Decimal::one())
+}
+
+// Each share is rounded down and the remainder goes to the first entry, so the shares always add
+// up to the full amount.
+pub fn split_fee(
+    fee_split: &[FeeSplitEntry],
+    default_recipient: &Addr,
+    amount: Uint128,
+) -> Vec<(Addr, Uint128)> {
+    if fee_split.is_empty() {
+        return vec![(default_recipient.clone(), amount)];
+    }
+
+    let mut shares: Vec<(Addr, Uint128)> = fee_split
+        .iter()
+        .map(|entry| (entry.recipient.clone(), amount * entry.weight))
+        .collect();
+    let distributed: Uint128 = shares.iter().map(|(_, share)| *share).sum();
+    shares[0].1 = shares[0].1.checked_add(amount - distributed).unwrap();
+    shares
+}
+
+pub fn fee_split_msgs(
+    fee_split: &[FeeSplitEntry],
+    default_recipient: &Addr,
+    amount: Uint128,
+    denom: &str,
+) -> Vec<BankMsg> {
+    split_fee(fee_split, default_recipient, amount)
+        .into_iter()
+        .filter(|(_, share)| !share.is_zero())
+        .map(|(recipient, share)| send_funds_msg(&recipient, &[Coin::new(share.u128(), denom)]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fee_split::{fee_split_msgs, is_valid_fee_split, split_fee, FeeSplitEntry};
+    use cosmwasm_std::{Addr, BankMsg, Coin, Decimal, Uint128};
+
+    fn get_fee_split() -> Vec<FeeSplitEntry> {
+        vec![
+            FeeSplitEntry {
+                recipient: Addr::unchecked("treasury"),
+                weight: Decimal::from_ratio(1_u128, 3_u128),
+            },
+            FeeSplitEntry {
+                recipient: Addr::unchecked("insurance"),
+                weight: Decimal::from_ratio(1_u128, 3_u128),
+            },
+            FeeSplitEntry {
+                recipient: Addr::unchecked("validator_program"),
+                weight: Decimal::one()
+                    - Decimal::from_ratio(1_u128, 3_u128)
+                    - Decimal::from_ratio(1_u128, 3_u128),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_is_valid_fee_split() {
+        assert!(is_valid_fee_split(&[]));
+        assert!(is_valid_fee_split(&get_fee_split()));
+
+        let mut fee_split = get_fee_split();
+        fee_split[2].weight = Decimal::from_ratio(1_u128, 3_u128);
+        assert!(!is_valid_fee_split(&fee_split));
+
+        let mut fee_split = get_fee_split();
+        fee_split[1].recipient = Addr::unchecked("treasury");
+        assert!(!is_valid_fee_split(&fee_split));
+
+        let fee_split = vec![
+            FeeSplitEntry {
+                recipient: Addr::unchecked("treasury"),
+                weight: Decimal::one(),
+            },
+            FeeSplitEntry {
+                recipient: Addr::unchecked("insurance"),
+                weight: Decimal::zero(),
+            },
+        ];
+        assert!(!is_valid_fee_split(&fee_split));
+    }
+
+    #[test]
+    fn test_split_fee() {
+        let default_recipient = Addr::unchecked("protocol_fee_contract");
+        assert_eq!(
+            split_fee(&[], &default_recipient, Uint128::new(100)),
+            vec![(default_recipient.clone(), Uint128::new(100))]
+        );
+        assert_eq!(
+            split_fee(&get_fee_split(), &default_recipient, Uint128::new(100)),
+            vec![
+                (Addr::unchecked("treasury"), Uint128::new(34)),
+                (Addr::unchecked("insurance"), Uint128::new(33)),
+                (Addr::unchecked("validator_program"), Uint128::new(33)),
+            ]
+        );
+        assert_eq!(
+            fee_split_msgs(
+                &get_fee_split(),
+                &default_recipient,
+                Uint128::new(1),
+                "uluna"
+            ),
+            vec![BankMsg::Send {
+                to_address: "treasury".to_string(),
+                amount: vec![Coin::new(1, "uluna")]
+            }]
+        );
+    }
+}
@@ -1,2 +1,7 @@
 pub mod coin_utils;
+pub mod events;
+pub mod fee_split;
 pub mod helpers;
+pub mod migration;
+pub mod ownership;
+pub mod sweep;
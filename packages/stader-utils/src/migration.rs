@@ -0,0 +1,155 @@
+use cosmwasm_std::{StdError, Storage};
+use cw2::{set_contract_version, CONTRACT};
+use std::fmt;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum MigrationError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Cannot migrate from contract '{0}'")]
+    InvalidContractName(String),
+
+    #[error("Cannot migrate from version {0} to older version {1}")]
+    Downgrade(String, String),
+
+    #[error("Invalid contract version '{0}'")]
+    InvalidVersion(String),
+
+    #[error("No contract version stored for '{0}'")]
+    MissingContractVersion(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl Version {
+    // Pre-release and build suffixes are ignored.
+    pub fn parse(version: &str) -> Result<Self, MigrationError> {
+        let invalid = || MigrationError::InvalidVersion(version.to_string());
+        let core = version.split(['-', '+']).next().unwrap_or_default();
+        let parts = core
+            .split('.')
+            .map(|x| x.parse::<u64>().map_err(|_| invalid()))
+            .collect::<Result<Vec<u64>, MigrationError>>()?;
+        match parts[..] {
+            [major, minor, patch] => Ok(Version {
+                major,
+                minor,
+                patch,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+// Checks the stored cw2 info and stores the new version. Returns the version being migrated from.
+// Every deployed version stores cw2 info on instantiate, so a missing entry means the wrong contract.
+pub fn start_migration(
+    storage: &mut dyn Storage,
+    contract_name: &str,
+    contract_version: &str,
+) -> Result<Version, MigrationError> {
+    let new_version = Version::parse(contract_version)?;
+    let stored = CONTRACT
+        .may_load(storage)?
+        .ok_or_else(|| MigrationError::MissingContractVersion(contract_name.to_string()))?;
+    if stored.contract.ne(contract_name) {
+        return Err(MigrationError::InvalidContractName(stored.contract));
+    }
+    let from_version = Version::parse(stored.version.as_str())?;
+    if from_version > new_version {
+        return Err(MigrationError::Downgrade(
+            from_version.to_string(),
+            new_version.to_string(),
+        ));
+    }
+
+    set_contract_version(storage, contract_name, contract_version)?;
+    Ok(from_version)
+}
+
+// A storage transform runs when migrating from last_version_without, the last released version
+// which does not have the new state, or anything older. Versions which already have it skip it.
+pub fn needs_migration(
+    from_version: &Version,
+    last_version_without: &str,
+) -> Result<bool, MigrationError> {
+    Ok(from_version.le(&Version::parse(last_version_without)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::migration::{needs_migration, start_migration, MigrationError, Version};
+    use cosmwasm_std::testing::mock_dependencies;
+    use cw2::{get_contract_version, set_contract_version};
+
+    #[test]
+    fn test_version() {
+        assert_eq!(
+            Version::parse("1.2.3-beta.1").unwrap(),
+            Version {
+                major: 1,
+                minor: 2,
+                patch: 3
+            }
+        );
+        assert!(Version::parse("0.10.0").unwrap() > Version::parse("0.9.1").unwrap());
+        assert_eq!(
+            Version::parse("1.2").unwrap_err(),
+            MigrationError::InvalidVersion("1.2".to_string())
+        );
+        assert_eq!(
+            Version::parse("v1.2.3").unwrap_err(),
+            MigrationError::InvalidVersion("v1.2.3".to_string())
+        );
+    }
+
+    #[test]
+    fn test_start_migration() {
+        let mut deps = mock_dependencies();
+
+        let err = start_migration(&mut deps.storage, "staking", "0.2.0").unwrap_err();
+        assert_eq!(
+            err,
+            MigrationError::MissingContractVersion("staking".to_string())
+        );
+
+        set_contract_version(&mut deps.storage, "staking", "0.1.0").unwrap();
+        let from_version = start_migration(&mut deps.storage, "staking", "0.2.0").unwrap();
+        assert_eq!(from_version, Version::parse("0.1.0").unwrap());
+        assert!(needs_migration(&from_version, "0.1.0").unwrap());
+        assert_eq!(
+            get_contract_version(&deps.storage).unwrap().version,
+            "0.2.0".to_string()
+        );
+
+        let from_version = start_migration(&mut deps.storage, "staking", "0.2.0").unwrap();
+        assert!(!needs_migration(&from_version, "0.1.0").unwrap());
+        assert!(needs_migration(&from_version, "0.2.0").unwrap());
+
+        let err = start_migration(&mut deps.storage, "staking", "0.1.9").unwrap_err();
+        assert_eq!(
+            err,
+            MigrationError::Downgrade("0.2.0".to_string(), "0.1.9".to_string())
+        );
+
+        set_contract_version(&mut deps.storage, "reward", "0.1.0").unwrap();
+        let err = start_migration(&mut deps.storage, "staking", "0.2.0").unwrap_err();
+        assert_eq!(
+            err,
+            MigrationError::InvalidContractName("reward".to_string())
+        );
+    }
+}
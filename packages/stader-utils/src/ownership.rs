@@ -0,0 +1,298 @@
+use crate::events::lunax_event;
+use cosmwasm_std::{Addr, Api, Env, Event, StdError, StdResult, Storage};
+use cw_storage_plus::Item;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+// Pending proposals expire after a week unless the proposer asks otherwise.
+pub const DEFAULT_OWNERSHIP_PROPOSAL_EXPIRY: u64 = 7 * 24 * 60 * 60;
+
+// this is a tmp store to store the intermediate values of manager updates.
+// manager updates are 2 phase, we set it and then accept it. This is done to
+// add a greater assurance of the update.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TmpManagerStore {
+    pub manager: String,
+    #[serde(default)]
+    pub expires_at: Option<u64>, // Block time in seconds. None only for proposals made before expiry existed.
+}
+
+impl TmpManagerStore {
+    pub fn is_expired(&self, env: &Env) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= env.block.time.seconds())
+    }
+}
+
+pub const TMP_MANAGER_STORE: Item<TmpManagerStore> = Item::new("tmp_manager_store");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnershipResponse {
+    pub manager: Addr,
+    pub pending_manager: Option<TmpManagerStore>, // None once the pending proposal has expired
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum OwnershipError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Tmp manager store is empty")]
+    TmpManagerStoreEmpty {},
+
+    #[error("Manager proposal has expired")]
+    ManagerProposalExpired {},
+
+    #[error("Manager proposal expiry should be non-zero")]
+    InvalidManagerProposalExpiry {},
+}
+
+// Replaces any pending proposal.
+pub fn propose_manager(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    env: &Env,
+    sender: &Addr,
+    manager: &Addr,
+    new_manager: String,
+    expires_in: Option<u64>,
+) -> Result<Event, OwnershipError> {
+    if sender.ne(manager) {
+        return Err(OwnershipError::Unauthorized {});
+    }
+    let expires_in = expires_in.unwrap_or(DEFAULT_OWNERSHIP_PROPOSAL_EXPIRY);
+    if expires_in == 0 {
+        return Err(OwnershipError::InvalidManagerProposalExpiry {});
+    }
+
+    let new_manager = api.addr_validate(new_manager.as_str())?;
+    let expires_at = env.block.time.plus_seconds(expires_in).seconds();
+    TMP_MANAGER_STORE.save(
+        storage,
+        &TmpManagerStore {
+            manager: new_manager.to_string(),
+            expires_at: Some(expires_at),
+        },
+    )?;
+
+    Ok(lunax_event("propose-manager")
+        .add_attribute("manager", manager)
+        .add_attribute("pending_manager", new_manager)
+        .add_attribute("expires_at", expires_at.to_string()))
+}
+
+// Returns the new manager, which the caller has to store.
+pub fn accept_manager(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    env: &Env,
+    sender: &Addr,
+) -> Result<(Addr, Event), OwnershipError> {
+    let tmp_manager_store = TMP_MANAGER_STORE
+        .may_load(storage)?
+        .ok_or(OwnershipError::TmpManagerStoreEmpty {})?;
+
+    let new_manager = api.addr_validate(tmp_manager_store.manager.as_str())?;
+    if sender.ne(&new_manager) {
+        return Err(OwnershipError::Unauthorized {});
+    }
+    if tmp_manager_store.is_expired(env) {
+        return Err(OwnershipError::ManagerProposalExpired {});
+    }
+    TMP_MANAGER_STORE.remove(storage);
+
+    let event = lunax_event("accept-manager").add_attribute("manager", new_manager.as_str());
+    Ok((new_manager, event))
+}
+
+// Either side of the proposal can cancel it, the pending manager by declining.
+pub fn cancel_manager_proposal(
+    storage: &mut dyn Storage,
+    sender: &Addr,
+    manager: &Addr,
+) -> Result<Event, OwnershipError> {
+    let tmp_manager_store = TMP_MANAGER_STORE
+        .may_load(storage)?
+        .ok_or(OwnershipError::TmpManagerStoreEmpty {})?;
+    if sender.ne(manager) && sender.as_str().ne(tmp_manager_store.manager.as_str()) {
+        return Err(OwnershipError::Unauthorized {});
+    }
+    TMP_MANAGER_STORE.remove(storage);
+
+    Ok(lunax_event("cancel-manager-proposal")
+        .add_attribute("manager", manager)
+        .add_attribute("pending_manager", tmp_manager_store.manager))
+}
+
+// Hands the manager role to the contract itself, which never calls its own privileged messages.
+// Returns the new manager, which the caller has to store. This cannot be undone.
+pub fn renounce_manager(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+    manager: &Addr,
+) -> Result<(Addr, Event), OwnershipError> {
+    if sender.ne(manager) {
+        return Err(OwnershipError::Unauthorized {});
+    }
+    TMP_MANAGER_STORE.remove(storage);
+
+    let new_manager = env.contract.address.clone();
+    let event = lunax_event("renounce-manager").add_attribute("manager", manager);
+    Ok((new_manager, event))
+}
+
+pub fn query_ownership(
+    storage: &dyn Storage,
+    env: &Env,
+    manager: Addr,
+) -> StdResult<OwnershipResponse> {
+    let pending_manager = TMP_MANAGER_STORE
+        .may_load(storage)?
+        .filter(|tmp_manager_store| !tmp_manager_store.is_expired(env));
+    Ok(OwnershipResponse {
+        manager,
+        pending_manager,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ownership::{
+        accept_manager, cancel_manager_proposal, propose_manager, query_ownership,
+        renounce_manager, OwnershipError, TmpManagerStore, DEFAULT_OWNERSHIP_PROPOSAL_EXPIRY,
+        TMP_MANAGER_STORE,
+    };
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, MOCK_CONTRACT_ADDR};
+    use cosmwasm_std::Addr;
+
+    #[test]
+    fn test_propose_and_accept_manager() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let manager = Addr::unchecked("manager");
+        let new_manager = Addr::unchecked("new_manager");
+
+        let err = propose_manager(
+            &mut deps.storage,
+            &deps.api,
+            &env,
+            &new_manager,
+            &manager,
+            "new_manager".to_string(),
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err, OwnershipError::Unauthorized {});
+
+        let err = propose_manager(
+            &mut deps.storage,
+            &deps.api,
+            &env,
+            &manager,
+            &manager,
+            "new_manager".to_string(),
+            Some(0),
+        )
+        .unwrap_err();
+        assert_eq!(err, OwnershipError::InvalidManagerProposalExpiry {});
+
+        propose_manager(
+            &mut deps.storage,
+            &deps.api,
+            &env,
+            &manager,
+            &manager,
+            "new_manager".to_string(),
+            None,
+        )
+        .unwrap();
+        let expires_at = env.block.time.seconds() + DEFAULT_OWNERSHIP_PROPOSAL_EXPIRY;
+        assert_eq!(
+            query_ownership(&deps.storage, &env, manager.clone())
+                .unwrap()
+                .pending_manager,
+            Some(TmpManagerStore {
+                manager: "new_manager".to_string(),
+                expires_at: Some(expires_at),
+            })
+        );
+
+        let err = accept_manager(&mut deps.storage, &deps.api, &env, &manager).unwrap_err();
+        assert_eq!(err, OwnershipError::Unauthorized {});
+
+        let mut expired_env = env.clone();
+        expired_env.block.time = expired_env
+            .block
+            .time
+            .plus_seconds(DEFAULT_OWNERSHIP_PROPOSAL_EXPIRY);
+        let err =
+            accept_manager(&mut deps.storage, &deps.api, &expired_env, &new_manager).unwrap_err();
+        assert_eq!(err, OwnershipError::ManagerProposalExpired {});
+        assert_eq!(
+            query_ownership(&deps.storage, &expired_env, manager.clone())
+                .unwrap()
+                .pending_manager,
+            None
+        );
+
+        let (accepted_manager, _) =
+            accept_manager(&mut deps.storage, &deps.api, &env, &new_manager).unwrap();
+        assert_eq!(accepted_manager, new_manager);
+        assert_eq!(TMP_MANAGER_STORE.may_load(&deps.storage).unwrap(), None);
+
+        let err = accept_manager(&mut deps.storage, &deps.api, &env, &new_manager).unwrap_err();
+        assert_eq!(err, OwnershipError::TmpManagerStoreEmpty {});
+    }
+
+    #[test]
+    fn test_cancel_and_renounce_manager() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let manager = Addr::unchecked("manager");
+        let new_manager = Addr::unchecked("new_manager");
+
+        let err = cancel_manager_proposal(&mut deps.storage, &manager, &manager).unwrap_err();
+        assert_eq!(err, OwnershipError::TmpManagerStoreEmpty {});
+
+        for canceller in [manager.clone(), new_manager.clone()] {
+            propose_manager(
+                &mut deps.storage,
+                &deps.api,
+                &env,
+                &manager,
+                &manager,
+                "new_manager".to_string(),
+                Some(100),
+            )
+            .unwrap();
+            let err =
+                cancel_manager_proposal(&mut deps.storage, &Addr::unchecked("other"), &manager)
+                    .unwrap_err();
+            assert_eq!(err, OwnershipError::Unauthorized {});
+            cancel_manager_proposal(&mut deps.storage, &canceller, &manager).unwrap();
+            assert_eq!(TMP_MANAGER_STORE.may_load(&deps.storage).unwrap(), None);
+        }
+
+        propose_manager(
+            &mut deps.storage,
+            &deps.api,
+            &env,
+            &manager,
+            &manager,
+            "new_manager".to_string(),
+            None,
+        )
+        .unwrap();
+        let err = renounce_manager(&mut deps.storage, &env, &new_manager, &manager).unwrap_err();
+        assert_eq!(err, OwnershipError::Unauthorized {});
+        let (renounced_manager, _) =
+            renounce_manager(&mut deps.storage, &env, &manager, &manager).unwrap();
+        assert_eq!(renounced_manager, Addr::unchecked(MOCK_CONTRACT_ADDR));
+        assert_eq!(TMP_MANAGER_STORE.may_load(&deps.storage).unwrap(), None);
+    }
+}
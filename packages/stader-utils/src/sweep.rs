@@ -0,0 +1,179 @@
+use crate::events::{lunax_event, LunaxEventExt};
+use crate::helpers::send_funds_msg;
+use cosmwasm_std::{
+    to_binary, Addr, Api, Coin, CosmosMsg, Env, Event, Order, StdResult, Storage, Timestamp,
+    Uint128, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+use cw_storage_plus::{Bound, Item, Map};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// Tokens sent to a contract by mistake. Each contract checks that the asset is not reserved
+// before sweeping it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DenomOrCw20 {
+    Denom(String),
+    Cw20(Addr),
+}
+
+impl DenomOrCw20 {
+    pub fn validate(self, api: &dyn Api) -> StdResult<Self> {
+        match self {
+            DenomOrCw20::Denom(denom) => Ok(DenomOrCw20::Denom(denom)),
+            DenomOrCw20::Cw20(contract_addr) => Ok(DenomOrCw20::Cw20(
+                api.addr_validate(contract_addr.as_str())?,
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SweepRecord {
+    pub id: u64,
+    pub asset: DenomOrCw20,
+    pub amount: Uint128,
+    pub recipient: Addr,
+    pub time: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SweepHistoryResponse {
+    pub sweeps: Vec<SweepRecord>,
+}
+
+// (id) -> sweep
+pub const SWEEP_HISTORY: Map<u64, SweepRecord> = Map::new("sweep_history");
+pub const SWEEP_COUNT: Item<u64> = Item::new("sweep_count");
+
+// Records the sweep and returns the transfer to the recipient. The asset should already be
+// validated and checked against the contract's reserved funds.
+pub fn sweep(
+    storage: &mut dyn Storage,
+    env: &Env,
+    asset: DenomOrCw20,
+    amount: Uint128,
+    recipient: Addr,
+) -> StdResult<(CosmosMsg, Event)> {
+    let msg: CosmosMsg = match &asset {
+        DenomOrCw20::Denom(denom) => {
+            send_funds_msg(&recipient, &[Coin::new(amount.u128(), denom.as_str())]).into()
+        }
+        DenomOrCw20::Cw20(contract_addr) => WasmMsg::Execute {
+            contract_addr: contract_addr.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }
+        .into(),
+    };
+
+    let id = SWEEP_COUNT
+        .may_load(storage)?
+        .unwrap_or_default()
+        .checked_add(1)
+        .unwrap();
+    let asset_attribute = match &asset {
+        DenomOrCw20::Denom(denom) => denom.clone(),
+        DenomOrCw20::Cw20(contract_addr) => contract_addr.to_string(),
+    };
+    SWEEP_HISTORY.save(
+        storage,
+        id,
+        &SweepRecord {
+            id,
+            asset,
+            amount,
+            recipient: recipient.clone(),
+            time: env.block.time,
+        },
+    )?;
+    SWEEP_COUNT.save(storage, &id)?;
+
+    let event = lunax_event("sweep")
+        .add_attribute("id", id.to_string())
+        .add_attribute("asset", asset_attribute)
+        .add_amount("amount", amount)
+        .add_attribute("recipient", recipient);
+    Ok((msg, event))
+}
+
+pub fn query_sweep_history(
+    storage: &dyn Storage,
+    start_after: Option<u64>,
+    limit: Option<u64>,
+) -> StdResult<SweepHistoryResponse> {
+    let limit = limit.unwrap_or(10).min(30) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let sweeps = SWEEP_HISTORY
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, sweep)| sweep))
+        .collect::<StdResult<Vec<SweepRecord>>>()?;
+    Ok(SweepHistoryResponse { sweeps })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sweep::{query_sweep_history, sweep, DenomOrCw20};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::{to_binary, Addr, BankMsg, Coin, CosmosMsg, Uint128, WasmMsg};
+    use cw20::Cw20ExecuteMsg;
+
+    #[test]
+    fn test_sweep() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let recipient = Addr::unchecked("user");
+
+        let (msg, event) = sweep(
+            &mut deps.storage,
+            &env,
+            DenomOrCw20::Denom("uusd".to_string()),
+            Uint128::new(100),
+            recipient.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "user".to_string(),
+                amount: vec![Coin::new(100, "uusd")],
+            })
+        );
+        assert_eq!(event.ty, "lunax-sweep");
+
+        let (msg, _) = sweep(
+            &mut deps.storage,
+            &env,
+            DenomOrCw20::Cw20(Addr::unchecked("token")),
+            Uint128::new(50),
+            recipient,
+        )
+        .unwrap();
+        assert_eq!(
+            msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "token".to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "user".to_string(),
+                    amount: Uint128::new(50),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+
+        let res = query_sweep_history(&deps.storage, Some(1), None).unwrap();
+        assert_eq!(res.sweeps.len(), 1);
+        assert_eq!(res.sweeps[0].id, 2);
+        assert_eq!(
+            res.sweeps[0].asset,
+            DenomOrCw20::Cw20(Addr::unchecked("token"))
+        );
+    }
+}